@@ -15,10 +15,11 @@ use crate::ui_components::elem_ranges::NutrientRatios;
 use crate::ui_components::intro::Intro;
 use crate::ui_components::known_fertilizers::KnownFertilizers;
 use crate::ui_components::mixture_solution::MixtureSolution;
+use crate::ui_components::objective_selector::ObjectiveSelector;
 use crate::ui_components::status_bar::StatusBar;
 use crate::ui_components::total_mass::TotalMassInput;
 
-use crate::calculator::query::MixtureQuery;
+use crate::calculator::query::{MixtureObjective, MixtureQuery};
 
 #[derive(Routable, Clone, PartialEq)]
 enum Route {
@@ -43,13 +44,15 @@ pub fn start_page() -> Html {
 
     let is_valid = (*state).is_valid();
 
-    let query = state.to_mixture_query();
+    let query = Rc::new(state.to_mixture_query());
     let solution = query.find_solution();
     let deficites = Rc::new(query.find_deficites());
 
     let show_solution = use_state(|| solution.is_ok());
 
     let on_calculate = {
+        let query = query.clone();
+        let navigator = navigator.clone();
         let show_solution = show_solution.clone();
         let added_fertilizers = state.added_fertilizers.clone();
         Callback::from(move |_| {
@@ -84,32 +87,117 @@ pub fn start_page() -> Html {
         state.dispatcher(),
     );
 
+    let on_profile_change = use_callback(
+        |value, dispatcher| dispatcher.dispatch(StoreAction::UpdateProfile(value)),
+        state.dispatcher(),
+    );
+
+    let on_crop_change = use_callback(
+        |value, dispatcher| dispatcher.dispatch(StoreAction::UpdateCrop(value)),
+        state.dispatcher(),
+    );
+
+    let on_target_change = use_callback(
+        |value, dispatcher| dispatcher.dispatch(StoreAction::UpdateTarget(value)),
+        state.dispatcher(),
+    );
+
+    let on_secondary_ratio_change = use_callback(
+        |(element, range), dispatcher| {
+            dispatcher.dispatch(StoreAction::UpdateSecondaryRatio(element, range))
+        },
+        state.dispatcher(),
+    );
+
     let on_mass_changed = use_callback(
         |value, dispatcher| dispatcher.dispatch(StoreAction::UpdateMass(value)),
         state.dispatcher(),
     );
 
+    let on_existing_changed = use_callback(
+        |value, dispatcher| dispatcher.dispatch(StoreAction::UpdateExisting(value)),
+        state.dispatcher(),
+    );
+
+    // Новая масса на странице результатов: сразу пересчитываем смесь и обновляем ссылку
+    let on_solution_mass_changed = {
+        let query = query.clone();
+        let dispatcher = state.dispatcher();
+        Callback::from(move |mass: f64| {
+            dispatcher.dispatch(StoreAction::UpdateMass(mass));
+            if let Some(ref navigator) = navigator {
+                let query = MixtureQuery { mass, ..(*query).clone() };
+                navigator
+                    .replace_with_query(&Route::Home, &query.to_url_query())
+                    .ok();
+            }
+        })
+    };
+
+    let on_objective_changed = use_callback(
+        |value, dispatcher| dispatcher.dispatch(StoreAction::UpdateObjective(value)),
+        state.dispatcher(),
+    );
+
+    let on_max_ingredients_changed = use_callback(
+        |value, dispatcher| dispatcher.dispatch(StoreAction::UpdateMaxIngredients(value)),
+        state.dispatcher(),
+    );
+
+    let on_nitrogen_form_limit_changed = use_callback(
+        |value, dispatcher| dispatcher.dispatch(StoreAction::UpdateNitrogenFormLimit(value)),
+        state.dispatcher(),
+    );
+
+    let show_prices = state.objective == MixtureObjective::Cost;
+
     html! {
         <>
             if !*show_solution {
-                <Intro />
+                <Intro profile={state.profile} />
                 <KnownFertilizers
                     store_dispatcher={state.dispatcher()}
                     fertilizers_status={state.permanent_fertilizers.clone()}
-                    deficites={deficites.clone()} />
+                    deficites={deficites.clone()}
+                    {show_prices} />
                 <AddedFertilizers
                     fertilizers={state.added_fertilizers.clone()}
-                    on_change={on_added_changed} />
-                    <NutrientRatios {on_ratio_change} ratios={state.ratios.clone()} />
-                <TotalMassInput value={state.mass} on_change={on_mass_changed} />
+                    on_change={on_added_changed}
+                    {show_prices} />
+                    <NutrientRatios
+                        profile={state.profile}
+                        {on_profile_change}
+                        crop={state.crop}
+                        {on_crop_change}
+                        {on_ratio_change}
+                        ratios={state.ratios.clone()}
+                        target={state.target}
+                        {on_target_change}
+                        {on_secondary_ratio_change} />
+                <TotalMassInput
+                    value={state.mass}
+                    on_change={on_mass_changed}
+                    existing={state.existing}
+                    on_existing_change={on_existing_changed} />
+                <ObjectiveSelector
+                    value={state.objective}
+                    on_change={on_objective_changed}
+                    max_ingredients={state.max_ingredients}
+                    on_max_ingredients_change={on_max_ingredients_changed}
+                    nitrogen_form_limit={state.nitrogen_form_limit}
+                    on_nitrogen_form_limit_change={on_nitrogen_form_limit_changed} />
                 <StatusBar
                     {deficites}
                     on_show_solution={on_calculate}
                     state_is_valid={is_valid} />
             } else if let Ok(solution) = solution {
-                <MixtureSolution {solution} on_calc_another={&on_calc_another} />
+                <MixtureSolution
+                    {solution}
+                    {query}
+                    on_calc_another={&on_calc_another}
+                    on_mass_change={on_solution_mass_changed} />
             } else {
-                <DeficiteDescription {deficites} on_calc_another={&on_calc_another} />
+                <DeficiteDescription {deficites} {query} on_calc_another={&on_calc_another} />
             }
         </>
     }
@@ -0,0 +1,146 @@
+//! Альтернативные рецепты. Если несколько удобрений взаимозаменяемы (например, разные
+//! суперфосфаты), симплекс-метод возвращает одну произвольную вершину из равноценных.
+//! Здесь перебираются вершины, получаемые запретом использовать удобрения из уже найденных
+//! рецептов, пока целевая функция остаётся в пределах допуска от оптимальной.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::calculator::cardinality::{
+    solve_excluding, solve_with_max_ingredients_excluding, used_fertilizers,
+};
+use crate::calculator::consts::{MAX_ALTERNATIVES, MAX_ALTERNATIVE_NODES};
+use crate::calculator::formatted_solution::FormattedSolution;
+use crate::calculator::query::MixtureQuery;
+
+/// Один из равноценных или почти равноценных рецептов
+#[derive(Debug, PartialEq)]
+pub(crate) struct AlternativeSolution {
+    /// На сколько (в долях) оптимизируемая величина больше, чем в лучшем рецепте
+    pub worsening: f64,
+    pub solution: FormattedSolution,
+}
+
+/// Находит различные (по набору удобрений) рецепты, значение целевой функции которых
+/// превышает оптимальное не более чем в `1 + tolerance` раз. Первым всегда идёт тот же
+/// рецепт, что возвращает `MixtureQuery::find_solution`, остальные упорядочены
+/// по возрастанию целевой функции.
+pub(crate) fn find_alternative_solutions(
+    query: &MixtureQuery,
+    tolerance: f64,
+) -> Vec<AlternativeSolution> {
+    let solve = |excluded: &[usize]| match query.max_ingredients {
+        Some(max_count) => solve_with_max_ingredients_excluding(query, max_count, excluded),
+        None => solve_excluding(query, excluded),
+    };
+
+    let mut found = Vec::<(f64, Vec<usize>, FormattedSolution)>::new();
+    let mut best_value = None::<f64>;
+    let mut visited = HashSet::<Vec<usize>>::new();
+    let mut nodes = VecDeque::from([Vec::<usize>::new()]);
+    let mut nodes_solved = 0;
+    while let Some(excluded) = nodes.pop_front() {
+        if nodes_solved == MAX_ALTERNATIVE_NODES || found.len() == MAX_ALTERNATIVES {
+            break;
+        }
+        nodes_solved += 1;
+        let Ok((solution, labels)) = solve(&excluded) else {
+            continue;
+        };
+        // Запрет удобрений не может улучшить смесь, так что ветка отсекается целиком
+        let best_value = *best_value.get_or_insert(solution.function_value);
+        if solution.function_value > best_value + tolerance * best_value.abs() {
+            continue;
+        }
+        let used = used_fertilizers(query, &solution);
+        for fert_idx in used.iter() {
+            let mut child = excluded.clone();
+            child.push(*fert_idx);
+            child.sort_unstable();
+            if visited.insert(child.clone()) {
+                nodes.push_back(child);
+            }
+        }
+        if found.iter().all(|(_, found_used, _)| *found_used != used) {
+            let formatted = FormattedSolution::new(query, &solution, &labels);
+            found.push((solution.function_value, used, formatted));
+        }
+    }
+
+    let Some(best_value) = found.first().map(|(value, _, _)| *value) else {
+        return Vec::new();
+    };
+    found[1..].sort_by(|(value1, _, _), (value2, _, _)| value1.total_cmp(value2));
+    found
+        .into_iter()
+        .map(|(value, _, solution)| AlternativeSolution {
+            worsening: (value / best_value - 1.0).max(0.0),
+            solution,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::find_alternative_solutions;
+    use crate::calculator::consts::PERMANENT_FERTILIZERS;
+    use crate::calculator::formatted_solution::FormattedSolution;
+    use crate::calculator::query::MixtureQuery;
+
+    fn query(fert_ids: &[usize]) -> MixtureQuery {
+        MixtureQuery::test_default(
+            fert_ids
+                .iter()
+                .map(|id| PERMANENT_FERTILIZERS[*id].clone())
+                .collect(),
+        )
+    }
+
+    fn used_names(solution: &FormattedSolution) -> Vec<String> {
+        solution
+            .components
+            .iter()
+            .filter(|(_, weight)| *weight > 0.0)
+            .map(|(fertilizer, _)| fertilizer.name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_interchangeable_fertilizers() {
+        let mut query = query(&[0, 8, 10, 14]);
+        // Тот же суперфосфат другого производителя: рецепты с ним ровно так же хороши
+        let mut twin = query.fertilizers[1].clone();
+        twin.name = Cow::Borrowed("Суперфосфат двойной (другой)");
+        query.fertilizers.push(twin);
+
+        let alternatives = find_alternative_solutions(&query, 1e-9);
+        assert!(alternatives.len() >= 2);
+        assert_eq!(alternatives[0].solution, query.find_solution().unwrap());
+        let mut recipes: Vec<Vec<String>> = alternatives
+            .iter()
+            .map(|alternative| used_names(&alternative.solution))
+            .collect();
+        for alternative in alternatives.iter() {
+            assert!(alternative.worsening < 1e-6);
+            let total: f64 = alternative.solution.components.iter().map(|(_, w)| w).sum();
+            assert!((total - query.mass).abs() < 1e-9);
+        }
+        recipes.dedup();
+        assert_eq!(recipes.len(), alternatives.len());
+    }
+
+    #[test]
+    fn test_near_optimal_are_sorted() {
+        let query = query(&(0..PERMANENT_FERTILIZERS.len()).collect::<Vec<_>>());
+        let alternatives = find_alternative_solutions(&query, 0.2);
+        assert!(!alternatives.is_empty());
+        assert_eq!(alternatives[0].worsening, 0.0);
+        for pair in alternatives[1..].windows(2) {
+            assert!(pair[0].worsening <= pair[1].worsening);
+        }
+        for alternative in alternatives.iter() {
+            assert!(alternative.worsening <= 0.2 + 1e-9);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Смеси из ограниченного числа удобрений: многие предпочтут купить три мешка, а не семь,
+//! даже если смесь получится немного хуже. Ограничение на число удобрений не выражается
+//! линейно, поэтому решается перебором подмножеств методом ветвей и границ поверх `LPTask`.
+
+use crate::calculator::consts::{MAX_BRANCH_NODES, MIN_INGREDIENTS, MIN_SIGNIFICANT_CHANGE};
+use crate::calculator::formatted_solution::FormattedSolution;
+use crate::calculator::modified_simplex::{LPSolution, SimplexError};
+use crate::calculator::query::{MixtureQuery, TaskRow};
+
+/// Ветка не исследуется, если может улучшить уже найденную смесь меньше, чем на эту долю
+const RELATIVE_OBJECTIVE_TOLERANCE: f64 = 1e-9;
+
+/// Решает задачу, запрещая использовать удобрения с указанными индексами.
+/// Запреты задаются границами переменных, так что система ограничений
+/// (и её двойственные оценки) остаётся прежней.
+pub(crate) fn solve_excluding(
+    query: &MixtureQuery,
+    excluded: &[usize],
+) -> Result<(LPSolution, Vec<TaskRow>), SimplexError> {
+    let (mut task, labels) = query.build_labeled_task(&[]);
+    for fert_idx in excluded {
+        task.set_bounds(*fert_idx, 0.0, 0.0);
+    }
+    Ok((task.solve_min()?, labels))
+}
+
+/// Индексы удобрений, которые действительно входят в смесь (не менее грамма)
+pub(crate) fn used_fertilizers(query: &MixtureQuery, solution: &LPSolution) -> Vec<usize> {
+    let scale_factor = query.scale_factor(solution);
+    (0..query.fertilizers.len())
+        .filter(|idx| solution.params[*idx] * scale_factor > MIN_SIGNIFICANT_CHANGE)
+        .collect()
+}
+
+/// Индексы удобрений, которые входят в решение хоть в каком-то количестве. Небазисные
+/// переменные решатель держит точно на границах, так что у не вошедшего в смесь
+/// удобрения вес ровно нулевой.
+pub(crate) fn support(query: &MixtureQuery, solution: &LPSolution) -> Vec<usize> {
+    (0..query.fertilizers.len())
+        .filter(|idx| solution.params[*idx] > 0.0)
+        .collect()
+}
+
+/// Узел дерева поиска: удобрения, запрещённые в этой ветке, и удобрения, которые в ней
+/// разрешены наверняка (их число не может превышать ограничения)
+struct Node {
+    excluded: Vec<usize>,
+    included: Vec<usize>,
+}
+
+/// Находит лучшую смесь, в которой не больше `max_count` удобрений. Если за
+/// `consts::MAX_BRANCH_NODES` шагов оптимальность доказать не удалось, возвращается
+/// лучшая из найденных смесей.
+pub(crate) fn solve_with_max_ingredients(
+    query: &MixtureQuery,
+    max_count: usize,
+) -> Result<(LPSolution, Vec<TaskRow>), SimplexError> {
+    solve_with_max_ingredients_excluding(query, max_count, &[])
+}
+
+/// То же, что `solve_with_max_ingredients`, но удобрения с индексами из `excluded`
+/// не используются совсем
+pub(crate) fn solve_with_max_ingredients_excluding(
+    query: &MixtureQuery,
+    max_count: usize,
+    excluded: &[usize],
+) -> Result<(LPSolution, Vec<TaskRow>), SimplexError> {
+    solve_with_max_ingredients_within(query, max_count, excluded, MAX_BRANCH_NODES)
+}
+
+/// То же, что `solve_with_max_ingredients_excluding`, но решается не больше `max_nodes` задач
+fn solve_with_max_ingredients_within(
+    query: &MixtureQuery,
+    max_count: usize,
+    excluded: &[usize],
+    max_nodes: usize,
+) -> Result<(LPSolution, Vec<TaskRow>), SimplexError> {
+    let num_ferts = query.fertilizers.len();
+    let mut best: Option<(LPSolution, Vec<TaskRow>)> = None;
+    let mut nodes = vec![Node { excluded: excluded.to_vec(), included: Vec::new() }];
+    let mut nodes_solved = 0;
+    // Как и при округлении рецепта, бюджет проверяется до того, как узел снят со стека
+    while nodes_solved < max_nodes {
+        let Some(mut node) = nodes.pop() else {
+            break;
+        };
+        nodes_solved += 1;
+        if node.included.len() == max_count {
+            // Больше ничего добавить нельзя, так что все остальные удобрения запрещены
+            node.excluded = (0..num_ferts)
+                .filter(|idx| !node.included.contains(idx))
+                .collect();
+        }
+        let Ok((solution, labels)) = solve_excluding(query, &node.excluded) else {
+            continue;
+        };
+        // Без ограничения на число удобрений смесь не хуже, так что это оценка снизу
+        if let Some((best_solution, _)) = &best {
+            let best_value = best_solution.function_value;
+            let tolerance = RELATIVE_OBJECTIVE_TOLERANCE * best_value.abs();
+            if solution.function_value >= best_value - tolerance {
+                continue;
+            }
+        }
+        // Считаются все удобрения с ненулевым весом, даже меньше грамма: иначе
+        // в найденной смеси их могло бы оказаться больше, чем разрешено
+        let used = support(query, &solution);
+        if used.len() <= max_count {
+            best = Some((solution, labels));
+            continue;
+        }
+        // Ветвление по удобрению с наименьшим весом: сначала пробуем обойтись без него
+        let branch_idx = used
+            .iter()
+            .filter(|idx| !node.included.contains(idx))
+            .min_by(|idx1, idx2| solution.params[**idx1].total_cmp(&solution.params[**idx2]))
+            .copied()
+            .expect("Not all used fertilizers can be included, there are too many of them");
+        let mut included = node.included.clone();
+        included.push(branch_idx);
+        nodes.push(Node { excluded: node.excluded.clone(), included });
+        let mut excluded = node.excluded;
+        excluded.push(branch_idx);
+        nodes.push(Node { excluded, included: node.included });
+    }
+
+    best.ok_or(if nodes.is_empty() {
+        SimplexError::NotFeasible
+    } else {
+        SimplexError::TooManySteps
+    })
+}
+
+/// Для каждого числа удобрений от `consts::MIN_INGREDIENTS` до `max_count` находит лучшую смесь из не более чем
+/// такого числа удобрений. Смеси, которые не лучше смеси из меньшего числа удобрений,
+/// пропускаются, так что число удобрений в результатах строго растёт.
+pub(crate) fn find_simplest_solutions(
+    query: &MixtureQuery,
+    max_count: usize,
+) -> Vec<(usize, FormattedSolution)> {
+    let mut result = Vec::<(usize, FormattedSolution)>::new();
+    let mut previous_value: Option<f64> = None;
+    for count in MIN_INGREDIENTS..=max_count {
+        let Ok((solution, labels)) = solve_with_max_ingredients(query, count) else {
+            continue;
+        };
+        let is_better = previous_value.is_none_or(|previous_value| {
+            let tolerance = RELATIVE_OBJECTIVE_TOLERANCE * previous_value.abs();
+            solution.function_value < previous_value - tolerance
+        });
+        if is_better {
+            previous_value = Some(solution.function_value);
+            let used_count = support(query, &solution).len();
+            result.push((
+                used_count,
+                FormattedSolution::new(query, &solution, &labels),
+            ));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        find_simplest_solutions, solve_with_max_ingredients, solve_with_max_ingredients_within,
+        support,
+    };
+    use crate::calculator::consts::PERMANENT_FERTILIZERS;
+    use crate::calculator::modified_simplex::SimplexError;
+    use crate::calculator::query::MixtureQuery;
+
+    fn all_permanent_query() -> MixtureQuery {
+        MixtureQuery::test_default(PERMANENT_FERTILIZERS.to_vec())
+    }
+
+    /// Полный перебор всех подмножеств из не более чем `max_count` удобрений
+    fn brute_force_best(query: &MixtureQuery, max_count: usize) -> Option<f64> {
+        let num_ferts = query.fertilizers.len();
+        (0u32..1 << num_ferts)
+            .filter(|mask| mask.count_ones() as usize <= max_count)
+            .filter_map(|mask| {
+                let subset = MixtureQuery {
+                    fertilizers: (0..num_ferts)
+                        .filter(|idx| mask & (1 << idx) != 0)
+                        .map(|idx| query.fertilizers[idx].clone())
+                        .collect(),
+                    ..query.clone()
+                };
+                subset.build_task(&[]).solve_min().ok()
+            })
+            .map(|solution| solution.function_value)
+            .min_by(f64::total_cmp)
+    }
+
+    #[test]
+    fn test_max_ingredients_is_optimal() {
+        let mut query = all_permanent_query();
+        query.fertilizers.truncate(12);
+        for max_count in 3..=5 {
+            let expected = brute_force_best(&query, max_count);
+            let found = solve_with_max_ingredients(&query, max_count).ok();
+            if let (Some(expected), Some((solution, _))) = (expected, &found) {
+                assert!(support(&query, solution).len() <= max_count);
+                assert!((solution.function_value - expected).abs() < 1e-6 * expected);
+            } else {
+                assert!(expected.is_none() && found.is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn test_simplest_solutions() {
+        let query = all_permanent_query();
+        let solutions = find_simplest_solutions(&query, 6);
+        assert!(!solutions.is_empty());
+        for pair in solutions.windows(2) {
+            assert!(pair[0].0 < pair[1].0);
+        }
+        for (count, solution) in solutions {
+            let used = solution
+                .components
+                .iter()
+                .filter(|(_, weight)| *weight > 0.0)
+                .count();
+            assert_eq!(used, count);
+            let total: f64 = solution.components.iter().map(|(_, weight)| weight).sum();
+            assert!((total - query.mass).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_node_budget() {
+        let query = all_permanent_query();
+        // Без ветвления из всех удобрений не выбрать три, а исчерпанный бюджет -
+        // не несовместность
+        for max_nodes in [0, 1] {
+            let result = solve_with_max_ingredients_within(&query, 3, &[], max_nodes);
+            assert_eq!(result.err(), Some(SimplexError::TooManySteps));
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::calculator::formatted_solution::MicroFertInfo;
 use std::borrow::Cow;
 
-use super::{ElemRange, Fertilizer};
+use super::nitrogen::NitrogenForms;
+use super::{Amounts, ElemName, Fertilizer};
 
 /// Содержание азота (кг) в стандартной смеси Т.Ю. Угаровой (необходимо для рассчёта микроудобрений)
 pub const STD_N_QUANTITY: f64 = 1.1;
@@ -79,12 +80,6 @@ pub(crate) const STD_N_K_CONCENTRATION: f64 = 14.5;
 /// и приводит к выдаче предупреждения о большой доле балластных элементов
 pub(crate) const CRITICAL_LOW_N_K_CONCENTRATION: f64 = 12.;
 
-/// Дозы внесения на разных видах почвы (min,max) смеси стандартной концентрации N|K
-pub(crate) const DOZES: &[(&str, ElemRange)] = &[
-    ("Супеси и песчаные почвы", ElemRange { from: 20., to: 25. }),
-    ("Остальные почвы", ElemRange { from: 25., to: 40. }),
-];
-
 /// Предельная доза внесения
 pub(crate) const MAX_DOZE: f64 = 50.;
 
@@ -92,6 +87,31 @@ pub(crate) const MAX_DOZE: f64 = 50.;
 pub(crate) const MIT_N_PER_METER: f64 = 6.60659;
 pub(crate) const MIT_MAX_DOZE: f64 = 50.;
 
+/// Число первых подкормок после посадки, пока растения приживаются и смесь вносится
+/// в минимальной дозе
+pub(crate) const ESTABLISHMENT_FEEDINGS: usize = 2;
+
+/// Число еженедельных подкормок за сезон, предлагаемое по умолчанию
+pub(crate) const DEFAULT_FEEDINGS: usize = 8;
+
+/// Длина гряд (погонных метров), предлагаемая по умолчанию для плана подкормок
+pub(crate) const DEFAULT_BED_LENGTH: f64 = 10.;
+
+/// Час дня подкормки, в который календарь напоминает о ней
+pub(crate) const FEEDING_REMINDER_HOUR: u32 = 9;
+
+/// Шаг, на который предлагается расширить границу соотношения элементов,
+/// при оценке влияния этой границы на массу смеси
+pub(crate) const RATIO_RELAXATION_STEP: f64 = 0.1;
+
+/// Шаг (в процентах азота), на который предлагается поднять предельную долю формы азота
+/// при оценке влияния этого предела на массу смеси
+pub(crate) const NITROGEN_SHARE_RELAXATION_STEP: f64 = 1.0;
+
+/// Изменения массы смеси меньше этого значения (кг), а её стоимости - меньше этой суммы,
+/// не стоят внимания пользователя
+pub(crate) const MIN_SIGNIFICANT_CHANGE: f64 = 1e-3;
+
 const fn max_fertilizer_id(fertilizers: &[Fertilizer], start_from: usize) -> Option<usize> {
     if start_from < fertilizers.len() {
         let current_id = fertilizers[start_from].id;
@@ -111,183 +131,179 @@ const fn max_fertilizer_id(fertilizers: &[Fertilizer], start_from: usize) -> Opt
 }
 
 /// Перманентные удобрения, доступные пользователю сразу, без необходимости их добавлять,
-/// но и без возможности их удалять.
+/// но и без возможности их удалять. Содержание кальция, серы и хлора (и форм азота в сложных
+/// удобрениях) - типичное для удобрений отечественного производства, у конкретного
+/// производителя оно может отличаться.
 pub(crate) const PERMANENT_FERTILIZERS: &[Fertilizer] = &[
     // ВАЖНО: При внесении любых изменений, убедись, что все ID - уникальны
     Fertilizer {
         name: Cow::Borrowed("Аммиачная селитра"),
-        N: 34.0,
-        P: 0.0,
-        K: 0.0,
-        Mg: 0.0,
-        with_Cl: false,
-        with_S: false,
+        content: Amounts::with(&[(ElemName::Nitrogen, 34.0)]),
+        nitrogen_forms: Some(NitrogenForms { ammonium: 17.0, nitrate: 17.0, urea: 0.0 }),
         limit: None,
+        minimum: None,
+        price: None,
         id: 0,
     },
     Fertilizer {
         name: Cow::Borrowed("Карбамид (мочевина)"),
-        N: 46.2,
-        P: 0.0,
-        K: 0.0,
-        Mg: 0.0,
-        with_Cl: false,
-        with_S: false,
+        content: Amounts::with(&[(ElemName::Nitrogen, 46.2)]),
+        nitrogen_forms: Some(NitrogenForms { ammonium: 0.0, nitrate: 0.0, urea: 46.2 }),
         limit: None,
+        minimum: None,
+        price: None,
         id: 1,
     },
     Fertilizer {
         name: Cow::Borrowed("Азофоска"),
-        N: 16.0,
-        P: 16.0,
-        K: 16.0,
-        Mg: 0.0,
-        with_Cl: true,
-        with_S: false,
+        content: Amounts::with(&[
+            (ElemName::Nitrogen, 16.0),
+            (ElemName::Phosphorus, 16.0),
+            (ElemName::Potassium, 16.0),
+            (ElemName::Chlorine, 8.0),
+        ]),
+        nitrogen_forms: Some(NitrogenForms { ammonium: 10.0, nitrate: 6.0, urea: 0.0 }),
         limit: None,
+        minimum: None,
+        price: None,
         id: 2,
     },
     Fertilizer {
         name: Cow::Borrowed("Диаммоний фосфат"),
-        N: 19.0,
-        P: 49.0,
-        K: 0.0,
-        Mg: 0.0,
-        with_Cl: false,
-        with_S: false,
+        content: Amounts::with(&[(ElemName::Nitrogen, 19.0), (ElemName::Phosphorus, 49.0)]),
+        nitrogen_forms: Some(NitrogenForms { ammonium: 19.0, nitrate: 0.0, urea: 0.0 }),
         limit: None,
+        minimum: None,
+        price: None,
         id: 3,
     },
     Fertilizer {
         name: Cow::Borrowed("Аммофос"),
-        N: 12.0,
-        P: 52.0,
-        K: 0.0,
-        Mg: 0.0,
-        with_Cl: false,
-        with_S: false,
+        content: Amounts::with(&[(ElemName::Nitrogen, 12.0), (ElemName::Phosphorus, 52.0)]),
+        nitrogen_forms: Some(NitrogenForms { ammonium: 12.0, nitrate: 0.0, urea: 0.0 }),
         limit: None,
+        minimum: None,
+        price: None,
         id: 4,
     },
     Fertilizer {
         name: Cow::Borrowed("Суперфорсфат простой"),
-        N: 0.0,
-        P: 19.0,
-        K: 0.0,
-        Mg: 0.0,
-        with_Cl: false,
-        with_S: true,
+        content: Amounts::with(&[
+            (ElemName::Phosphorus, 19.0),
+            (ElemName::Calcium, 26.0),
+            (ElemName::Sulfur, 10.0),
+        ]),
+        nitrogen_forms: None,
         limit: None,
+        minimum: None,
+        price: None,
         id: 5,
     },
     Fertilizer {
         name: Cow::Borrowed("Суперфосфат гранулированный"),
-        N: 0.0,
-        P: 26.0,
-        K: 0.0,
-        Mg: 0.0,
-        with_Cl: false,
-        with_S: true,
+        content: Amounts::with(&[
+            (ElemName::Phosphorus, 26.0),
+            (ElemName::Calcium, 20.0),
+            (ElemName::Sulfur, 9.0),
+        ]),
+        nitrogen_forms: None,
         limit: None,
+        minimum: None,
+        price: None,
         id: 6,
     },
     Fertilizer {
         name: Cow::Borrowed("Суперфосфат гранулированный (с азотом)"),
-        N: 6.0,
-        P: 26.0,
-        K: 0.0,
-        Mg: 0.0,
-        with_Cl: false,
-        with_S: true,
+        content: Amounts::with(&[
+            (ElemName::Nitrogen, 6.0),
+            (ElemName::Phosphorus, 26.0),
+            (ElemName::Calcium, 18.0),
+            (ElemName::Sulfur, 8.0),
+        ]),
+        nitrogen_forms: Some(NitrogenForms { ammonium: 6.0, nitrate: 0.0, urea: 0.0 }),
         limit: None,
+        minimum: None,
+        price: None,
         id: 7,
     },
     Fertilizer {
         name: Cow::Borrowed("Суперфосфат двойной"),
-        N: 0.0,
-        P: 43.0,
-        K: 0.0,
-        Mg: 0.0,
-        with_Cl: false,
-        with_S: true,
+        content: Amounts::with(&[
+            (ElemName::Phosphorus, 43.0),
+            (ElemName::Calcium, 14.0),
+            (ElemName::Sulfur, 5.0),
+        ]),
+        nitrogen_forms: None,
         limit: None,
+        minimum: None,
+        price: None,
         id: 8,
     },
     Fertilizer {
         name: Cow::Borrowed("Монофосфат калия"),
-        N: 0.0,
-        P: 52.0,
-        K: 34.0,
-        Mg: 0.0,
-        with_Cl: false,
-        with_S: false,
+        content: Amounts::with(&[(ElemName::Phosphorus, 52.0), (ElemName::Potassium, 34.0)]),
+        nitrogen_forms: None,
         limit: None,
+        minimum: None,
+        price: None,
         id: 9,
     },
     Fertilizer {
         name: Cow::Borrowed("Сульфат калия"),
-        N: 0.0,
-        P: 0.0,
-        K: 50.0,
-        Mg: 0.0,
-        with_Cl: false,
-        with_S: true,
+        content: Amounts::with(&[(ElemName::Potassium, 50.0), (ElemName::Sulfur, 18.0)]),
+        nitrogen_forms: None,
         limit: None,
+        minimum: None,
+        price: None,
         id: 10,
     },
     Fertilizer {
         name: Cow::Borrowed("Хлорид калия"),
-        N: 0.0,
-        P: 0.0,
-        K: 60.0,
-        Mg: 0.0,
-        with_Cl: true,
-        with_S: false,
+        content: Amounts::with(&[(ElemName::Potassium, 60.0), (ElemName::Chlorine, 47.0)]),
+        nitrogen_forms: None,
         limit: None,
+        minimum: None,
+        price: None,
         id: 11,
     },
     Fertilizer {
         name: Cow::Borrowed("Калийная селитра"),
-        N: 13.0,
-        P: 0.0,
-        K: 46.0,
-        Mg: 0.0,
-        with_Cl: false,
-        with_S: false,
+        content: Amounts::with(&[(ElemName::Nitrogen, 13.0), (ElemName::Potassium, 46.0)]),
+        nitrogen_forms: Some(NitrogenForms { ammonium: 0.0, nitrate: 13.0, urea: 0.0 }),
         limit: None,
+        minimum: None,
+        price: None,
         id: 12,
     },
     Fertilizer {
         name: Cow::Borrowed("Калимаг"),
-        N: 0.0,
-        P: 0.0,
-        K: 35.0,
-        Mg: 8.0,
-        with_Cl: true,
-        with_S: false,
+        content: Amounts::with(&[
+            (ElemName::Potassium, 35.0),
+            (ElemName::Magnesium, 8.0),
+            (ElemName::Chlorine, 23.0),
+        ]),
+        nitrogen_forms: None,
         limit: None,
+        minimum: None,
+        price: None,
         id: 13,
     },
     Fertilizer {
         name: Cow::Borrowed("Сульфат магния (магний сернокислый)"),
-        N: 0.0,
-        P: 0.0,
-        K: 0.0,
-        Mg: 16.0,
-        with_Cl: false,
-        with_S: true,
+        content: Amounts::with(&[(ElemName::Magnesium, 16.0), (ElemName::Sulfur, 13.0)]),
+        nitrogen_forms: None,
         limit: None,
+        minimum: None,
+        price: None,
         id: 14,
     },
     Fertilizer {
         name: Cow::Borrowed("Магниевая селитра"),
-        N: 11.1,
-        P: 0.0,
-        K: 0.0,
-        Mg: 15.5,
-        with_Cl: false,
-        with_S: false,
+        content: Amounts::with(&[(ElemName::Nitrogen, 11.1), (ElemName::Magnesium, 15.5)]),
+        nitrogen_forms: Some(NitrogenForms { ammonium: 0.0, nitrate: 11.1, urea: 0.0 }),
         limit: None,
+        minimum: None,
+        price: None,
         id: 15,
     },
 ];
@@ -298,3 +314,61 @@ pub(crate) const FIRST_EXTRA_FERT_ID: usize = match max_fertilizer_id(PERMANENT_
     Some(id) => id + 1,
     None => 0,
 };
+
+/// Точность (в процентах или в единицах соотношения), с которой подбирается наименьшее
+/// ослабление ограничения, при котором несовместная смесь составляется
+pub(crate) const RELAXATION_PRECISION: f64 = 0.01;
+
+/// Дальше этого отклонения от исходного значения ослабление ограничения не ищется:
+/// если и оно не помогает, дело не в этом ограничении
+pub(crate) const RELAXATION_MAX_DEVIATION: f64 = 100.0;
+
+/// Шаги (в килограммах), до которых пользователь может округлить веса удобрений в смеси,
+/// с их названиями для интерфейса
+pub(crate) const ROUNDING_STEPS: &[(f64, &str)] = &[
+    (0.001, "1 г"),
+    (0.01, "10 г"),
+    (0.1, "100 г"),
+    (1.0, "1 кг"),
+];
+
+/// Предельное число задач, решаемых методом ветвей и границ при округлении рецепта
+pub(crate) const MAX_BRANCH_NODES: usize = 5000;
+
+/// Меньше, чем из двух удобрений, смесь с заданными соотношениями составить практически нельзя
+pub(crate) const MIN_INGREDIENTS: usize = 2;
+
+/// Насколько (в долях) альтернативный рецепт может быть хуже лучшего, чтобы его предложить
+pub(crate) const ALTERNATIVE_TOLERANCE: f64 = 0.05;
+
+/// Сколько альтернативных рецептов (вместе с основным) показывать
+pub(crate) const MAX_ALTERNATIVES: usize = 10;
+
+/// Сколько задач решать при поиске альтернативных рецептов
+pub(crate) const MAX_ALTERNATIVE_NODES: usize = 200;
+
+/// Во сколько раз относительное отклонение соотношения от целевого (в режиме идеального
+/// соотношения) дороже килограмма самого "дорогого" для цели оптимизации удобрения
+/// на единицу фосфора. Велико, чтобы близость к цели была важнее всего остального.
+pub(crate) const TARGET_DEVIATION_PRIORITY: f64 = 1e4;
+
+/// Целевые соотношения меньше этого при взвешивании отклонений считаются равными ему,
+/// чтобы нулевая цель не давала бесконечного веса
+pub(crate) const MIN_TARGET_RATIO: f64 = 0.01;
+
+/// Насколько (в единицах соотношения) соотношение округлённого рецепта в режиме идеального
+/// соотношения может выйти за пределы между целевым и точным соотношениями
+pub(crate) const TARGET_ROUNDING_TOLERANCE: f64 = 0.02;
+
+/// Концентрация серы в смеси (%), ниже которой предлагается добавить удобрения с серой
+pub(crate) const MIN_S_CONCENTRATION: f64 = 1.0;
+
+/// Концентрация хлора в смеси (%), ниже которой предлагается добавить удобрения с хлором
+pub(crate) const MIN_CL_CONCENTRATION: f64 = 0.3;
+
+/// Концентрация хлора в смеси (%), выше которой смесь опасна для чувствительных к хлору культур
+pub(crate) const MAX_CL_CONCENTRATION: f64 = 6.0;
+
+/// Доля аммонийного азота во всём азоте смеси (%), выше которой смесь не рекомендуется
+/// для полива рассады
+pub(crate) const MAX_SEEDLING_AMMONIUM_SHARE: f64 = 50.0;
@@ -0,0 +1,112 @@
+//! Культуры. Томатам и картофелю нужно больше калия, чем азота, капусте - наоборот,
+//! поэтому соотношения смеси №2 и дозы её внесения на грядах зависят от того,
+//! что на этих грядах растёт.
+
+use super::{ElemRange, ElemRatios};
+
+/// Культура, для которой подбирается смесь
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Crop {
+    /// Соотношения и дозы Т.Ю. Угаровой для большинства овощей. Старые ссылки считают их.
+    #[default]
+    Universal,
+    Tomato,
+    Cucumber,
+    Cabbage,
+    Potato,
+}
+
+impl Crop {
+    pub const ALL: [Crop; 5] = [
+        Crop::Universal,
+        Crop::Tomato,
+        Crop::Cucumber,
+        Crop::Cabbage,
+        Crop::Potato,
+    ];
+
+    /// Название культуры для переключателя, со строчной буквы
+    pub fn title(self) -> &'static str {
+        match self {
+            Crop::Universal => "большинство овощей",
+            Crop::Tomato => "томаты, перцы, баклажаны",
+            Crop::Cucumber => "огурцы, кабачки",
+            Crop::Cabbage => "капуста",
+            Crop::Potato => "картофель",
+        }
+    }
+
+    pub fn urlencode(self) -> &'static str {
+        match self {
+            Crop::Universal => "any",
+            Crop::Tomato => "tomato",
+            Crop::Cucumber => "cucumber",
+            Crop::Cabbage => "cabbage",
+            Crop::Potato => "potato",
+        }
+    }
+
+    pub fn from_url(text: &str) -> Option<Self> {
+        Crop::ALL.into_iter().find(|crop| crop.urlencode() == text)
+    }
+
+    /// Соотношения макроэлементов к фосфору для культуры (вторичные элементы не задаются)
+    pub fn ratios(self) -> ElemRatios {
+        let (n_to_p, k_to_p, mg_to_p) = match self {
+            Crop::Universal => return ElemRatios::default(),
+            Crop::Tomato => ((1.2, 1.5), (2.2, 2.6), (0.3, 0.5)),
+            Crop::Cucumber => ((1.5, 1.8), (2.0, 2.4), (0.3, 0.5)),
+            Crop::Cabbage => ((2.0, 2.4), (1.8, 2.2), (0.25, 0.45)),
+            Crop::Potato => ((1.3, 1.6), (2.4, 2.8), (0.3, 0.5)),
+        };
+        let range = |(from, to)| ElemRange { from, to };
+        ElemRatios {
+            n_to_p: range(n_to_p),
+            k_to_p: range(k_to_p),
+            mg_to_p: range(mg_to_p),
+            secondary: Default::default(),
+        }
+    }
+
+    /// Дозы внесения (г на погонный метр гряды) на разных видах почвы (min,max)
+    /// смеси стандартной концентрации N|K (`consts::STD_N_K_CONCENTRATION`)
+    pub fn dozes(self) -> &'static [(&'static str, ElemRange)] {
+        match self {
+            Crop::Universal | Crop::Cucumber => &[
+                ("Супеси и песчаные почвы", ElemRange { from: 20., to: 25. }),
+                ("Остальные почвы", ElemRange { from: 25., to: 40. }),
+            ],
+            Crop::Tomato | Crop::Potato => &[
+                ("Супеси и песчаные почвы", ElemRange { from: 25., to: 30. }),
+                ("Остальные почвы", ElemRange { from: 30., to: 45. }),
+            ],
+            Crop::Cabbage => &[
+                ("Супеси и песчаные почвы", ElemRange { from: 25., to: 35. }),
+                ("Остальные почвы", ElemRange { from: 35., to: 50. }),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Crop;
+    use crate::calculator::ElemRatios;
+
+    #[test]
+    fn test_crop_url() {
+        for crop in Crop::ALL {
+            assert_eq!(Crop::from_url(crop.urlencode()), Some(crop));
+        }
+        assert_eq!(Crop::from_url("onion"), None);
+    }
+
+    #[test]
+    fn test_crop_ratios() {
+        assert_eq!(Crop::Universal.ratios(), ElemRatios::default());
+        for crop in Crop::ALL {
+            assert!(crop.ratios().all_valid());
+            assert!(crop.dozes().iter().all(|(_, doze)| doze.is_valid()));
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use crate::calculator::consts::STD_N_QUANTITY;
+use crate::calculator::crops::Crop;
 use crate::calculator::modified_simplex::LPSolution;
-use crate::calculator::query::MixtureQuery;
-use crate::calculator::{consts, Amounts, ElemRange, ElemRangeName, Fertilizer};
+use crate::calculator::nitrogen::{NitrogenForm, NitrogenForms};
+use crate::calculator::profiles::{MicroFertTable, MixtureProfile};
+use crate::calculator::query::{ExistingMixture, MixtureQuery, TaskConstraint, TaskRow};
+use crate::calculator::{consts, Amounts, ElemName, ElemRange, ElemRangeName, Fertilizer};
 
 /// Вычисляет дозы микроудобрений на основании реальных доз макро-элементов
 /// в вычисленной смеси и предварительно подсчитанных по книгам Угаровой
@@ -17,7 +20,7 @@ fn calc_microferts(
         .iter()
         .map(|(name, slice)| (*name, Vec::with_capacity(slice.len())))
         .collect();
-    let micro_ratio = nutrient_quantities.N / STD_N_QUANTITY;
+    let micro_ratio = nutrient_quantities[ElemName::Nitrogen] / STD_N_QUANTITY;
     for ((_elem, ferts), (_, output_quant)) in basis.iter().zip(microferts.iter_mut()) {
         for (micro_fert, micro_quant) in ferts.iter() {
             output_quant.push((micro_fert, *micro_quant * micro_ratio));
@@ -32,68 +35,189 @@ pub(crate) struct SolutionRemarks {
     pub text: String,
 }
 
-fn check_mixture_bonus_element<F>(
-    components: &[(Fertilizer, f64)],
-    perm_fertilizers: &[Fertilizer],
-    message_prefix: &str,
-    bonus_checker: F,
-) -> Option<SolutionRemarks>
-where
-    F: Fn(&Fertilizer) -> bool,
-{
-    let weight: f64 = components.iter().map(|(_, weight)| *weight).sum();
-    let has_enough_element = components.iter().any(|(fertilizer, comp_weight)| {
-        let portion = *comp_weight / weight;
-        // Отбрасываем компоненты, содержание которых в смеси - менее 10%
-        let is_significant = portion > 0.1;
-        is_significant && bonus_checker(fertilizer)
-    });
-    if has_enough_element {
-        None
-    } else {
-        let mut text = String::from(message_prefix);
-        let mut added_ferts = 0;
-        for pf in perm_fertilizers {
-            if bonus_checker(pf) {
-                if added_ferts != 0 {
-                    text.push_str(", ");
-                }
-                text.push_str(pf.name.as_ref());
-                added_ferts += 1;
-            }
-        }
-        Some(SolutionRemarks { text, class: "info" })
-    }
+/// Перечисляет через запятую перманентные удобрения, содержащие элемент
+fn permanent_fertilizers_with(element: ElemName) -> String {
+    consts::PERMANENT_FERTILIZERS
+        .iter()
+        .filter(|fertilizer| fertilizer[element] > 0.0)
+        .map(|fertilizer| fertilizer.name.as_ref())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
-/// Проверяет, присутствуют ли в среди компонентов смеси дополнительные соединения,
-/// вроде Ca, S, Cl и т.п., и, если их нет, вносит в remarks своё замечание.
-/// Используется в `format_solution`.
-fn check_admixtures(components: &[(Fertilizer, f64)]) -> Vec<SolutionRemarks> {
+/// Проверяет концентрацию в смеси серы и хлора, и, если её мало (или хлора слишком много),
+/// вносит в remarks своё замечание. Используется в `format_solution`.
+fn check_admixtures(concentration: &Amounts) -> Vec<SolutionRemarks> {
     let mut result = Vec::<SolutionRemarks>::with_capacity(2);
-    if let Some(s_remark) = check_mixture_bonus_element(
-        components,
-        consts::PERMANENT_FERTILIZERS,
-        "В смеси нет или очень мало <strong>серы</strong> (S). \
-         Если смесь предназначена для ящиков-гряд или для выращивания \
-         рассады&nbsp;&mdash;&nbsp;рекомендуем использовать удобрения, \
-         содержащие серу: ",
-        |f| f.with_S,
-    ) {
-        result.push(s_remark);
+    if concentration[ElemName::Sulfur] < consts::MIN_S_CONCENTRATION {
+        result.push(SolutionRemarks {
+            text: format!(
+                "В смеси мало <strong>серы</strong> (S): {:.2}%. \
+                 Если смесь предназначена для ящиков-гряд или для выращивания \
+                 рассады&nbsp;&mdash;&nbsp;рекомендуем использовать удобрения, \
+                 содержащие серу: {}",
+                concentration[ElemName::Sulfur],
+                permanent_fertilizers_with(ElemName::Sulfur)
+            ),
+            class: "info",
+        });
+    }
+    if concentration[ElemName::Chlorine] < consts::MIN_CL_CONCENTRATION {
+        result.push(SolutionRemarks {
+            text: format!(
+                "В смеси мало <strong>хлора</strong> (Cl): {:.2}%. \
+                 Обычно в почве его содержится достаточно, но если смесь делается для ящиков-гряд \
+                 или для выращивания рассады на бедном опилочном грунте \
+                 &nbsp;&mdash;&nbsp;рекомендуем использовать хотя бы немного удобрений, \
+                 содержащих хлор: {}",
+                concentration[ElemName::Chlorine],
+                permanent_fertilizers_with(ElemName::Chlorine)
+            ),
+            class: "info",
+        });
+    } else if concentration[ElemName::Chlorine] > consts::MAX_CL_CONCENTRATION {
+        result.push(SolutionRemarks {
+            text: format!(
+                "В смеси много <strong>хлора</strong> (Cl): {:.2}%. \
+                 Для чувствительных к хлору культур (картофель, томаты, огурцы, ягодные) \
+                 лучше заменить часть хлорсодержащих удобрений бесхлорными.",
+                concentration[ElemName::Chlorine]
+            ),
+            class: "warning",
+        });
     }
-    if let Some(cl_remark) = check_mixture_bonus_element(
-        components,
-        consts::PERMANENT_FERTILIZERS,
-        "Похоже, что в смеси нет или очень мало <strong>хлора</strong> (Cl).
-         Обычно в почве его содержится достаточно, но если смесь делается для ящиков-гряд
-         или для выращивания рассады на бедном опилочном грунте \
-         &nbsp;&mdash;&nbsp;рекомендуем использовать хотя бы немного удобрений, \
-         содержащих хлор: ",
-        |f| f.with_Cl,
-    ) {
-        result.push(cl_remark);
+    result
+}
+
+/// Предупреждает, если в азоте смеси преобладает аммонийный: для взрослых растений
+/// это не страшно, а рассаду избыток аммония угнетает. Поэтому проверяются только смеси,
+/// которыми может поливаться рассада (см. `MixtureProfile::has_seedling_dozes`).
+fn check_nitrogen_forms(
+    concentration: &Amounts,
+    nitrogen_forms: &NitrogenForms,
+) -> Option<SolutionRemarks> {
+    let ammonium_share =
+        100.0 * nitrogen_forms[NitrogenForm::Ammonium] / concentration[ElemName::Nitrogen];
+    (ammonium_share > consts::MAX_SEEDLING_AMMONIUM_SHARE).then(|| SolutionRemarks {
+        text: format!(
+            "В азоте смеси преобладает <strong>аммонийный</strong>: {:.0}%. \
+             Если смесь предназначена для полива рассады&nbsp;&mdash;&nbsp;рекомендуем \
+             заменить часть удобрений с аммонийным азотом нитратными: {}",
+            ammonium_share,
+            nitrate_fertilizers()
+        ),
+        class: "warning",
+    })
+}
+
+/// Перечисляет через запятую перманентные удобрения, азот которых - только нитратный
+fn nitrate_fertilizers() -> String {
+    consts::PERMANENT_FERTILIZERS
+        .iter()
+        .filter(|fertilizer| {
+            fertilizer
+                .nitrogen_forms
+                .is_some_and(|forms| forms.nitrate > 0.0 && forms.nitrate == forms.total())
+        })
+        .map(|fertilizer| fertilizer.name.as_ref())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Ограничение, мешающее улучшить смесь, и выгода от его ослабления
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct BindingConstraint {
+    pub constraint: TaskConstraint,
+    /// Текущее значение ограничения: граница соотношения, остаток удобрения (кг),
+    /// минимум удобрения (кг или % смеси) или предельная доля формы азота (%)
+    pub value: f64,
+    /// Насколько уменьшится оптимизируемая величина (масса, стоимость или балласт) для смеси
+    /// с тем же количеством питательных веществ, если ослабить ограничение
+    /// на `consts::RATIO_RELAXATION_STEP` (или на 1 кг остатка, или на 1 кг или 1% минимума,
+    /// или на `consts::NITROGEN_SHARE_RELAXATION_STEP` доли формы азота)
+    pub saving: f64,
+}
+
+/// Находит связывающие ограничения по двойственным оценкам решения.
+/// Значение функции вычисляется на единицу фосфора, так что для перевода её изменения
+/// в изменение для всей смеси достаточно умножить его на `scale_factor`.
+fn find_binding_constraints(
+    query: &MixtureQuery,
+    solution: &LPSolution,
+    rows: &[TaskRow],
+) -> Vec<BindingConstraint> {
+    let mut result = Vec::new();
+    for (row, dual) in rows.iter().zip(solution.duals.iter()) {
+        let constraint = row.binding_constraint(*dual);
+        let (value, function_change) = match constraint {
+            // Расширение нижней границы - это уменьшение правой части неравенства ">="
+            TaskConstraint::RatioFrom(range_name) => (
+                query.ratio(range_name).from,
+                -dual * consts::RATIO_RELAXATION_STEP,
+            ),
+            TaskConstraint::RatioTo(range_name) => (
+                query.ratio(range_name).to,
+                dual * consts::RATIO_RELAXATION_STEP,
+            ),
+            TaskConstraint::SecondaryFrom(element) => (
+                query.secondary_ratio(element).from,
+                -dual * consts::RATIO_RELAXATION_STEP,
+            ),
+            TaskConstraint::SecondaryTo(element) => (
+                query.secondary_ratio(element).to,
+                dual * consts::RATIO_RELAXATION_STEP,
+            ),
+            // Строка остатка содержит коэффициенты R = limit/mass при всех удобрениях
+            // (и R - 1 при ограниченном), производная функции по R равна -dual * sum(x),
+            // а sum(x) / mass - это как раз 1 / scale_factor.
+            TaskConstraint::Limit(fert_idx) => {
+                let Some(limit) = query.fertilizers[fert_idx].limit else {
+                    continue;
+                };
+                (limit, -dual / query.scale_factor(solution))
+            }
+            // В строке минимума стоит -r при всех удобрениях, так что уменьшение r
+            // ослабляет её так же, как увеличение R ослабляет строку остатка
+            TaskConstraint::Minimum(fert_idx) => {
+                let Some(minimum) = query.fertilizers[fert_idx].minimum else {
+                    continue;
+                };
+                let rate_step = minimum.with_value(1.0).rate(query.mass);
+                let sum_x = query.mass / query.scale_factor(solution);
+                (minimum.value(), -dual * rate_step * sum_x)
+            }
+            // Увеличение предельной доли $s$ уменьшает коэффициенты строки на $N_i / 100$
+            // на единицу $s$, то есть ослабляет её так же, как рост правой части
+            // на количество азота известных форм (на единицу фосфора)
+            TaskConstraint::NitrogenShare(_) => {
+                let Some(limit) = query.nitrogen_form_limit else {
+                    continue;
+                };
+                let known_nitrogen: f64 = query
+                    .fertilizers
+                    .iter()
+                    .zip(solution.params.iter())
+                    .filter_map(|(fertilizer, x)| {
+                        fertilizer
+                            .nitrogen_forms
+                            .map(|forms| forms.total() * x / 100.0)
+                    })
+                    .sum();
+                let share_step = consts::NITROGEN_SHARE_RELAXATION_STEP / 100.0;
+                (limit.max_share, dual * share_step * known_nitrogen)
+            }
+            // Масса партии задана пользователем и улучшением смеси не является,
+            // а за идеальными соотношениями следят переменные отклонения
+            TaskConstraint::Existing | TaskConstraint::Phosphorus | TaskConstraint::Target(_) => {
+                continue
+            }
+        };
+        let saving = -function_change * query.scale_factor(solution);
+        if saving > consts::MIN_SIGNIFICANT_CHANGE {
+            result.push(BindingConstraint { constraint, value, saving });
+        }
     }
+    result.sort_by(|c1, c2| c2.saving.total_cmp(&c1.saving));
     result
 }
 
@@ -102,54 +226,94 @@ fn check_admixtures(components: &[(Fertilizer, f64)]) -> Vec<SolutionRemarks> {
 /// состава. Используется для отображения результатов.
 #[derive(Debug, PartialEq)]
 pub(crate) struct FormattedSolution {
+    /// Удобрения и их веса (кг). Если часть смеси уже приготовлена - то, что к ней нужно добавить
     pub components: Vec<(Fertilizer, f64)>,
+    /// Уже приготовленная часть смеси, входящая в партию
+    pub existing: Option<ExistingMixture>,
+    /// Для каждого компонента: на сколько вырастет оптимизируемая величина (масса, стоимость
+    /// или балласт) смеси с тем же количеством питательных веществ, если принудительно добавить
+    /// в неё 1 кг удобрения. Равно приведённой стоимости переменной, и для использованных
+    /// удобрений - нулевое.
+    pub forced_penalty: Vec<f64>,
+    /// Концентрация элементов во всей партии, вместе с уже приготовленной частью
     pub concentration: Amounts,
+    /// Концентрация азота каждой формы во всей партии. Азот удобрений, формы которого
+    /// неизвестны, сюда не входит, так что в сумме может получиться меньше всего азота.
+    pub nitrogen_forms: NitrogenForms,
     pub relation: [(ElemRangeName, f64); 3],
-    pub microferts: Vec<(&'static str, Vec<(&'static str, f64)>)>,
-    pub microferts_2a: Vec<(&'static str, Vec<(&'static str, f64)>)>,
-    pub microferts_mit: Vec<(&'static str, Vec<(&'static str, f64)>)>,
+    /// Отклонения соотношений от идеальных (соотношение минус идеал), если они заданы
+    pub target_deviation: Option<[(ElemRangeName, f64); 3]>,
+    /// Назначение смеси, по которому выбраны таблицы микроудобрений и доз
+    pub profile: MixtureProfile,
+    /// Культура, для которой рассчитаны дозы внесения на грядах
+    pub crop: Crop,
+    /// Дозы микроудобрений по каждой из таблиц профиля
+    pub microferts: Vec<(MicroFertTable, Vec<DynMicroFertInfo>)>,
+    /// Дозы внесения на грядах; пусто, если смесь для грядок не предназначена
     pub ground_dozes: Vec<(&'static str, ElemRange)>,
     pub mit_ground_doze: f64,
     pub remarks: Vec<SolutionRemarks>,
+    /// Дозы для полива рассады; пусто, если смесь не для рассады
     pub seedling_dozes: Vec<(f64, f64, f64)>,
     pub total_weight: f64,
+    /// Стоимость удобрений для смеси, если цена известна хотя бы для одного из них
+    /// (удобрения без цены считаются бесплатными)
+    pub total_price: Option<f64>,
+    /// Ограничения, от которых зависит концентрация смеси, в порядке убывания их влияния
+    pub bindings: Vec<BindingConstraint>,
 }
 
 impl FormattedSolution {
     // Оформляет результаты вычислений solve_task в пригодный для показа вид, заодно проверяя
     // их на соответствие условиям задачи"
     #[allow(non_snake_case)]
-    pub fn new(query: &MixtureQuery, solution: &LPSolution) -> Self {
-        let scale_factor = query.mass / solution.function_value;
+    pub fn new(query: &MixtureQuery, solution: &LPSolution, rows: &[TaskRow]) -> Self {
+        let scale_factor = query.scale_factor(solution);
         let mut components = Vec::<(Fertilizer, f64)>::with_capacity(query.fertilizers.len());
         let mut concentration = Amounts::default();
         let mut quantity = Amounts::default();
+        let mut nitrogen_forms = NitrogenForms::default();
 
         // quantity = {'N':0,'P':0,'K':0,'Mg':0}
         let mut remarks = Vec::<SolutionRemarks>::new();
         // Подсчёт концентрации и количества каждого элемента в смеси
+        let existing_fertilizer = query
+            .existing
+            .map(|existing| (existing.as_fertilizer(), existing.mass));
+        let mut add_to_mixture = |fert: &Fertilizer, weight: f64| {
+            for element in ElemName::ALL {
+                concentration[element] += fert[element] * weight / query.mass;
+                quantity[element] += weight * fert[element] / 100.0;
+            }
+            if let Some(forms) = fert.nitrogen_forms {
+                for form in NitrogenForm::ALL {
+                    nitrogen_forms[form] += forms[form] * weight / query.mass;
+                }
+            }
+        };
         for (i, fert) in query.fertilizers.iter().enumerate() {
             // let name,composition = fert
             let weight = scale_factor * solution.params[i];
-            let calc_concentration = |elem_percentage| elem_percentage * weight / query.mass;
-            concentration.N += calc_concentration(fert.N);
-            concentration.P += calc_concentration(fert.P);
-            concentration.K += calc_concentration(fert.K);
-            concentration.Mg += calc_concentration(fert.Mg);
-            quantity.N += weight * fert.N / 100.0;
-            quantity.P += weight * fert.P / 100.0;
-            quantity.K += weight * fert.K / 100.0;
-            quantity.Mg += weight * fert.Mg / 100.0;
+            add_to_mixture(fert, weight);
             components.push((fert.clone(), weight));
         }
+        if let Some((fert, weight)) = &existing_fertilizer {
+            add_to_mixture(fert, *weight);
+        }
         // Дозы микроудобрений
-        let microferts = calc_microferts(&consts::MICROFERTS, &quantity);
-        let microferts_2a = calc_microferts(&consts::MICROFERTS_2A, &quantity);
-        let microferts_mit = calc_microferts(&consts::MICROFERTS_MIT, &quantity);
+        let microferts = query
+            .profile
+            .microfert_tables()
+            .iter()
+            .map(|table| (*table, calc_microferts(table.basis(), &quantity)))
+            .collect();
         // Рассчёт дозы внесения на разных почвах (исходя из концентрации)
-        let avg_N_K_concentration = (concentration.N + concentration.K) / 2.0;
+        let avg_N_K_concentration =
+            (concentration[ElemName::Nitrogen] + concentration[ElemName::Potassium]) / 2.0;
         let doze_factor = consts::STD_N_K_CONCENTRATION / avg_N_K_concentration;
-        if concentration.N.min(concentration.K) < consts::CRITICAL_LOW_N_K_CONCENTRATION {
+        if concentration[ElemName::Nitrogen].min(concentration[ElemName::Potassium])
+            < consts::CRITICAL_LOW_N_K_CONCENTRATION
+        {
             remarks.push(SolutionRemarks {
                 class: "critical",
                 text: String::from(
@@ -159,9 +323,17 @@ impl FormattedSolution {
                 ),
             });
         }
-        remarks.extend(check_admixtures(&components));
+        remarks.extend(check_admixtures(&concentration));
+        if query.profile.has_seedling_dozes() {
+            remarks.extend(check_nitrogen_forms(&concentration, &nitrogen_forms));
+        }
         let mut ground_dozes = Vec::new();
-        for (ground_type, min_max) in consts::DOZES {
+        let ground_types = if query.profile.has_ground_dozes() {
+            query.crop.dozes()
+        } else {
+            &[]
+        };
+        for (ground_type, min_max) in ground_types {
             let (min_doze, max_doze) = (min_max.from * doze_factor, min_max.to * doze_factor);
             ground_dozes.push((
                 *ground_type,
@@ -171,12 +343,18 @@ impl FormattedSolution {
                 },
             ));
         }
-        let mit_ground_doze =
-            (100.0 * consts::MIT_N_PER_METER / concentration.N).min(consts::MIT_MAX_DOZE);
+        let mit_ground_doze = (100.0 * consts::MIT_N_PER_METER / concentration[ElemName::Nitrogen])
+            .min(consts::MIT_MAX_DOZE);
         // Рассчёт дозы для удобрительного полива рассады
-        let doze_per_liter = consts::N_PER_LITER_2A / (concentration.N / 100.0);
-        let doze_per_liter_mit = consts::N_PER_LITER_MIT / (concentration.N / 100.0);
-        let seedling_dozes: Vec<_> = [3_f64, 5_f64, 10_f64]
+        let doze_per_liter = consts::N_PER_LITER_2A / (concentration[ElemName::Nitrogen] / 100.0);
+        let doze_per_liter_mit =
+            consts::N_PER_LITER_MIT / (concentration[ElemName::Nitrogen] / 100.0);
+        let seedling_volumes: &[f64] = if query.profile.has_seedling_dozes() {
+            &[3.0, 5.0, 10.0]
+        } else {
+            &[]
+        };
+        let seedling_dozes: Vec<_> = seedling_volumes
             .iter()
             .map(|volume| {
                 (
@@ -186,24 +364,41 @@ impl FormattedSolution {
                 )
             })
             .collect();
+        let total_weight = components.iter().map(|(_, weight)| weight).sum::<f64>()
+            + existing_fertilizer.map_or(0.0, |(_, weight)| weight);
+        let total_price = components
+            .iter()
+            .filter(|(_, weight)| *weight > 0.0)
+            .filter_map(|(fertilizer, weight)| fertilizer.price.map(|price| price * weight))
+            .reduce(|total, price| total + price);
         // Соотношения элементов
-        let relation = [
-            (ElemRangeName::Nitrogen, concentration.N / concentration.P),
-            (ElemRangeName::Potassium, concentration.K / concentration.P),
-            (ElemRangeName::Magnesium, concentration.Mg / concentration.P),
-        ];
+        let relation = ElemRangeName::ALL.map(|range_name| {
+            (
+                range_name,
+                concentration[range_name.element()] / concentration[ElemName::Phosphorus],
+            )
+        });
+        let target_deviation = query.target.map(|target| {
+            relation.map(|(range_name, value)| (range_name, value - target[range_name]))
+        });
         FormattedSolution {
             components,
+            existing: query.existing,
+            forced_penalty: solution.reduced_costs[..query.fertilizers.len()].to_vec(),
             concentration,
+            nitrogen_forms,
             relation,
+            target_deviation,
+            profile: query.profile,
+            crop: query.crop,
             microferts,
-            microferts_2a,
-            microferts_mit,
             ground_dozes,
             mit_ground_doze,
             remarks,
             seedling_dozes,
-            total_weight: solution.function_value * scale_factor,
+            total_weight,
+            total_price,
+            bindings: find_binding_constraints(query, solution, rows),
         }
     }
 }
@@ -0,0 +1,313 @@
+//! Объяснение, почему смесь не составляется. Псевдо-удобрения из `find_deficites` показывают,
+//! какого элемента не хватает, но не говорят, какие именно условия друг другу противоречат.
+//! Здесь находится неприводимое противоречивое подмножество ограничений (IIS): убери из него
+//! любое ограничение - и оставшиеся станут совместны. Для каждого ограничения из этого
+//! подмножества ищется наименьшее ослабление, при котором смесь составляется.
+
+use crate::calculator::consts::{RELAXATION_MAX_DEVIATION, RELAXATION_PRECISION};
+use crate::calculator::modified_simplex::SimplexError;
+use crate::calculator::query::{MixtureQuery, TaskConstraint};
+use crate::calculator::ElemRange;
+
+/// Как изменить одно ограничение (оставив прочие условия прежними), чтобы смесь составилась
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Relaxation {
+    /// Текущее значение: граница соотношения, остаток удобрения (кг), минимум удобрения
+    /// (в тех единицах, в которых он задан), предельная доля формы азота (%)
+    /// или масса всей партии с уже смешанным (кг)
+    pub value: f64,
+    /// Ближайшее к текущему значение, при котором смесь уже составляется
+    pub relaxed: f64,
+}
+
+/// Ограничение из неприводимого противоречивого подмножества
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ConflictingConstraint {
+    pub constraint: TaskConstraint,
+    /// `None`, если одного этого ограничения ослабить недостаточно
+    pub relaxation: Option<Relaxation>,
+}
+
+/// Совместна ли система, в которой из всех условий запроса оставлены только условия `kept`.
+/// Если симплекс-метод не справился, система на всякий случай считается совместной,
+/// чтобы не выбросить из объяснения ограничение, которое может быть важным.
+fn is_feasible(query: &MixtureQuery, kept: &[TaskConstraint]) -> bool {
+    // Без нормировки на единицу фосфора система однородна, и ей удовлетворяет пустая смесь
+    if !kept.contains(&TaskConstraint::Phosphorus) {
+        return true;
+    }
+    let mut relaxed = query.clone();
+    for constraint in query.constraints() {
+        if kept.contains(&constraint) {
+            continue;
+        }
+        match constraint {
+            TaskConstraint::Limit(fert_idx) => relaxed.fertilizers[fert_idx].limit = None,
+            TaskConstraint::Minimum(fert_idx) => relaxed.fertilizers[fert_idx].minimum = None,
+            TaskConstraint::RatioFrom(range_name) => relaxed.ratio_mut(range_name).from = 0.0,
+            TaskConstraint::RatioTo(range_name) => relaxed.ratio_mut(range_name).to = f64::INFINITY,
+            // Необязательное соотношение проще всего ослабить, убрав его целиком
+            TaskConstraint::SecondaryFrom(element) | TaskConstraint::SecondaryTo(element) => {
+                relaxed.secondary_ratios.set(element, None)
+            }
+            TaskConstraint::NitrogenShare(_) => relaxed.nitrogen_form_limit = None,
+            TaskConstraint::Existing => relaxed.existing = None,
+            TaskConstraint::Phosphorus | TaskConstraint::Target(_) => {}
+        }
+    }
+    !matches!(
+        relaxed.build_task(&[]).solve_min(),
+        Err(SimplexError::NotFeasible)
+    )
+}
+
+/// Текущее значение ограничения и значение, при котором оно заведомо ничего не ограничивает
+/// (или ограничивает настолько слабо, насколько вообще имеет смысл предлагать)
+fn relaxation_range(query: &MixtureQuery, constraint: TaskConstraint) -> Option<(f64, f64)> {
+    match constraint {
+        TaskConstraint::Limit(fert_idx) => {
+            let limit = query.fertilizers[fert_idx].limit?;
+            Some((limit, query.mass))
+        }
+        TaskConstraint::Minimum(fert_idx) => {
+            let minimum = query.fertilizers[fert_idx].minimum?;
+            Some((minimum.value(), 0.0))
+        }
+        TaskConstraint::RatioFrom(range_name) => Some((query.ratio(range_name).from, 0.0)),
+        TaskConstraint::SecondaryFrom(element) => Some((query.secondary_ratio(element).from, 0.0)),
+        TaskConstraint::SecondaryTo(element) => {
+            let to = query.secondary_ratio(element).to;
+            Some((to, to + RELAXATION_MAX_DEVIATION))
+        }
+        TaskConstraint::RatioTo(range_name) => {
+            let to = query.ratio(range_name).to;
+            Some((to, to + RELAXATION_MAX_DEVIATION))
+        }
+        TaskConstraint::NitrogenShare(_) => {
+            let limit = query.nitrogen_form_limit?;
+            Some((limit.max_share, 100.0))
+        }
+        // Чем больше вся партия, тем меньше в ней доля уже смешанного
+        TaskConstraint::Existing => Some((query.mass, query.mass * RELAXATION_MAX_DEVIATION)),
+        TaskConstraint::Phosphorus | TaskConstraint::Target(_) => None,
+    }
+}
+
+fn set_constraint_value(query: &mut MixtureQuery, constraint: TaskConstraint, value: f64) {
+    match constraint {
+        TaskConstraint::Limit(fert_idx) => query.fertilizers[fert_idx].limit = Some(value),
+        TaskConstraint::Minimum(fert_idx) => {
+            let fertilizer = &mut query.fertilizers[fert_idx];
+            fertilizer.minimum = fertilizer.minimum.map(|minimum| minimum.with_value(value));
+        }
+        TaskConstraint::RatioFrom(range_name) => query.ratio_mut(range_name).from = value,
+        TaskConstraint::RatioTo(range_name) => query.ratio_mut(range_name).to = value,
+        TaskConstraint::SecondaryFrom(element) => {
+            let range = query.secondary_ratio(element);
+            query
+                .secondary_ratios
+                .set(element, Some(ElemRange { from: value, ..range }));
+        }
+        TaskConstraint::SecondaryTo(element) => {
+            let range = query.secondary_ratio(element);
+            query
+                .secondary_ratios
+                .set(element, Some(ElemRange { to: value, ..range }));
+        }
+        TaskConstraint::NitrogenShare(_) => {
+            if let Some(limit) = &mut query.nitrogen_form_limit {
+                limit.max_share = value;
+            }
+        }
+        TaskConstraint::Existing => query.mass = value,
+        TaskConstraint::Phosphorus | TaskConstraint::Target(_) => {}
+    }
+}
+
+/// Делением отрезка пополам ищет наименьшее ослабление ограничения, при котором
+/// смесь составляется, если остальные условия не менять
+fn find_relaxation(query: &MixtureQuery, constraint: TaskConstraint) -> Option<Relaxation> {
+    let (value, far) = relaxation_range(query, constraint)?;
+    let mut probe = query.clone();
+    let mut feasible = |value: f64| {
+        set_constraint_value(&mut probe, constraint, value);
+        probe.build_task(&[]).solve_min().is_ok()
+    };
+    if !feasible(far) {
+        return None;
+    }
+    let (mut bad, mut good) = (value, far);
+    while (good - bad).abs() > RELAXATION_PRECISION {
+        let middle = 0.5 * (good + bad);
+        if feasible(middle) {
+            good = middle;
+        } else {
+            bad = middle;
+        }
+    }
+    Some(Relaxation { value, relaxed: good })
+}
+
+/// Находит неприводимое противоречивое подмножество ограничений задачи методом
+/// последовательного удаления: ограничение выбрасывается, если и без него система
+/// несовместна. Если смесь составляется, возвращает пустой список.
+/// Ограничение на число удобрений в смеси здесь не учитывается.
+pub(crate) fn find_conflicting_constraints(query: &MixtureQuery) -> Vec<ConflictingConstraint> {
+    let mut kept = query.constraints();
+    if is_feasible(query, &kept) {
+        return Vec::new();
+    }
+    for constraint in kept.clone() {
+        let without: Vec<TaskConstraint> =
+            kept.iter().copied().filter(|c| *c != constraint).collect();
+        if !is_feasible(query, &without) {
+            kept = without;
+        }
+    }
+    kept.into_iter()
+        .map(|constraint| ConflictingConstraint {
+            constraint,
+            relaxation: find_relaxation(query, constraint),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::find_conflicting_constraints;
+    use crate::calculator::consts::PERMANENT_FERTILIZERS;
+    use crate::calculator::nitrogen::{NitrogenForm, NitrogenFormLimit};
+    use crate::calculator::query::{ExistingMixture, MixtureQuery, TaskConstraint};
+    use crate::calculator::{Amounts, ElemName, ElemRangeName, FertilizerMinimum};
+
+    fn query(fert_ids: &[usize]) -> MixtureQuery {
+        MixtureQuery::test_default(
+            fert_ids
+                .iter()
+                .map(|id| PERMANENT_FERTILIZERS[*id].clone())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_feasible_query_has_no_conflicts() {
+        assert!(find_conflicting_constraints(&query(&[0, 8, 10, 14])).is_empty());
+    }
+
+    #[test]
+    fn test_magnesium_limit_conflict() {
+        // Магний есть только в калимаге, и того осталось слишком мало
+        let mut query = query(&[0, 8, 10, 13]);
+        query.fertilizers[3].limit = Some(0.1);
+        let conflicts = find_conflicting_constraints(&query);
+        let constraints: Vec<TaskConstraint> = conflicts.iter().map(|c| c.constraint).collect();
+        assert!(constraints.contains(&TaskConstraint::Limit(3)));
+        assert!(constraints.contains(&TaskConstraint::RatioFrom(ElemRangeName::Magnesium)));
+        assert!(constraints.contains(&TaskConstraint::Phosphorus));
+        // Предложенного ослабления любого из ограничений достаточно, чтобы смесь составилась
+        for conflict in conflicts.iter() {
+            let Some(relaxation) = conflict.relaxation else {
+                continue;
+            };
+            let mut relaxed = query.clone();
+            match conflict.constraint {
+                TaskConstraint::Limit(fert_idx) => {
+                    assert!(relaxation.relaxed > relaxation.value);
+                    relaxed.fertilizers[fert_idx].limit = Some(relaxation.relaxed);
+                }
+                TaskConstraint::Minimum(fert_idx) => {
+                    assert!(relaxation.relaxed < relaxation.value);
+                    let fertilizer = &mut relaxed.fertilizers[fert_idx];
+                    fertilizer.minimum = fertilizer
+                        .minimum
+                        .map(|minimum| minimum.with_value(relaxation.relaxed));
+                }
+                TaskConstraint::RatioFrom(range_name) => {
+                    assert!(relaxation.relaxed < relaxation.value);
+                    relaxed.ratio_mut(range_name).from = relaxation.relaxed;
+                }
+                TaskConstraint::RatioTo(range_name) => {
+                    assert!(relaxation.relaxed > relaxation.value);
+                    relaxed.ratio_mut(range_name).to = relaxation.relaxed;
+                }
+                TaskConstraint::Existing => {
+                    assert!(relaxation.relaxed > relaxation.value);
+                    relaxed.mass = relaxation.relaxed;
+                }
+                TaskConstraint::SecondaryFrom(_)
+                | TaskConstraint::SecondaryTo(_)
+                | TaskConstraint::NitrogenShare(_)
+                | TaskConstraint::Phosphorus
+                | TaskConstraint::Target(_) => unreachable!(),
+            }
+            assert!(relaxed.find_solution().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_minimum_conflict() {
+        // В смеси не может быть 9 кг из 10 удобрения без фосфора
+        let mut query = query(&[0, 8, 10, 14]);
+        query.fertilizers[2].minimum = Some(FertilizerMinimum::Mass(9.0));
+        let conflicts = find_conflicting_constraints(&query);
+        let minimum_conflict = conflicts
+            .iter()
+            .find(|c| c.constraint == TaskConstraint::Minimum(2))
+            .expect("Minimum must be among conflicting constraints");
+        let relaxation = minimum_conflict.relaxation.expect("Minimum can be relaxed");
+        assert_eq!(relaxation.value, 9.0);
+        assert!(relaxation.relaxed < 9.0);
+        query.fertilizers[2].minimum = Some(FertilizerMinimum::Mass(relaxation.relaxed));
+        assert!(query.find_solution().is_ok());
+    }
+
+    #[test]
+    fn test_existing_mixture_too_large() {
+        // 9 кг смеси почти без фосфора не разбавить одним килограммом
+        let mut query = query(&[0, 8, 10, 14]);
+        query.existing = Some(ExistingMixture {
+            mass: 9.0,
+            content: Amounts::with(&[
+                (ElemName::Nitrogen, 20.0),
+                (ElemName::Phosphorus, 1.0),
+                (ElemName::Potassium, 5.0),
+            ]),
+        });
+        let conflicts = find_conflicting_constraints(&query);
+        let existing_conflict = conflicts
+            .iter()
+            .find(|c| c.constraint == TaskConstraint::Existing)
+            .expect("Existing mixture must be among conflicting constraints");
+        let relaxation = existing_conflict.relaxation.expect("Batch can be enlarged");
+        assert_eq!(relaxation.value, 10.0);
+        query.mass = relaxation.relaxed;
+        assert!(query.find_solution().is_ok());
+    }
+
+    #[test]
+    fn test_nitrogen_share_conflict() {
+        // Весь азот смеси - из карбамида
+        let mut query = query(&[1, 8, 10, 14]);
+        query.nitrogen_form_limit =
+            Some(NitrogenFormLimit { form: NitrogenForm::Urea, max_share: 50.0 });
+        let conflicts = find_conflicting_constraints(&query);
+        let share_conflict = conflicts
+            .iter()
+            .find(|c| c.constraint == TaskConstraint::NitrogenShare(NitrogenForm::Urea))
+            .expect("Nitrogen share must be among conflicting constraints");
+        let relaxation = share_conflict.relaxation.expect("Share can be raised");
+        assert_eq!(relaxation.value, 50.0);
+        assert!(relaxation.relaxed > 99.0);
+        query.nitrogen_form_limit =
+            Some(NitrogenFormLimit { form: NitrogenForm::Urea, max_share: relaxation.relaxed });
+        assert!(query.find_solution().is_ok());
+    }
+
+    #[test]
+    fn test_no_phosphorus() {
+        let conflicts = find_conflicting_constraints(&query(&[0, 10, 14]));
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].constraint, TaskConstraint::Phosphorus);
+        assert_eq!(conflicts[0].relaxation, None);
+    }
+}
@@ -0,0 +1,308 @@
+//! Прежняя реализация модифицированного симплекс-метода (см. `modified_simplex`), которая
+//! вычисляет обратную матрицу базиса заново на каждой итерации. Оставлена для
+//! перекрёстной проверки решателя по умолчанию и сравнения скорости.
+
+use std::collections::HashSet;
+
+use nalgebra::{self, DMatrix, DVector, RowDVector};
+
+use crate::calculator::lp_solver::{solve_with_plain_rows, LPSolver};
+use crate::calculator::modified_simplex::{
+    Constraint, ConstraintOp, LPSolution, LPTask, PivotRule, SimplexError, Tolerances,
+    MAX_DEGENERATE_STEPS, MAX_STEPS,
+};
+
+pub(crate) struct InverseBasisSimplex;
+
+impl LPSolver for InverseBasisSimplex {
+    fn solve_min(&self, task: &LPTask) -> Result<LPSolution, SimplexError> {
+        solve_with_plain_rows(task, solve_plain)
+    }
+}
+
+/// Решает задачу без двусторонних строк и границ переменных
+#[allow(non_snake_case)]
+fn solve_plain(task: &LPTask) -> Result<LPSolution, SimplexError> {
+    let form = standard_form(task);
+    let mut basis = BasisSolution::new(form.initial_basis.clone(), form.first_artificial);
+    if form.first_artificial < form.A.ncols() {
+        // Первый этап: минимизируем сумму искусственных переменных. Если она не может быть
+        // сведена к нулю, то система ограничений несовместна.
+        let phase1_costs = RowDVector::from_fn(form.A.ncols(), |_, col| {
+            if form.is_artificial(col) {
+                1.0
+            } else {
+                0.0
+            }
+        });
+        let (X_B, _) = run_simplex(
+            &form.A,
+            &form.b,
+            &phase1_costs,
+            &mut basis,
+            &task.tolerances,
+        )?;
+        let phase1_value = (phase1_costs.select_columns(&basis.basis_cols) * &X_B)[(0, 0)];
+        if phase1_value > task.tolerances.feasibility {
+            return Err(SimplexError::NotFeasible);
+        }
+        drive_out_artificials(&form, &mut basis, &task.tolerances)?;
+    }
+    // Второй этап: оптимизация исходной функции, начиная с найденного допустимого базиса
+    let (X_B, inv_B) = run_simplex(&form.A, &form.b, &form.costs, &mut basis, &task.tolerances)?;
+    let C_B = form.costs.select_columns(&basis.basis_cols);
+    let func_val = &C_B * &X_B;
+    // Двойственные оценки y = C_B * B^-1. Множитель нормировки из add_constr
+    // возвращает их к исходной записи ограничения.
+    let duals = &C_B * &inv_B;
+    let num_vars = task.func_vec.len();
+    let reduced_costs = form.costs.columns(0, num_vars) - &duals * form.A.columns(0, num_vars);
+    Ok(LPSolution {
+        function_value: func_val[(0, 0)],
+        params: (0..num_vars)
+            .map(|i| match basis.basis_cols.iter().position(|n| *n == i) {
+                Some(var_pos) => X_B[var_pos],
+                None => 0.0,
+            })
+            .collect(),
+        duals: duals
+            .iter()
+            .zip(task.constr.iter())
+            .map(|(y, row)| y * row.scale)
+            .collect(),
+        reduced_costs: reduced_costs.iter().copied().collect(),
+    })
+}
+
+/// Задача, приведённая к виду `A * X = b, X >= 0` (с целевой функцией `costs * X`).
+/// Вслед за основными переменными в матрице идут дополнительные, а за ними - искусственные.
+#[allow(non_snake_case)]
+struct StandardForm {
+    A: DMatrix<f64>,
+    b: DVector<f64>,
+    costs: RowDVector<f64>,
+    first_artificial: usize,
+    /// Начальный базис, по одной переменной на каждую строку ограничений
+    initial_basis: Vec<usize>,
+}
+
+impl StandardForm {
+    fn is_artificial(&self, col: usize) -> bool {
+        col >= self.first_artificial
+    }
+}
+
+#[derive(Clone)]
+struct BasisSolution {
+    basis_cols: Vec<usize>,
+    non_basis_cols: Vec<usize>,
+}
+
+impl BasisSolution {
+    fn new(basis_cols: Vec<usize>, num_cols: usize) -> Self {
+        let non_basis_cols = (0..num_cols).filter(|i| !basis_cols.contains(i)).collect();
+        Self { basis_cols, non_basis_cols }
+    }
+
+    /// Обмен между базисными и небазисными векторами
+    fn exchange(&mut self, intr_vec_ind: usize, excl_vec_ind: usize) {
+        self.basis_cols
+            .push(self.non_basis_cols.remove(intr_vec_ind));
+        self.non_basis_cols
+            .push(self.basis_cols.remove(excl_vec_ind));
+    }
+}
+
+/// Приводит задачу к стандартной форме, добавляя по одной дополнительной переменной
+/// на каждое неравенство, а также искусственные переменные для тех ограничений,
+/// которые не могут получить начальную базисную переменную иначе.
+#[allow(non_snake_case)]
+fn standard_form(task: &LPTask) -> StandardForm {
+    let num_vars = task.func_vec.len();
+    let fict_vars_count = task
+        .constr
+        .iter()
+        .filter(|row| !matches!(row.op, ConstraintOp::Equal))
+        .count();
+    // Дополнительная переменная неравенства вида "<=" (с коэффициентом +1), а также
+    // неравенства вида ">=" с нулевой правой частью (коэффициент -1) сразу даёт допустимое
+    // базисное значение. Остальным строкам нужна искусственная переменная.
+    let needs_artificial = |row: &Constraint| match row.op {
+        ConstraintOp::Less | ConstraintOp::LessOrEqual => false,
+        ConstraintOp::Greater | ConstraintOp::GreaterOrEqual => row.right > 0.0,
+        ConstraintOp::Equal => true,
+        ConstraintOp::Range => unreachable!("Двусторонние строки заменяются парой неравенств"),
+    };
+    let artificial_count = task
+        .constr
+        .iter()
+        .filter(|row| needs_artificial(row))
+        .count();
+    let first_artificial = num_vars + fict_vars_count;
+    let mut A = DMatrix::<f64>::zeros(task.constr.len(), first_artificial + artificial_count);
+    let mut initial_basis = Vec::with_capacity(task.constr.len());
+    let mut fict_ind = num_vars;
+    let mut artificial_ind = first_artificial;
+    for (i, row) in task.constr.iter().enumerate() {
+        A.view_mut((i, 0), (1, num_vars))
+            .copy_from(&row.left.transpose());
+        let fict_coef = match row.op {
+            ConstraintOp::Greater | ConstraintOp::GreaterOrEqual => Some(-1.0),
+            ConstraintOp::Less | ConstraintOp::LessOrEqual => Some(1.0),
+            ConstraintOp::Equal => None,
+            ConstraintOp::Range => unreachable!("Двусторонние строки заменяются парой неравенств"),
+        };
+        if let Some(coef) = fict_coef {
+            A[(i, fict_ind)] = coef;
+            if !needs_artificial(row) {
+                initial_basis.push(fict_ind);
+            }
+            fict_ind += 1;
+        }
+        if needs_artificial(row) {
+            A[(i, artificial_ind)] = 1.0;
+            initial_basis.push(artificial_ind);
+            artificial_ind += 1;
+        }
+    }
+    let b = DVector::from_iterator(task.constr.len(), task.constr.iter().map(|c| c.right));
+    let costs = RowDVector::from_iterator(
+        A.ncols(),
+        task.func_vec
+            .iter()
+            .copied()
+            .chain(std::iter::repeat_n(0.0, A.ncols() - num_vars)),
+    );
+    StandardForm { A, b, costs, first_artificial, initial_basis }
+}
+
+/// Итерации модифицированного симплекс-метода, начиная с допустимого базиса `basis`.
+/// Возвращает значения базисных переменных найденного оптимального решения
+/// и обратную матрицу оптимального базиса.
+///
+/// Вырожденные вершины (с нулевыми базисными переменными) встречаются очень часто,
+/// например, когда удобрение вовсе не содержит какого-то элемента. Итерации в них не
+/// уменьшают целевую функцию, поэтому после серии таких итераций, либо при повторном
+/// посещении того же базиса, выбор переменных переключается на правило Бленда -
+/// до первой итерации, на которой решение действительно сдвинулось.
+#[allow(non_snake_case)]
+fn run_simplex(
+    A: &DMatrix<f64>,
+    b: &DVector<f64>,
+    costs: &RowDVector<f64>,
+    basis: &mut BasisSolution,
+    tolerances: &Tolerances,
+) -> Result<(DVector<f64>, DMatrix<f64>), SimplexError> {
+    let mut pivot_rule = PivotRule::Dantzig;
+    let mut degenerate_steps = 0_usize;
+    let mut visited_bases = HashSet::<Vec<usize>>::new();
+    for _ in 0..MAX_STEPS {
+        let C_B = costs.select_columns(&basis.basis_cols);
+        let B = A.select_columns(&basis.basis_cols);
+        let inv_B = B.try_inverse().ok_or(SimplexError::SingularBasis)?;
+        // Ошибки округления могут дать крошечные отрицательные значения на месте нулевых
+        let X_B = (&inv_B * b).map(|x| if x.abs() <= tolerances.zero { 0.0 } else { x });
+        let noB = A.select_columns(&basis.non_basis_cols);
+        let noC = costs.select_columns(&basis.non_basis_cols);
+        let z_minus_c = &C_B * &inv_B * &noB - &noC;
+        // Ищем вводимую переменную
+        let candidates = z_minus_c
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v > tolerances.optimality);
+        let potential_intr_vec_ind = match pivot_rule {
+            PivotRule::Dantzig => candidates
+                .max_by(|(_, v1), (_, v2)| v1.total_cmp(v2))
+                .map(|(i, _)| i),
+            PivotRule::Bland => candidates
+                .min_by_key(|(i, _)| basis.non_basis_cols[*i])
+                .map(|(i, _)| i),
+        };
+        let intr_vec_ind = match potential_intr_vec_ind {
+            // Если для всех небазисных переменных величины z - с >= 0
+            // в задаче максимизации или z. - с <= 0
+            // в задаче минимизации, то вычисления заканчиваются, так
+            // как получено оптимальное решение
+            None => return Ok((X_B, inv_B)),
+            Some(idx) => idx,
+        };
+        // Ищем исключаемую переменную. Рассматриваются только строки с положительным
+        // ведущим элементом; если таких нет, то функция неограниченно убывает.
+        let N = &inv_B * A.column(basis.non_basis_cols[intr_vec_ind]);
+        let ratios: Vec<(usize, f64)> = (0..N.nrows())
+            .filter(|i| N[*i] > tolerances.pivot)
+            .map(|i| (i, X_B[i].max(0.0) / N[i]))
+            .collect();
+        let min_ratio = ratios
+            .iter()
+            .map(|(_, ratio)| *ratio)
+            .min_by(f64::total_cmp)
+            .ok_or(SimplexError::Unbounded)?;
+        // Среди строк с (почти) равными минимальными отношениями Бленд выбирает переменную
+        // с наименьшим номером, а в остальных случаях предпочтение отдаётся наибольшему
+        // ведущему элементу, как более устойчивому численно.
+        let ties = ratios
+            .iter()
+            .filter(|(_, ratio)| *ratio <= min_ratio + tolerances.zero)
+            .map(|(i, _)| *i);
+        let excl_vec_ind = match pivot_rule {
+            PivotRule::Dantzig => ties.max_by(|i1, i2| N[*i1].total_cmp(&N[*i2])),
+            PivotRule::Bland => ties.min_by_key(|i| basis.basis_cols[*i]),
+        }
+        .expect("The row with the minimal ratio is always among the ties");
+        if min_ratio <= tolerances.zero {
+            // Вырожденная итерация: решение не сдвинется, меняется только базис
+            let mut basis_key = basis.basis_cols.clone();
+            basis_key.sort_unstable();
+            let is_cycle = !visited_bases.insert(basis_key);
+            degenerate_steps += 1;
+            if is_cycle || degenerate_steps > MAX_DEGENERATE_STEPS {
+                pivot_rule = PivotRule::Bland;
+            }
+        } else {
+            degenerate_steps = 0;
+            visited_bases.clear();
+            pivot_rule = PivotRule::Dantzig;
+        }
+        basis.exchange(intr_vec_ind, excl_vec_ind);
+    }
+    Err(SimplexError::TooManySteps)
+}
+
+/// После первого этапа искусственные переменные могут остаться в базисе с нулевыми значениями
+/// (вырожденный случай). Каждую из них заменяем любой неискусственной переменной с ненулевым
+/// ведущим элементом в той же строке. Если такой не нашлось - строка линейно зависит
+/// от остальных, и искусственная переменная остаётся в базисе, навсегда сохраняя
+/// нулевое значение. Вводить искусственные переменные в базис на втором этапе запрещено.
+#[allow(non_snake_case)]
+fn drive_out_artificials(
+    form: &StandardForm,
+    basis: &mut BasisSolution,
+    tolerances: &Tolerances,
+) -> Result<(), SimplexError> {
+    for pos in 0..basis.basis_cols.len() {
+        if !form.is_artificial(basis.basis_cols[pos]) {
+            continue;
+        }
+        let B = form.A.select_columns(&basis.basis_cols);
+        let inv_B = B.try_inverse().ok_or(SimplexError::SingularBasis)?;
+        let pivot_row = inv_B.row(pos) * &form.A;
+        let replacement = basis
+            .non_basis_cols
+            .iter()
+            .enumerate()
+            .filter(|(_, col)| !form.is_artificial(**col))
+            .map(|(i, col)| (i, pivot_row[*col].abs()))
+            .filter(|(_, v)| *v > tolerances.pivot)
+            .max_by(|(_, v1), (_, v2)| v1.total_cmp(v2))
+            .map(|(i, _)| i);
+        if let Some(intr_vec_ind) = replacement {
+            std::mem::swap(
+                &mut basis.basis_cols[pos],
+                &mut basis.non_basis_cols[intr_vec_ind],
+            );
+        }
+    }
+    basis.non_basis_cols.retain(|col| !form.is_artificial(*col));
+    Ok(())
+}
@@ -0,0 +1,354 @@
+//! Общий интерфейс решателей задач линейного программирования. Задача по-прежнему
+//! описывается `LPTask`, а решать её может любая из реализаций:
+//! - `revised_simplex::RevisedSimplex` - решатель по умолчанию (LU-разложение базиса);
+//! - `inverse_simplex::InverseBasisSimplex` - прежняя реализация с обращением базиса;
+//! - `tableau_simplex::TableauSimplex` - независимая реализация на полной симплекс-таблице.
+//!
+//! Решатель выбирается полем `LPTask::backend` (для смеси - параметром ссылки `solver`),
+//! так что при подозрении на ошибку решателя по умолчанию расчёт можно повторить другим.
+//! Последние две реализации медленнее и используются прежде всего для перекрёстной проверки.
+//! Двусторонние строки и границы переменных они получают в виде обычных строк
+//! (см. `solve_with_plain_rows`).
+
+use crate::calculator::inverse_simplex::InverseBasisSimplex;
+use crate::calculator::modified_simplex::{ConstraintOp, LPSolution, LPTask, SimplexError};
+use crate::calculator::revised_simplex::RevisedSimplex;
+use crate::calculator::tableau_simplex::TableauSimplex;
+
+pub(crate) trait LPSolver {
+    /// Находит минимум целевой функции задачи `task`
+    fn solve_min(&self, task: &LPTask) -> Result<LPSolution, SimplexError>;
+}
+
+/// Реализация решателя, которой решается задача
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum SolverBackend {
+    /// `revised_simplex::RevisedSimplex`
+    #[default]
+    Revised,
+    /// `inverse_simplex::InverseBasisSimplex`
+    InverseBasis,
+    /// `tableau_simplex::TableauSimplex`
+    Tableau,
+}
+
+impl SolverBackend {
+    pub const ALL: [SolverBackend; 3] = [
+        SolverBackend::Revised,
+        SolverBackend::InverseBasis,
+        SolverBackend::Tableau,
+    ];
+
+    pub fn solver(self) -> &'static dyn LPSolver {
+        match self {
+            SolverBackend::Revised => &RevisedSimplex,
+            SolverBackend::InverseBasis => &InverseBasisSimplex,
+            SolverBackend::Tableau => &TableauSimplex,
+        }
+    }
+
+    pub fn urlencode(self) -> &'static str {
+        match self {
+            SolverBackend::Revised => "revised",
+            SolverBackend::InverseBasis => "inverse",
+            SolverBackend::Tableau => "tableau",
+        }
+    }
+
+    pub fn from_url(text: &str) -> Option<Self> {
+        SolverBackend::ALL
+            .into_iter()
+            .find(|backend| backend.urlencode() == text)
+    }
+}
+
+/// Решает задачу функцией `solve`, которая понимает только односторонние ограничения
+/// и неотрицательные переменные без других границ. Двусторонние строки и границы переменных
+/// заменяются обычными строками, а двойственные оценки и приведённые стоимости
+/// пересчитываются обратно для исходной задачи.
+pub(crate) fn solve_with_plain_rows(
+    task: &LPTask,
+    solve: impl Fn(&LPTask) -> Result<LPSolution, SimplexError>,
+) -> Result<LPSolution, SimplexError> {
+    let num_vars = task.func_vec.len();
+    let mut plain = LPTask::new(task.func_vec.as_slice()).with_tolerances(task.tolerances);
+    // Строки новой задачи, на которые заменена каждая строка исходной
+    let mut plain_rows = Vec::with_capacity(task.constr.len());
+    for row in task.constr.iter() {
+        let first = plain.constr.len();
+        let left = row.left.as_slice();
+        match row.op {
+            ConstraintOp::Range => {
+                plain.add_constr(left, ConstraintOp::GreaterOrEqual, row.right - row.range);
+                plain.add_constr(left, ConstraintOp::LessOrEqual, row.right);
+            }
+            op => plain.add_constr(left, op, row.right),
+        }
+        plain_rows.push(first..plain.constr.len());
+    }
+    let mut unit = vec![0.0; num_vars];
+    for (var, (lower, upper)) in task.bounds.iter().enumerate() {
+        unit[var] = 1.0;
+        if *lower > 0.0 {
+            plain.add_constr(&unit, ConstraintOp::GreaterOrEqual, *lower);
+        }
+        if upper.is_finite() {
+            plain.add_constr(&unit, ConstraintOp::LessOrEqual, *upper);
+        }
+        unit[var] = 0.0;
+    }
+    let solution = solve(&plain)?;
+    // Оценки относятся к строкам исходной задачи в том виде, в каком они хранятся
+    let duals: Vec<f64> = plain_rows
+        .into_iter()
+        .map(|rows| solution.duals[rows].iter().sum())
+        .collect();
+    let reduced_costs = (0..num_vars)
+        .map(|var| {
+            let used: f64 = task
+                .constr
+                .iter()
+                .zip(duals.iter())
+                .map(|(row, y)| y * row.left[var])
+                .sum();
+            task.func_vec[var] - used
+        })
+        .collect();
+    Ok(LPSolution {
+        function_value: solution.function_value,
+        params: solution.params,
+        duals: duals
+            .iter()
+            .zip(task.constr.iter())
+            .map(|(y, row)| y * row.scale)
+            .collect(),
+        reduced_costs,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::LPSolver;
+    use crate::calculator::consts::PERMANENT_FERTILIZERS;
+    use crate::calculator::inverse_simplex::InverseBasisSimplex;
+    use crate::calculator::modified_simplex::{ConstraintOp, LPSolution, LPTask, SimplexError};
+    use crate::calculator::query::{MixtureObjective, MixtureQuery};
+    use crate::calculator::revised_simplex::RevisedSimplex;
+    use crate::calculator::tableau_simplex::TableauSimplex;
+    use crate::calculator::{Amounts, ElemName, ElemRange, Fertilizer};
+
+    /// Простой детерминированный генератор (xorshift), чтобы не зависеть от внешних крейтов
+    pub(crate) struct Random(pub u64);
+
+    impl Random {
+        pub fn next_f64(&mut self) -> f64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 >> 11) as f64 / (1u64 << 53) as f64
+        }
+
+        fn below(&mut self, bound: usize) -> usize {
+            ((self.next_f64() * bound as f64) as usize).min(bound - 1)
+        }
+    }
+
+    fn random_fertilizer(random: &mut Random, i: usize) -> Fertilizer {
+        let mut content = |max: f64| {
+            if random.next_f64() < 0.4 {
+                0.0
+            } else {
+                (random.next_f64() * max * 10.0).round() / 10.0
+            }
+        };
+        let (n, p, k, mg) = (content(35.0), content(30.0), content(30.0), content(10.0));
+        Fertilizer {
+            name: format!("Удобрение {}", i).into(),
+            content: Amounts::with(&[
+                (ElemName::Nitrogen, n),
+                (ElemName::Phosphorus, p),
+                (ElemName::Potassium, k),
+                (ElemName::Magnesium, mg),
+            ]),
+            limit: (random.next_f64() < 0.1).then_some(1.0),
+            ..Default::default()
+        }
+    }
+
+    /// Каталог из постоянных удобрений и `count` случайных, как будто импортированных
+    /// из прайс-листа магазина: у многих удобрений часть элементов отсутствует
+    pub(crate) fn large_catalog_query(count: usize, seed: u64) -> MixtureQuery {
+        let mut random = Random(seed);
+        let mut fertilizers = PERMANENT_FERTILIZERS.to_vec();
+        for i in 0..count {
+            fertilizers.push(random_fertilizer(&mut random, i));
+        }
+        MixtureQuery::test_default(fertilizers)
+    }
+
+    /// Небольшой запрос со случайным набором удобрений, остатками, ценами, соотношениями
+    /// и целевой функцией. Примерно треть таких запросов несовместна.
+    fn random_query(random: &mut Random) -> MixtureQuery {
+        let mut fertilizers = Vec::new();
+        for fertilizer in PERMANENT_FERTILIZERS.iter() {
+            if random.next_f64() < 0.4 {
+                let mut fertilizer = fertilizer.clone();
+                if random.next_f64() < 0.3 {
+                    fertilizer.limit = Some((random.next_f64() * 30.0).round() / 10.0);
+                }
+                fertilizer.price = Some((random.next_f64() * 500.0).round());
+                fertilizers.push(fertilizer);
+            }
+        }
+        for i in 0..random.below(4) {
+            let mut fertilizer = random_fertilizer(random, i);
+            fertilizer.price = Some((random.next_f64() * 500.0).round());
+            fertilizers.push(fertilizer);
+        }
+        let mut range = |max: f64| {
+            let a = (random.next_f64() * max * 100.0).round() / 100.0;
+            let b = (random.next_f64() * max * 100.0).round() / 100.0;
+            ElemRange { from: a.min(b), to: a.max(b) }
+        };
+        let (n, k, mg) = (range(2.5), range(2.5), range(0.6));
+        let objective = [
+            MixtureObjective::Mass,
+            MixtureObjective::Cost,
+            MixtureObjective::Ballast,
+        ][random.below(3)];
+        MixtureQuery {
+            N_ratio: n,
+            K_ratio: k,
+            Mg_ratio: mg,
+            objective,
+            ..MixtureQuery::test_default(fertilizers)
+        }
+    }
+
+    /// Проверяет, что решение не выходит за границы переменных и удовлетворяет всем ограничениям задачи,
+    /// а значение функции соответствует найденным переменным
+    fn assert_valid_solution(task: &LPTask, solution: &LPSolution) {
+        const TOLERANCE: f64 = 1e-7;
+        for (x, (lower, upper)) in solution.params.iter().zip(task.bounds.iter()) {
+            assert!(*x >= lower - TOLERANCE && *x <= upper + TOLERANCE);
+        }
+        for row in task.constr.iter() {
+            let left: f64 = row
+                .left
+                .iter()
+                .zip(solution.params.iter())
+                .map(|(a, x)| a * x)
+                .sum();
+            let tolerance = TOLERANCE * row.right.abs().max(1.0);
+            let satisfied = match row.op {
+                ConstraintOp::Range => {
+                    left <= row.right + tolerance && left >= row.right - row.range - tolerance
+                }
+                ConstraintOp::Equal => (left - row.right).abs() <= tolerance,
+                ConstraintOp::Less | ConstraintOp::LessOrEqual => left <= row.right + tolerance,
+                ConstraintOp::Greater | ConstraintOp::GreaterOrEqual => {
+                    left >= row.right - tolerance
+                }
+            };
+            assert!(satisfied, "{} {:?} {}", left, row.op, row.right);
+        }
+        let value: f64 = task
+            .func_vec
+            .iter()
+            .zip(solution.params.iter())
+            .map(|(c, x)| c * x)
+            .sum();
+        assert!((value - solution.function_value).abs() <= TOLERANCE * value.abs().max(1.0));
+    }
+
+    /// Решает задачу всеми реализациями и сравнивает результаты
+    pub(crate) fn assert_solvers_agree(task: &LPTask) {
+        let reference = task.solve_min_with(&TableauSimplex);
+        let solvers: [&dyn LPSolver; 2] = [&RevisedSimplex, &InverseBasisSimplex];
+        for solver in solvers {
+            match (&reference, solver.solve_min(task)) {
+                (Ok(reference), Ok(solution)) => {
+                    assert_valid_solution(task, &solution);
+                    let tolerance = 1e-7 * reference.function_value.abs().max(1.0);
+                    assert!(
+                        (solution.function_value - reference.function_value).abs() < tolerance,
+                        "{} != {}",
+                        solution.function_value,
+                        reference.function_value
+                    );
+                }
+                (reference, solution) => {
+                    assert_eq!(reference.as_ref().err(), solution.as_ref().err())
+                }
+            }
+        }
+        if let Ok(reference) = &reference {
+            assert_valid_solution(task, reference);
+        }
+    }
+
+    #[test]
+    fn test_solvers_agree_on_random_queries() {
+        let mut random = Random(2024);
+        let mut infeasible = 0;
+        for _ in 0..300 {
+            let query = random_query(&mut random);
+            let task = query.build_task(&[]);
+            assert_solvers_agree(&task);
+            if matches!(task.solve_min(), Err(SimplexError::NotFeasible)) {
+                infeasible += 1;
+            }
+        }
+        // Генератор должен давать и совместные, и несовместные задачи
+        assert!(infeasible > 0 && infeasible < 300);
+    }
+
+    #[test]
+    fn test_solvers_agree_on_large_catalogs() {
+        for seed in 1..=20 {
+            assert_solvers_agree(&large_catalog_query(40, seed).build_task(&[]));
+        }
+    }
+
+    #[test]
+    fn test_extreme_percentages() {
+        let mut query = large_catalog_query(0, 1);
+        // Микроудобрение с сотыми долями процента магния и почти чистое вещество,
+        // которого осталось на кончике ножа
+        query.fertilizers.push(Fertilizer {
+            name: "Микроудобрение".into(),
+            content: Amounts::with(&[(ElemName::Nitrogen, 0.02), (ElemName::Magnesium, 0.01)]),
+            ..Default::default()
+        });
+        query.fertilizers.push(Fertilizer {
+            name: "Концентрат".into(),
+            content: Amounts::with(&[(ElemName::Phosphorus, 99.0)]),
+            limit: Some(1e-6),
+            ..Default::default()
+        });
+        let task = query.build_task(&[]);
+        assert_solvers_agree(&task);
+        assert!(task.solve_min().is_ok());
+        let weights = query.find_solution().unwrap();
+        let concentrate = weights.components.last().unwrap().1;
+        assert!(concentrate <= 1e-6 * (1.0 + 1e-9));
+    }
+
+    #[test]
+    fn test_solvers_agree_with_bounds() {
+        let mut random = Random(77);
+        for _ in 0..200 {
+            let query = random_query(&mut random);
+            let mut task = query.build_task(&[]);
+            for var in 0..query.fertilizers.len() {
+                match random.below(4) {
+                    0 => task.set_bounds(var, 0.0, 0.0),
+                    1 => task.set_bounds(var, 0.0, random.next_f64() * 5.0),
+                    2 => task.set_bounds(var, random.next_f64(), f64::INFINITY),
+                    _ => {}
+                }
+            }
+            assert_solvers_agree(&task);
+        }
+    }
+}
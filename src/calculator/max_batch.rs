@@ -0,0 +1,178 @@
+//! Наибольшая партия смеси, которую можно приготовить из имеющихся остатков удобрений.
+//! Здесь масса смеси не задаётся, а максимизируется, поэтому задача строится не на единицу
+//! фосфора, как в `MixtureQuery::build_task`, а прямо в килограммах: остатки удобрений
+//! становятся верхними границами переменных, а соотношения элементов к фосфору -
+//! однородными неравенствами.
+
+use crate::calculator::consts::MIN_SIGNIFICANT_CHANGE;
+use crate::calculator::modified_simplex::{ConstraintOp, LPTask, SimplexError};
+use crate::calculator::query::MixtureQuery;
+use crate::calculator::{ElemName, ElemRangeName, Fertilizer, FertilizerMinimum};
+
+/// Допуск, с которым удобрение считается израсходованным полностью
+const EXHAUSTED_TOLERANCE: f64 = 1e-6;
+
+/// Наибольшая партия смеси и то, что мешает сделать её ещё больше
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MaxBatch {
+    /// Масса партии (кг), вместе с уже приготовленной частью смеси
+    pub mass: f64,
+    /// Веса удобрений (кг), в том же порядке, что и в запросе
+    pub weights: Vec<f64>,
+    /// Израсходованные полностью удобрения (индексы в запросе) и на сколько килограммов
+    /// выросла бы партия с каждым лишним килограммом такого удобрения.
+    /// Упорядочены по убыванию прироста, так что первым идёт то, что кончается первым.
+    pub exhausted: Vec<(usize, f64)>,
+}
+
+/// Строит задачу в килограммах: переменные - веса удобрений запроса
+/// и (последней) уже приготовленной части смеси, минимизируется масса со знаком минус
+fn build_max_batch_task(query: &MixtureQuery) -> LPTask {
+    let existing = query.existing.map(|existing| existing.as_fertilizer());
+    let fertilizers: Vec<&Fertilizer> = query.fertilizers.iter().chain(existing.iter()).collect();
+    let mut task = LPTask::new(&vec![-1.0; fertilizers.len()]).with_backend(query.solver);
+    let ranges =
+        ElemRangeName::ALL.map(|range_name| (range_name.element(), *query.ratio(range_name)));
+    for (element, range) in ranges.into_iter().chain(query.secondary_ratios.iter()) {
+        let from_constr: Vec<f64> = fertilizers
+            .iter()
+            .map(|f| f[element] - range.from * f[ElemName::Phosphorus])
+            .collect();
+        let to_constr: Vec<f64> = fertilizers
+            .iter()
+            .map(|f| f[element] - range.to * f[ElemName::Phosphorus])
+            .collect();
+        task.add_constr(&from_constr, ConstraintOp::GreaterOrEqual, 0.0);
+        task.add_constr(&to_constr, ConstraintOp::LessOrEqual, 0.0);
+    }
+    if let Some(limit) = query.nitrogen_form_limit {
+        let share_constr: Vec<f64> = fertilizers.iter().map(|f| limit.coefficient(f)).collect();
+        task.add_constr(&share_constr, ConstraintOp::LessOrEqual, 0.0);
+    }
+    let mut share_constr = vec![0.0; fertilizers.len()];
+    for (fert_idx, fertilizer) in query.fertilizers.iter().enumerate() {
+        let mut lower = 0.0;
+        match fertilizer.minimum {
+            Some(FertilizerMinimum::Mass(min_weight)) => lower = min_weight,
+            Some(FertilizerMinimum::Share(percent)) => {
+                // Доля от всей партии: $X_n - r * (X_1 + X_2 + .. + X_n) >= 0$
+                let min_rate = percent / 100.0;
+                share_constr.fill(-min_rate);
+                share_constr[fert_idx] = 1.0 - min_rate;
+                task.add_constr(&share_constr, ConstraintOp::GreaterOrEqual, 0.0);
+            }
+            None => {}
+        }
+        task.set_bounds(fert_idx, lower, fertilizer.limit.unwrap_or(f64::INFINITY));
+    }
+    if let Some(existing) = query.existing {
+        task.set_bounds(fertilizers.len() - 1, existing.mass, existing.mass);
+    }
+    task
+}
+
+/// Находит наибольшую партию смеси, которую можно приготовить из остатков удобрений
+/// с соблюдением соотношений элементов. Масса смеси, цель оптимизации и ограничение
+/// на число удобрений из запроса не учитываются, а соотношения всегда берутся из жёстких
+/// диапазонов, даже если заданы идеальные. Если хотя бы одно подходящее удобрение
+/// не ограничено остатком, партия не ограничена (`SimplexError::Unbounded`).
+pub(crate) fn find_max_batch(query: &MixtureQuery) -> Result<MaxBatch, SimplexError> {
+    let solution = build_max_batch_task(query).solve_min()?;
+    let num_ferts = query.fertilizers.len();
+    let weights = solution.params[..num_ferts].to_vec();
+    let mut exhausted: Vec<(usize, f64)> = query
+        .fertilizers
+        .iter()
+        .zip(weights.iter())
+        .enumerate()
+        .filter_map(|(fert_idx, (fertilizer, weight))| {
+            let limit = fertilizer.limit?;
+            // Приведённая стоимость переменной на верхней границе неположительна,
+            // и со знаком минус равна приросту массы партии на килограмм остатка
+            let gain = -solution.reduced_costs[fert_idx];
+            (limit > 0.0 && *weight >= limit - EXHAUSTED_TOLERANCE && gain > MIN_SIGNIFICANT_CHANGE)
+                .then_some((fert_idx, gain))
+        })
+        .collect();
+    exhausted.sort_by(|(_, gain1), (_, gain2)| gain2.total_cmp(gain1));
+    Ok(MaxBatch { mass: -solution.function_value, weights, exhausted })
+}
+
+#[cfg(test)]
+mod test {
+    use super::find_max_batch;
+    use crate::calculator::consts::PERMANENT_FERTILIZERS;
+    use crate::calculator::modified_simplex::SimplexError;
+    use crate::calculator::query::{ExistingMixture, MixtureQuery};
+    use crate::calculator::{Amounts, ElemName};
+
+    fn limited_query(fert_ids: &[usize], limit: f64) -> MixtureQuery {
+        MixtureQuery::test_default(
+            fert_ids
+                .iter()
+                .map(|id| {
+                    let mut fertilizer = PERMANENT_FERTILIZERS[*id].clone();
+                    fertilizer.limit = Some(limit);
+                    fertilizer
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_max_batch_is_solvable() {
+        let mut query = limited_query(&[0, 1, 5, 6, 9, 10, 12, 15], 2.0);
+        let max_batch = find_max_batch(&query).expect("Batch is limited by stock");
+        let total: f64 = max_batch.weights.iter().sum();
+        assert!((total - max_batch.mass).abs() < 1e-9);
+        assert!(!max_batch.exhausted.is_empty());
+        // Партия такой массы составляется, а чуть большей - уже нет
+        query.mass = max_batch.mass;
+        let solution = query.find_solution().expect("Max batch must be solvable");
+        for ((fertilizer, weight), max_weight) in solution.components.iter().zip(&max_batch.weights)
+        {
+            assert!(*weight <= fertilizer.limit.unwrap() + 1e-9);
+            assert!((weight - max_weight).abs() < 1e-6);
+        }
+        query.mass = max_batch.mass * 1.001;
+        assert_eq!(query.find_solution(), Err(SimplexError::NotFeasible));
+    }
+
+    #[test]
+    fn test_exhausted_gain() {
+        let mut query = limited_query(&[0, 1, 5, 6, 9, 10, 12, 15], 2.0);
+        let max_batch = find_max_batch(&query).unwrap();
+        let (first_idx, gain) = max_batch.exhausted[0];
+        query.fertilizers[first_idx].limit = Some(2.01);
+        let larger = find_max_batch(&query).unwrap();
+        assert!(((larger.mass - max_batch.mass) / 0.01 - gain).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_existing_mixture_counts() {
+        let mut query = limited_query(&[0, 1, 5, 6, 9, 10, 12, 15], 2.0);
+        let without_existing = find_max_batch(&query).unwrap();
+        query.existing = Some(ExistingMixture {
+            mass: 1.0,
+            content: Amounts::with(&[
+                (ElemName::Nitrogen, 10.0),
+                (ElemName::Phosphorus, 6.0),
+                (ElemName::Potassium, 8.0),
+                (ElemName::Magnesium, 1.0),
+            ]),
+        });
+        let max_batch = find_max_batch(&query).unwrap();
+        let added: f64 = max_batch.weights.iter().sum();
+        assert!((added + 1.0 - max_batch.mass).abs() < 1e-9);
+        assert!(max_batch.mass > without_existing.mass);
+    }
+
+    #[test]
+    fn test_unlimited_stock() {
+        let mut query = limited_query(&[0, 1, 5, 6, 9, 10, 12, 15], 2.0);
+        for fertilizer in query.fertilizers.iter_mut() {
+            fertilizer.limit = None;
+        }
+        assert_eq!(find_max_batch(&query), Err(SimplexError::Unbounded));
+    }
+}
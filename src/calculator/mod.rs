@@ -1,7 +1,23 @@
+pub(crate) mod alternatives;
+pub(crate) mod cardinality;
 pub(crate) mod consts;
+pub(crate) mod crops;
 pub(crate) mod formatted_solution;
+pub(crate) mod infeasibility;
+pub(crate) mod inverse_simplex;
+pub(crate) mod lp_solver;
+pub(crate) mod max_batch;
 pub(crate) mod modified_simplex;
+pub(crate) mod nitrogen;
+pub(crate) mod nutrients;
+pub(crate) mod profiles;
 pub(crate) mod query;
+pub(crate) mod revised_simplex;
+pub(crate) mod rounding;
+pub(crate) mod schedule;
+pub(crate) mod sensitivity;
+pub(crate) mod tableau_simplex;
+pub(crate) mod units;
 
 use std::borrow::Cow;
 use std::fmt::Write;
@@ -10,21 +26,15 @@ use std::sync::atomic::Ordering;
 
 use serde::{Deserialize, Serialize};
 
+use nitrogen::NitrogenForms;
+pub(crate) use nutrients::{ElemName, NutrientMap};
+
 #[derive(PartialEq, Clone, Copy, Debug)]
 pub(crate) struct ElemRange {
     pub from: f64,
     pub to: f64,
 }
 
-/// Используется для индексации в структурах удобрений, дефицитов, и т.п.
-#[derive(Clone, Copy)]
-pub(crate) enum ElemName {
-    Nitrogen,
-    Phosphorus,
-    Potassium,
-    Magnesium,
-}
-
 /// Используется для индексации в диапазонах макроудобрений
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub(crate) enum ElemRangeName {
@@ -33,12 +43,63 @@ pub(crate) enum ElemRangeName {
     Magnesium,
 }
 
+/// Необязательные диапазоны соотношений вторичных элементов (`ElemName::secondary`)
+/// к фосфору. Если диапазон не задан, содержание элемента в смеси не ограничивается.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub(crate) struct SecondaryRatios(NutrientMap<Option<ElemRange>>);
+
+impl SecondaryRatios {
+    /// Диапазон соотношения элемента к фосфору (для макроэлементов всегда `None`)
+    pub fn get(&self, element: ElemName) -> Option<ElemRange> {
+        self.0[element]
+    }
+
+    pub fn set(&mut self, element: ElemName, range: Option<ElemRange>) {
+        assert!(
+            element.info().default_ratio.is_some(),
+            "Only secondary elements have optional ratios"
+        );
+        self.0[element] = range;
+    }
+
+    /// Заданные диапазоны, в порядке справочника элементов
+    pub fn iter(&self) -> impl Iterator<Item = (ElemName, ElemRange)> + '_ {
+        self.0
+            .iter()
+            .filter_map(|(element, range)| range.map(|range| (element, range)))
+    }
+
+    pub fn all_valid(&self) -> bool {
+        self.iter().all(|(_, range)| range.is_valid())
+    }
+}
+
+impl ElemRangeName {
+    /// Соотношения к фосфору, в порядке строк задачи
+    pub const ALL: [ElemRangeName; 3] = [
+        ElemRangeName::Nitrogen,
+        ElemRangeName::Potassium,
+        ElemRangeName::Magnesium,
+    ];
+
+    /// Элемент, соотношение которого к фосфору задаёт диапазон
+    pub fn element(self) -> ElemName {
+        match self {
+            ElemRangeName::Nitrogen => ElemName::Nitrogen,
+            ElemRangeName::Potassium => ElemName::Potassium,
+            ElemRangeName::Magnesium => ElemName::Magnesium,
+        }
+    }
+}
+
 /// Соотношения макроэлементов к фосфору
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) struct ElemRatios {
     pub n_to_p: ElemRange,
     pub k_to_p: ElemRange,
     pub mg_to_p: ElemRange,
+    /// Соотношения к фосфору кальция, серы, хлора и натрия, если они важны
+    pub secondary: SecondaryRatios,
 }
 
 impl Default for ElemRatios {
@@ -47,13 +108,17 @@ impl Default for ElemRatios {
             n_to_p: ElemRange { from: 1.75, to: 1.85 },
             k_to_p: ElemRange { from: 1.75, to: 1.85 },
             mg_to_p: ElemRange { from: 0.25, to: 0.45 },
+            secondary: Default::default(),
         }
     }
 }
 
 impl ElemRatios {
     pub fn all_valid(&self) -> bool {
-        self.n_to_p.is_valid() && self.k_to_p.is_valid() && self.mg_to_p.is_valid()
+        self.n_to_p.is_valid()
+            && self.k_to_p.is_valid()
+            && self.mg_to_p.is_valid()
+            && self.secondary.all_valid()
     }
 }
 
@@ -69,6 +134,49 @@ impl std::ops::Index<ElemRangeName> for ElemRatios {
     }
 }
 
+/// Идеальные соотношения макроэлементов к фосфору. В отличие от `ElemRatios`, это не жёсткие
+/// границы: подбирается смесь, соотношения которой к ним как можно ближе.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) struct TargetRatios {
+    pub n_to_p: f64,
+    pub k_to_p: f64,
+    pub mg_to_p: f64,
+}
+
+impl TargetRatios {
+    /// Середины диапазонов соотношений
+    pub fn middle_of(ratios: &ElemRatios) -> Self {
+        let middle = |range: ElemRange| (range.from + range.to) / 2.0;
+        Self {
+            n_to_p: middle(ratios.n_to_p),
+            k_to_p: middle(ratios.k_to_p),
+            mg_to_p: middle(ratios.mg_to_p),
+        }
+    }
+}
+
+impl std::ops::Index<ElemRangeName> for TargetRatios {
+    type Output = f64;
+
+    fn index(&self, index: ElemRangeName) -> &Self::Output {
+        match index {
+            ElemRangeName::Nitrogen => &self.n_to_p,
+            ElemRangeName::Potassium => &self.k_to_p,
+            ElemRangeName::Magnesium => &self.mg_to_p,
+        }
+    }
+}
+
+impl std::ops::IndexMut<ElemRangeName> for TargetRatios {
+    fn index_mut(&mut self, index: ElemRangeName) -> &mut Self::Output {
+        match index {
+            ElemRangeName::Nitrogen => &mut self.n_to_p,
+            ElemRangeName::Potassium => &mut self.k_to_p,
+            ElemRangeName::Magnesium => &mut self.mg_to_p,
+        }
+    }
+}
+
 impl std::ops::IndexMut<ElemRangeName> for ElemRatios {
     fn index_mut(&mut self, index: ElemRangeName) -> &mut Self::Output {
         match index {
@@ -79,21 +187,66 @@ impl std::ops::IndexMut<ElemRangeName> for ElemRatios {
     }
 }
 
+/// Сколько удобрения обязательно должно попасть в смесь
+/// (например, чтобы израсходовать уже открытый мешок)
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub(crate) enum FertilizerMinimum {
+    /// Не меньше указанной массы, кг
+    Mass(f64),
+    /// Не меньше указанной доли смеси, %
+    Share(f64),
+}
+
+impl FertilizerMinimum {
+    /// Значение в единицах, в которых минимум задан (кг или %)
+    pub fn value(self) -> f64 {
+        match self {
+            Self::Mass(value) | Self::Share(value) => value,
+        }
+    }
+
+    /// Тот же вид минимума, но с другим значением
+    pub fn with_value(self, value: f64) -> Self {
+        match self {
+            Self::Mass(_) => Self::Mass(value),
+            Self::Share(_) => Self::Share(value),
+        }
+    }
+
+    /// Минимально допустимая доля удобрения в смеси массой `mass`
+    pub fn rate(self, mass: f64) -> f64 {
+        match self {
+            Self::Mass(value) => value / mass,
+            Self::Share(percent) => percent / 100.0,
+        }
+    }
+}
+
 /// Всё, что мы знаем про удобрение. Используется как для предопределённых (permanent) удобрений,
-/// так и для удобрений, добавленных пользователем (в последнем случае, содержание хлора или серы
-/// не указывается, так как обычно этой информации всё равно нет на упаковке)
+/// так и для удобрений, добавленных пользователем (в последнем случае содержание вторичных
+/// элементов часто не указывается, так как этой информации нет на упаковке, и считается нулевым)
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
-#[allow(non_snake_case)]
 pub(crate) struct Fertilizer {
     pub name: Cow<'static, str>,
-    pub N: f64,
-    pub P: f64,
-    pub K: f64,
-    pub Mg: f64,
-    pub with_Cl: bool,
-    pub with_S: bool,
+    /// Содержание элементов, % (в формах из справочника `nutrients::NUTRIENTS`).
+    /// Хранится, как и раньше, в полях `N`, `P`, ...; вторичных элементов
+    /// в старой истории добавленных удобрений нет, они считаются нулевыми.
+    #[serde(flatten)]
+    pub content: Amounts,
+    /// Содержание азота каждой формы, если оно известно. В ссылки не записывается:
+    /// для перманентных удобрений оно берётся из их таблицы.
+    #[serde(default)]
+    pub nitrogen_forms: Option<NitrogenForms>,
     /// Предельно доступное количество удобрения
     pub limit: Option<f64>,
+    /// Сколько удобрения смесь должна содержать как минимум.
+    /// В старой истории добавленных удобрений минимума нет.
+    #[serde(default)]
+    pub minimum: Option<FertilizerMinimum>,
+    /// Цена килограмма удобрения. Удобрения без цены считаются бесплатными
+    /// (например, уже купленными). В старой истории добавленных удобрений цены нет.
+    #[serde(default)]
+    pub price: Option<f64>,
     /// Идентификатор у удобрения, упрощающий последующую работу с ним.
     /// Он динамический, регенерируется заново при каждом перезапуске
     /// (если не считать перманентных удобрений), а значит сериализовать его бесполезно.
@@ -115,47 +268,65 @@ impl Fertilizer {
     }
 
     pub fn content_id(&self) -> String {
-        format!("{}_{}_{}_{}_{}", self.name, self.N, self.P, self.K, self.Mg)
+        let mut result = self.name.to_string();
+        for element in ElemName::MACRO {
+            write!(&mut result, "_{}", self[element]).expect("Content must be serializable");
+        }
+        result
     }
 
     /// Кодирует информацию об удобрении в нечто вроде
-    /// `fert=Азофоска:N:16.0,P:16.0,K:16.0,Mg:0.0,Cl:f,S:t`
+    /// `fert=Азофоска:N:16.0,P:16.0,K:16.0,Mg:0.0,Ca:0,S:0,Cl:8,Na:0,lim:2,min:1,price:150`
+    /// (минимальная доля смеси в процентах записывается как `minshare:25`)
     /// для последующей вставки в ссылки.
     pub fn urlencode(&self) -> String {
-        let cl_flag = if self.with_Cl { "t" } else { "f" };
-        let s_flag = if self.with_S { "t" } else { "f" };
-        let mut result = format!(
-            "{}:N:{},P:{},K:{},Mg:{},Cl:{},S:{}",
-            self.name, self.N, self.P, self.K, self.Mg, cl_flag, s_flag
-        );
+        let mut result = format!("{}:", self.name);
+        for (i, element) in ElemName::ALL.into_iter().enumerate() {
+            if i > 0 {
+                result.push(',');
+            }
+            write!(&mut result, "{}:{}", element.symbol(), self[element])
+                .expect("Content must be serializable");
+        }
         if let Some(lim) = self.limit {
             write!(&mut result, ",lim:{}", lim).expect("Limit must be serializable");
         }
+        match self.minimum {
+            Some(FertilizerMinimum::Mass(min)) => {
+                write!(&mut result, ",min:{}", min).expect("Minimum must be serializable")
+            }
+            Some(FertilizerMinimum::Share(share)) => {
+                write!(&mut result, ",minshare:{}", share).expect("Minimum must be serializable")
+            }
+            None => {}
+        }
+        if let Some(price) = self.price {
+            write!(&mut result, ",price:{}", price).expect("Price must be serializable");
+        }
         result
     }
+
+    /// Доля балласта: всё, что не является азотом и оксидами фосфора, калия и магния
+    pub fn ballast(&self) -> f64 {
+        1.0 - ElemName::MACRO
+            .iter()
+            .map(|element| self[*element])
+            .sum::<f64>()
+            / 100.0
+    }
 }
 
 impl std::ops::Index<ElemName> for Fertilizer {
     type Output = f64;
 
     fn index(&self, index: ElemName) -> &Self::Output {
-        match index {
-            ElemName::Nitrogen => &self.N,
-            ElemName::Phosphorus => &self.P,
-            ElemName::Potassium => &self.K,
-            ElemName::Magnesium => &self.Mg,
-        }
+        &self.content[index]
     }
 }
 
 impl std::ops::IndexMut<ElemName> for Fertilizer {
     fn index_mut(&mut self, index: ElemName) -> &mut Self::Output {
-        match index {
-            ElemName::Nitrogen => &mut self.N,
-            ElemName::Phosphorus => &mut self.P,
-            ElemName::Potassium => &mut self.K,
-            ElemName::Magnesium => &mut self.Mg,
-        }
+        &mut self.content[index]
     }
 }
 
@@ -163,13 +334,11 @@ impl Default for Fertilizer {
     fn default() -> Self {
         Self {
             name: Default::default(),
-            N: Default::default(),
-            P: Default::default(),
-            K: Default::default(),
-            Mg: Default::default(),
-            with_Cl: Default::default(),
-            with_S: Default::default(),
+            content: Default::default(),
+            nitrogen_forms: Default::default(),
             limit: Default::default(),
+            minimum: Default::default(),
+            price: Default::default(),
             id: Self::new_id(),
         }
     }
@@ -194,54 +363,27 @@ impl ElemRange {
 /// 1. хранит концентрации элементов (оксидов) в удобренииях,
 /// 2. также хранит абсолютные содержания этих элементов в килограммах, на последних стадиях
 ///    вывода результатов.
-#[derive(Default, Debug, PartialEq)]
-#[allow(non_snake_case)]
-pub(crate) struct Amounts {
-    pub N: f64,
-    pub P: f64,
-    pub K: f64,
-    pub Mg: f64,
-}
+pub(crate) type Amounts = NutrientMap<f64>;
 
-impl std::ops::Index<ElemName> for Amounts {
-    type Output = f64;
-
-    fn index(&self, index: ElemName) -> &Self::Output {
-        match index {
-            ElemName::Nitrogen => &self.N,
-            ElemName::Phosphorus => &self.P,
-            ElemName::Potassium => &self.K,
-            ElemName::Magnesium => &self.Mg,
-        }
-    }
+/// Недостача одного элемента в смеси
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Deficite {
+    /// Сколько элемента (оксида) не хватает в смеси заданной массы, кг
+    pub amount: f64,
+    /// Какую часть нужного количества элемента составляет недостача, %
+    pub percent: f64,
+    /// Постоянные удобрения (индексы в `consts::PERMANENT_FERTILIZERS`), добавление которых
+    /// восполняет недостачу, и сколько каждого из них окажется в смеси (кг).
+    /// Отсортированы по возрастанию массы: первое восполняет недостачу лучше всех.
+    pub remedies: Vec<(usize, f64)>,
 }
 
 /// При подсчёте результатов, хранит информацию о дефицитности элементов
-/// (true - значит, элемента в смеси недостаточно).
-#[derive(Debug, PartialEq)]
-#[allow(non_snake_case)]
-pub(crate) struct Deficites {
-    pub N: bool,
-    pub P: bool,
-    pub K: bool,
-    pub Mg: bool,
-}
+/// (`None` - значит, элемента в смеси достаточно).
+pub(crate) type Deficites = NutrientMap<Option<Deficite>>;
 
 impl Deficites {
     pub fn any(&self) -> bool {
-        self.N || self.P || self.K || self.Mg
-    }
-}
-
-impl std::ops::Index<ElemName> for Deficites {
-    type Output = bool;
-
-    fn index(&self, index: ElemName) -> &Self::Output {
-        match index {
-            ElemName::Nitrogen => &self.N,
-            ElemName::Phosphorus => &self.P,
-            ElemName::Potassium => &self.K,
-            ElemName::Magnesium => &self.Mg,
-        }
+        self.iter().any(|(_, deficite)| deficite.is_some())
     }
 }
@@ -1,114 +1,165 @@
 /// Реализация модифицированного симплекс-метода, описанного
 /// в книге Хемди А. Таха "Введение в исследование операций"
 /// [7-е издание] (стр. 329)
-// from base import SimplexMethod
-// import numpy as np
-// from math import factorial
-use nalgebra::{self, DMatrix, DVector};
-
-const MAX_COMBINATIONS: usize = 400000;
-const MAX_STEPS: usize = 1500;
-
-/// Вычисление всех возможных сочетаний без повторений.
-/// Используется при нахождении начального базисного решения.
-struct Combinator {
-    used: Vec<usize>,
-    n: usize,
-    k: usize,
-    first_step: bool,
-}
+///
+/// Начальное базисное решение подбирается двухэтапным методом (там же, стр. 118):
+/// на первом этапе минимизируется сумма искусственных переменных, добавленных
+/// в ограничения, не имеющие естественной базисной (дополнительной) переменной.
+use nalgebra::{self, DVector};
 
-impl Combinator {
-    fn new(n: usize, k: usize) -> Self {
-        Combinator { n, k, used: (0..k).collect(), first_step: true }
-    }
+use crate::calculator::lp_solver::{LPSolver, SolverBackend};
 
-    pub fn sequence_len(&self) -> usize {
-        fn factorial(n: usize) -> usize {
-            (2..=n).fold(1, usize::saturating_mul)
-        }
-        factorial(self.n) / (factorial(self.k) * factorial(self.n - self.k))
-    }
+pub(crate) const MAX_STEPS: usize = 1500;
 
-    fn next(&mut self) -> Option<&[usize]> {
-        if self.first_step {
-            self.first_step = false;
-            Some(&self.used[..])
-        } else {
-            for i in (0..self.k).rev() {
-                let max_current = if i + 1 == self.k {
-                    self.n - 1
-                } else {
-                    self.used[i + 1] - 1
-                };
-                if self.used[i] < max_current {
-                    self.used[i] += 1;
-                    for t in (i + 1)..self.k {
-                        self.used[t] = self.used[t - 1] + 1;
-                    }
-                    return Some(&self.used[..]);
-                }
-            }
-            None
-        }
+/// Допуски, с которыми решатели сравнивают величины с нулём. Значения по умолчанию
+/// рассчитаны на строки, которые `LPTask::add_constr` нормирует так, чтобы наибольший
+/// по модулю коэффициент был равен единице.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerances {
+    /// Остаточная сумма искусственных переменных после первого этапа, при превышении которой
+    /// задача считается несовместной
+    pub feasibility: f64,
+    /// Оценки z - c, не превышающие этого значения, считаются неположительными
+    /// (решение оптимально)
+    pub optimality: f64,
+    /// Наименьшее по модулю значение, допустимое для ведущего (разрешающего) элемента
+    pub pivot: f64,
+    /// Базисные переменные и отношения в пределах этого допуска от нуля считаются нулевыми
+    pub zero: f64,
+}
+
+impl Default for Tolerances {
+    fn default() -> Self {
+        Self { feasibility: 1e-9, optimality: 1e-9, pivot: 1e-12, zero: 1e-12 }
     }
 }
 
-#[derive(thiserror::Error, Debug)]
+/// Число вырожденных итераций подряд, после которого включается правило Бленда
+pub(crate) const MAX_DEGENERATE_STEPS: usize = 10;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
 pub enum SimplexError {
     // система составлена неверно, т.к. не содержит"
     //  небазисных переменных. увеличьте число дополнительных"
     // переменных (например, заменив равенства на неравенства).")
     #[error("problem is not feasible")]
     NotFeasible,
-    #[error("the problem is too big: {0}")]
-    BigTask(DMatrix<f64>),
     #[error("problem is not bounded")]
     Unbounded,
     #[error("maximum number of execution steps has been reached")]
     TooManySteps,
-    #[error("no base solution exists")]
-    NoBaseSolution,
+    #[error("basis matrix has become singular")]
+    SingularBasis,
 }
 
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum ConstraintOp {
     Equal,
     Less,
     Greater,
     GreaterOrEqual,
     LessOrEqual,
+    /// Двустороннее ограничение `right - range <= left * X <= right`
+    Range,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Constraint {
     pub left: DVector<f64>, // Row of the "A" matrix
     pub op: ConstraintOp,
     pub right: f64, // the "b" value from the right
+    /// Множитель, на который было умножено исходное ограничение при нормировке:
+    /// отрицательный, если ради положительной правой части менялся знак
+    pub(crate) scale: f64,
+    /// Ширина диапазона для `ConstraintOp::Range`
+    pub(crate) range: f64,
 }
 
 impl From<(&[f64], ConstraintOp, f64)> for Constraint {
     fn from(value: (&[f64], ConstraintOp, f64)) -> Self {
-        Self { left: DVector::from(value.0.to_vec()), op: value.1, right: value.2 }
+        Self {
+            left: DVector::from(value.0.to_vec()),
+            op: value.1,
+            right: value.2,
+            scale: 1.0,
+            range: 0.0,
+        }
     }
 }
 
 /// Задача линейного программирования
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LPTask {
-    func_vec: DVector<f64>,
-    constr: Vec<Constraint>,
+    pub(crate) func_vec: DVector<f64>,
+    pub(crate) constr: Vec<Constraint>,
+    /// Нижняя и верхняя границы каждой переменной (верхняя может быть бесконечной)
+    pub(crate) bounds: Vec<(f64, f64)>,
+    pub(crate) tolerances: Tolerances,
+    /// Решатель, которым задача решается методом `solve_min`
+    pub(crate) backend: SolverBackend,
 }
 
 impl LPTask {
     pub fn new(func_vec: &[f64]) -> Self {
-        Self { func_vec: DVector::from_row_slice(func_vec), constr: Default::default() }
+        Self {
+            func_vec: DVector::from_row_slice(func_vec),
+            constr: Default::default(),
+            bounds: vec![(0.0, f64::INFINITY); func_vec.len()],
+            tolerances: Default::default(),
+            backend: Default::default(),
+        }
+    }
+
+    /// Заменяет допуски, с которыми задача будет решаться
+    pub fn with_tolerances(mut self, tolerances: Tolerances) -> Self {
+        self.tolerances = tolerances;
+        self
+    }
+
+    /// Заменяет решатель, которым задача будет решаться
+    pub fn with_backend(mut self, backend: SolverBackend) -> Self {
+        self.backend = backend;
+        self
     }
 
-    /// Добавляет новое ограничение, попутно приводя его к форме с положительной правой частью."
+    /// Добавляет новое ограничение, попутно приводя его к форме с положительной правой частью
+    /// и нормируя так, чтобы наибольший по модулю коэффициент был равен единице.
+    /// Без нормировки строки с процентами содержания (сотые доли) и строки остатков
+    /// (коэффициенты порядка единицы) сравнивались бы с нулём с одним и тем же допуском.
     pub fn add_constr(&mut self, left: &[f64], op: ConstraintOp, right: f64) {
-        let mut constraint = Constraint { right, op, left: left.to_vec().into() };
+        let range = 0.0;
+        let constraint = Constraint { right, op, left: left.to_vec().into(), scale: 1.0, range };
+        self.push_constr(constraint);
+    }
+
+    /// Добавляет двустороннее ограничение `lower <= left * X <= upper`. Бесконечная граница
+    /// означает, что с этой стороны ограничения нет. В отличие от пары отдельных неравенств,
+    /// такая строка не увеличивает базис: её дополнительная переменная просто ограничена сверху.
+    pub fn add_range_constr(&mut self, left: &[f64], lower: f64, upper: f64) {
+        match (lower.is_finite(), upper.is_finite()) {
+            (true, true) if lower == upper => self.add_constr(left, ConstraintOp::Equal, upper),
+            (true, true) => self.push_constr(Constraint {
+                left: left.to_vec().into(),
+                op: ConstraintOp::Range,
+                right: upper,
+                scale: 1.0,
+                range: upper - lower,
+            }),
+            (true, false) => self.add_constr(left, ConstraintOp::GreaterOrEqual, lower),
+            (false, true) => self.add_constr(left, ConstraintOp::LessOrEqual, upper),
+            (false, false) => panic!("Двустороннее ограничение без границ"),
+        }
+    }
+
+    /// Задаёт границы переменной: `lower <= X[var] <= upper`. По умолчанию все переменные
+    /// лишь неотрицательны. Верхняя граница может быть бесконечной, нижняя - не меньше нуля.
+    pub fn set_bounds(&mut self, var: usize, lower: f64, upper: f64) {
+        assert!(lower >= 0.0, "Переменные задачи неотрицательны");
+        self.bounds[var] = (lower, upper);
+    }
+
+    fn push_constr(&mut self, mut constraint: Constraint) {
         assert_eq!(
             constraint.left.shape(),
             self.func_vec.shape(),
@@ -121,266 +172,77 @@ impl LPTask {
             for v in constraint.left.iter_mut() {
                 *v = -*v;
             }
-            constraint.right = -constraint.right;
+            // Для диапазона границы меняются местами: -upper <= -left * X <= -lower
+            constraint.right = match constraint.op {
+                ConstraintOp::Range => constraint.range - constraint.right,
+                _ => -constraint.right,
+            };
+            constraint.scale = -1.0;
             constraint.op = match constraint.op {
                 ConstraintOp::GreaterOrEqual => ConstraintOp::LessOrEqual,
                 ConstraintOp::LessOrEqual => ConstraintOp::GreaterOrEqual,
                 ConstraintOp::Less => ConstraintOp::Greater,
                 ConstraintOp::Greater => ConstraintOp::Less,
                 ConstraintOp::Equal => ConstraintOp::Equal,
+                ConstraintOp::Range => ConstraintOp::Range,
             };
         }
+        let max_coef = constraint.left.amax();
+        if max_coef > 0.0 {
+            constraint.left /= max_coef;
+            constraint.right /= max_coef;
+            constraint.range /= max_coef;
+            constraint.scale /= max_coef;
+        }
         self.constr.push(constraint);
     }
 
-    #[allow(non_snake_case)]
+    /// Решает задачу минимизации выбранным для неё решателем (по умолчанию -
+    /// `revised_simplex::RevisedSimplex`, которому не нужно обращать базисную матрицу
+    /// на каждой итерации)
     pub fn solve_min(&self) -> Result<LPSolution, SimplexError> {
-        let A = self.get_source_matrix();
-        let mut basis = find_basis_solution(&A)?;
-        if basis.non_basis_cols.is_empty() {
-            return Err(SimplexError::NotFeasible);
-        }
-        let mut counter = 0_usize;
-        loop {
-            if counter > MAX_STEPS {
-                return Err(SimplexError::TooManySteps);
-            }
-            let A_last_row = A.row(A.nrows() - 1);
-            let C_B = A_last_row.select_columns(&basis.basis_cols);
-            let A_but_last_rows = A.rows(0, A.nrows() - 1);
-            let B = A_but_last_rows.select_columns(&basis.basis_cols);
-            let b = A_but_last_rows.column(A.ncols() - 1);
-            let inv_B = B.clone_owned().try_inverse().expect(
-                "The matrix is expected to be invertible, as verified by find_basis_solution",
-            );
-            let X_B = &inv_B * b;
-            let noB = A_but_last_rows.select_columns(&basis.non_basis_cols);
-            let noC = A_last_row.select_columns(&basis.non_basis_cols);
-            let z_minus_c = &C_B * &inv_B * &noB - &noC;
-            if z_minus_c.iter().all(|i| *i <= 0.0) {
-                // Если для всех небазисных переменных величины z - с >= 0
-                // в задаче максимизации или z. - с <= 0
-                // в задаче минимизации, то вычисления заканчиваются, так
-                // как получено оптимальное решение
-                let func_val = &C_B * &X_B;
-                let solution = X_B.transpose();
-
-                return Ok(LPSolution {
-                    function_value: func_val[(0, 0)],
-                    params: (0..self.func_vec.len())
-                        .map(|i| match basis.basis_cols.iter().position(|n| *n == i) {
-                            Some(var_pos) => solution[(0, var_pos)],
-                            None => 0.0,
-                        })
-                        .collect(),
-                });
-            }
-            // Ищем вводимую переменную
-            let potential_intr_vec_ind = z_minus_c
-                .row(0)
-                .iter()
-                .enumerate()
-                .filter(|(_, v)| **v > 0.0)
-                .max_by(|(_, v1), (_, v2)| v1.total_cmp(v2))
-                .map(|(i, _)| i);
-            let intr_vec_ind = match potential_intr_vec_ind {
-                None => return Err(SimplexError::NotFeasible),
-                Some(idx) if z_minus_c.row(0)[idx].is_nan() => {
-                    return Err(SimplexError::NotFeasible);
-                }
-                Some(idx) => idx,
-            };
-            // Ищем исключаемую переменную
-            let N = &inv_B * A_but_last_rows.column(basis.non_basis_cols[intr_vec_ind]);
-            let candidates = X_B.component_div(&N);
-            let excl_vec_ind = (0..candidates.nrows())
-                .map(|i| (i, candidates.row(i)[0]))
-                .filter(|(_, c)| c.is_finite() && *c > 0.0)
-                .min_by(|(_, c1), (_, c2)| c1.total_cmp(c2))
-                .map(|(i, _)| i)
-                .ok_or(SimplexError::NotFeasible)?;
-            let m_val = candidates[(excl_vec_ind, 0)];
-            if m_val.is_nan() {
-                return Err(SimplexError::Unbounded);
-            }
-            // Обмен между базисными и небазисными векторами
-            basis
-                .basis_cols
-                .push(basis.non_basis_cols.remove(intr_vec_ind));
-            basis
-                .non_basis_cols
-                .push(basis.basis_cols.remove(excl_vec_ind));
-            counter += 1;
-        }
+        self.solve_min_with(self.backend.solver())
     }
 
-    /// Возвращает исходную матрицу A, с уже добавленными
-    /// фиктивными переменными
-    #[allow(non_snake_case)]
-    fn get_source_matrix(&self) -> DMatrix<f64> {
-        let fict_vars_count = self
-            .constr
-            .iter()
-            .filter(|row| {
-                matches!(
-                    row.op,
-                    ConstraintOp::LessOrEqual | ConstraintOp::GreaterOrEqual
-                )
-            })
-            .count();
-        let mut M = DMatrix::<f64>::zeros(
-            self.constr.len() + 1,
-            self.func_vec.len() + fict_vars_count + 1,
-        );
-        let mut fict_ind = 0;
-        for (i, Constraint { left, op, right }) in self.constr.iter().enumerate() {
-            let extra_columns = match op {
-                ConstraintOp::Greater | ConstraintOp::GreaterOrEqual => {
-                    let extra = DVector::<f64>::from_fn(fict_vars_count, |a, _| {
-                        if a == fict_ind {
-                            -1.0
-                        } else {
-                            0.0
-                        }
-                    });
-                    fict_ind += 1;
-                    extra
-                }
-                ConstraintOp::Less | ConstraintOp::LessOrEqual => {
-                    let extra = DVector::<f64>::from_fn(fict_vars_count, |a, _| {
-                        if a == fict_ind {
-                            1.0
-                        } else {
-                            0.0
-                        }
-                    });
-                    fict_ind += 1;
-                    extra
-                }
-                ConstraintOp::Equal => DVector::<f64>::zeros(fict_vars_count),
-            };
-            let row_vec = DVector::from_iterator(
-                left.len() + extra_columns.len() + 1,
-                left.iter()
-                    .chain(extra_columns.iter())
-                    .copied()
-                    .chain(std::iter::once(*right)),
-            )
-            .transpose();
-            M.set_row(i, &row_vec);
-        }
-
-        let last_row = DVector::from_iterator(
-            self.func_vec.len() + fict_vars_count + 1,
-            self.func_vec
-                .iter()
-                .copied()
-                .chain(std::iter::repeat(0.0).take(fict_vars_count + 1)),
-        )
-        .transpose();
-        M.set_row(self.constr.len(), &last_row);
-        M
+    /// Решает задачу минимизации указанным решателем
+    pub fn solve_min_with(&self, solver: &dyn LPSolver) -> Result<LPSolution, SimplexError> {
+        solver.solve_min(self)
     }
 }
 
-#[allow(dead_code)]
-#[derive(Clone)]
-struct BasisSolution {
-    solution: DMatrix<f64>,
-    basis_cols: Vec<usize>,
-    non_basis_cols: Vec<usize>,
-}
-
-/// Подбор начального базисного решения. Это набор векторов, входящих
-/// в базис на первой итерации модифицированного симплекс-метода.
-/// Матрица базисных векторов должна иметь обратную (det(B) != 0)
-/// и при решении BX=b вектор X не должен содержать отрицательных элементов
-/// (т.к. x1,x2,x3... >=0).
-#[allow(non_snake_case)]
-fn find_basis_solution(A: &DMatrix<f64>) -> Result<BasisSolution, SimplexError> {
-    // from numpy.linalg import inv, det
-    let basis_size = A.nrows() - 1;
-    // Размер матрицы базиса должен быть равен
-    // числу элементов правой части системы
-    // Перебор всех сочетаний (без повторений) векторов,
-    // в поисках базисного сочетания
-    //
-    // taking the last column vector, shourt of 1 last element
-    let A_but_last_row = A.rows(0, A.nrows() - 1);
-    let b = A_but_last_row.column(A.ncols() - 1);
-
-    let mut combinator = Combinator::new(A.ncols() - 1, basis_size);
-    if combinator.sequence_len() > MAX_COMBINATIONS {
-        return Err(SimplexError::BigTask(A.clone()));
-    }
-    while let Some(basis_cols) = combinator.next() {
-        let B = A_but_last_row.select_columns(basis_cols);
-        let B_det = B.determinant();
-        if B_det.abs() > f64::MIN_POSITIVE {
-            if let Some(B_inv) = B.clone_owned().try_inverse() {
-                let Xn = &B_inv * b;
-                let Xn_min = Xn
-                    .iter()
-                    .min_by(|a, b| a.total_cmp(b))
-                    .copied()
-                    .unwrap_or_default();
-                if Xn_min >= 0.0 {
-                    let var_ids = 0..(A.ncols() - 1);
-                    let non_basis_cols = var_ids.filter(|i| !basis_cols.contains(i)).collect();
-                    return Ok(BasisSolution {
-                        non_basis_cols,
-                        solution: B,
-                        basis_cols: basis_cols.into(),
-                    });
-                }
-            }
-        }
-    }
-    Err(SimplexError::NoBaseSolution)
+/// Правило выбора вводимой и исключаемой переменных
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum PivotRule {
+    /// Вводится переменная с наибольшей оценкой z - c. Обычно сходится быстрее всего,
+    /// но в вырожденных вершинах может зациклиться.
+    Dantzig,
+    /// Правило Бленда: из всех подходящих переменных выбирается переменная с наименьшим
+    /// номером. Гарантирует отсутствие зацикливания, хотя и требует больше итераций.
+    Bland,
 }
 
 #[derive(Debug, Clone)]
 pub struct LPSolution {
     pub function_value: f64,
     pub params: Vec<f64>,
+    /// Двойственные оценки (теневые цены) ограничений, в порядке их добавления: на сколько
+    /// изменится значение функции при увеличении правой части ограничения на единицу.
+    /// Ненулевая оценка означает, что ограничение является связывающим.
+    pub duals: Vec<f64>,
+    /// Приведённые стоимости переменных: на сколько увеличится значение функции при вводе
+    /// в решение единицы переменной. Для базисных переменных равны нулю.
+    pub reduced_costs: Vec<f64>,
 }
 
 #[cfg(test)]
 mod test {
-    use super::{Combinator, ConstraintOp, LPTask};
+    use super::{ConstraintOp, LPTask, SimplexError, Tolerances};
 
-    const E_MAX: f64 = f64::EPSILON;
+    // Порядок перебора базисов влияет на накопленную ошибку округления,
+    // поэтому сравнение ведётся с точностью, заведомо превышающей её.
+    const E_MAX: f64 = 1e-12;
     const EXPECT_SOLUTION: &str = "Must be solvable";
 
-    #[test]
-    fn test_combinator() {
-        let n = 5;
-        let k = 3;
-        let num_expected_combinations = 10;
-        let mut c = Combinator::new(n, k);
-        assert_eq!(c.sequence_len(), num_expected_combinations);
-        let mut result = vec![];
-        while let Some(v) = c.next() {
-            result.push(Vec::from(v));
-        }
-        assert_eq!(result.len(), num_expected_combinations);
-        assert_eq!(
-            result,
-            vec![
-                [0, 1, 2],
-                [0, 1, 3],
-                [0, 1, 4],
-                [0, 2, 3],
-                [0, 2, 4],
-                [0, 3, 4],
-                [1, 2, 3],
-                [1, 2, 4],
-                [1, 3, 4],
-                [2, 3, 4]
-            ]
-        );
-    }
-
     fn vec_diff(v1: &[f64], v2: &[f64]) -> f64 {
         assert_eq!(v1.len(), v2.len());
         v1.iter()
@@ -491,4 +353,138 @@ mod test {
             ) < E_MAX
         );
     }
+
+    #[test]
+    fn test_not_feasible() {
+        let mut task = LPTask::new(&[1., 1.]);
+        task.add_constr(&[1., 1.], ConstraintOp::LessOrEqual, 1.);
+        task.add_constr(&[1., 1.], ConstraintOp::GreaterOrEqual, 2.);
+        assert!(matches!(task.solve_min(), Err(SimplexError::NotFeasible)));
+    }
+
+    #[test]
+    fn test_redundant_equality() {
+        // Второе равенство линейно зависит от первого, и его искусственная переменная
+        // не может быть выведена из базиса после первого этапа.
+        let mut task = LPTask::new(&[1., 2.]);
+        task.add_constr(&[1., 1.], ConstraintOp::Equal, 2.);
+        task.add_constr(&[2., 2.], ConstraintOp::Equal, 4.);
+        let solution = task.solve_min().expect(EXPECT_SOLUTION);
+        assert!((solution.function_value - 2.0).abs() < E_MAX);
+        assert!(vec_diff(&solution.params, &[2.0, 0.0]) < E_MAX);
+    }
+
+    #[test]
+    fn test_duals() {
+        let mut task = LPTask::new(&[1., 1.]);
+        task.add_constr(&[3., 2.], ConstraintOp::GreaterOrEqual, 2.);
+        task.add_constr(&[1., 4.], ConstraintOp::GreaterOrEqual, 3.);
+        // То же самое ограничение, записанное с отрицательной правой частью
+        task.add_constr(&[-3., -2.], ConstraintOp::LessOrEqual, -2.);
+        task.add_constr(&[1., 0.], ConstraintOp::LessOrEqual, 10.);
+        let solution = task.solve_min().expect(EXPECT_SOLUTION);
+        assert!((solution.function_value - 0.9).abs() < E_MAX);
+        let first_dual = solution.duals[0] - solution.duals[2];
+        assert!((first_dual - 0.3).abs() < E_MAX);
+        assert!((solution.duals[1] - 0.1).abs() < E_MAX);
+        assert!(solution.duals[3].abs() < E_MAX);
+        assert!(vec_diff(&solution.reduced_costs, &[0.0, 0.0]) < E_MAX);
+    }
+
+    #[test]
+    fn test_reduced_costs() {
+        // Третья переменная в решение не войдёт: она дороже комбинации первых двух
+        let mut task = LPTask::new(&[1., 1., 3.]);
+        task.add_constr(&[1., 0., 1.], ConstraintOp::GreaterOrEqual, 1.);
+        task.add_constr(&[0., 1., 1.], ConstraintOp::GreaterOrEqual, 1.);
+        let solution = task.solve_min().expect(EXPECT_SOLUTION);
+        assert!((solution.function_value - 2.0).abs() < E_MAX);
+        assert!(vec_diff(&solution.duals, &[1.0, 1.0]) < E_MAX);
+        assert!(vec_diff(&solution.reduced_costs, &[0.0, 0.0, 1.0]) < E_MAX);
+    }
+
+    #[test]
+    fn test_tiny_coefficients() {
+        // Без нормировки строк все коэффициенты меньше допуска для ведущего элемента
+        let mut task = LPTask::new(&[1., 1.]);
+        task.add_constr(&[1e-13, 2e-13], ConstraintOp::GreaterOrEqual, 1e-13);
+        task.add_constr(&[3e-13, 1e-13], ConstraintOp::GreaterOrEqual, 1e-13);
+        let solution = task.solve_min().expect(EXPECT_SOLUTION);
+        assert!((solution.function_value - 0.6).abs() < E_MAX);
+        assert!(vec_diff(&solution.params, &[0.2, 0.4]) < E_MAX);
+        // Двойственные оценки относятся к исходной, не нормированной записи ограничений
+        assert!((solution.duals[0] * 1e-13 - 0.4).abs() < E_MAX);
+        assert!((solution.duals[1] * 1e-13 - 0.2).abs() < E_MAX);
+    }
+
+    #[test]
+    fn test_feasibility_tolerance() {
+        let mut task = LPTask::new(&[1.]);
+        task.add_constr(&[1.], ConstraintOp::LessOrEqual, 1.);
+        task.add_constr(&[1.], ConstraintOp::GreaterOrEqual, 1. + 1e-7);
+        assert!(matches!(task.solve_min(), Err(SimplexError::NotFeasible)));
+        let tolerances = Tolerances { feasibility: 1e-6, ..Default::default() };
+        let solution = task
+            .with_tolerances(tolerances)
+            .solve_min()
+            .expect(EXPECT_SOLUTION);
+        assert!((solution.function_value - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_range_constraint() {
+        // 1 <= x + y <= 3: при минимизации связывает нижняя граница, при максимизации - верхняя
+        let mut task = LPTask::new(&[1., 2.]);
+        task.add_range_constr(&[1., 1.], 1., 3.);
+        let solution = task.solve_min().expect(EXPECT_SOLUTION);
+        assert!((solution.function_value - 1.0).abs() < E_MAX);
+        assert!(vec_diff(&solution.params, &[1.0, 0.0]) < E_MAX);
+        assert!((solution.duals[0] - 1.0).abs() < E_MAX);
+
+        let mut task = LPTask::new(&[-1., -2.]);
+        task.add_range_constr(&[1., 1.], 1., 3.);
+        let solution = task.solve_min().expect(EXPECT_SOLUTION);
+        assert!((solution.function_value - (-6.0)).abs() < E_MAX);
+        assert!(vec_diff(&solution.params, &[0.0, 3.0]) < E_MAX);
+        assert!((solution.duals[0] - (-2.0)).abs() < E_MAX);
+        assert!(vec_diff(&solution.reduced_costs, &[1.0, 0.0]) < E_MAX);
+    }
+
+    #[test]
+    fn test_negative_range() {
+        let mut task = LPTask::new(&[1., 1.]);
+        task.add_range_constr(&[-1., -2.], -4., -2.);
+        let solution = task.solve_min().expect(EXPECT_SOLUTION);
+        assert!((solution.function_value - 1.0).abs() < E_MAX);
+        assert!(vec_diff(&solution.params, &[0.0, 1.0]) < E_MAX);
+        assert!((solution.duals[0] - (-0.5)).abs() < E_MAX);
+    }
+
+    #[test]
+    fn test_variable_bounds() {
+        // Без границ выгоднее всего взять только первую переменную
+        let mut task = LPTask::new(&[1., 2., 3.]);
+        task.add_constr(&[1., 1., 1.], ConstraintOp::GreaterOrEqual, 4.);
+        task.set_bounds(0, 0., 1.5);
+        task.set_bounds(2, 0.5, f64::INFINITY);
+        let solution = task.solve_min().expect(EXPECT_SOLUTION);
+        assert!((solution.function_value - 7.0).abs() < E_MAX);
+        assert!(vec_diff(&solution.params, &[1.5, 2.0, 0.5]) < E_MAX);
+        assert!((solution.duals[0] - 2.0).abs() < E_MAX);
+        // Переменная на верхней границе выгодна, на нижней - невыгодна
+        assert!(vec_diff(&solution.reduced_costs, &[-1.0, 0.0, 1.0]) < E_MAX);
+    }
+
+    #[test]
+    fn test_fixed_variables() {
+        let mut task = LPTask::new(&[1., 1.]);
+        task.add_constr(&[1., 1.], ConstraintOp::Equal, 2.);
+        task.set_bounds(0, 0., 0.);
+        let solution = task.solve_min().expect(EXPECT_SOLUTION);
+        assert!(vec_diff(&solution.params, &[0.0, 2.0]) < E_MAX);
+        task.set_bounds(1, 0., 1.);
+        assert!(matches!(task.solve_min(), Err(SimplexError::NotFeasible)));
+        task.set_bounds(0, 1.5, 1.);
+        assert!(matches!(task.solve_min(), Err(SimplexError::NotFeasible)));
+    }
 }
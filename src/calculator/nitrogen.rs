@@ -0,0 +1,159 @@
+//! Формы азота. Аммиачная селитра, карбамид и калийная селитра дают одинаковый "азот"
+//! в составе смеси, но растения усваивают его по-разному: нитратный - сразу,
+//! аммонийный - подкисляя почву (его избыток угнетает рассаду), а амидный (мочевина) -
+//! лишь после того, как почвенные бактерии переведут его в аммонийный.
+
+use serde::{Deserialize, Serialize};
+
+use super::Fertilizer;
+
+/// Форма, в которой азот содержится в удобрении
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum NitrogenForm {
+    Ammonium,
+    Nitrate,
+    Urea,
+}
+
+impl NitrogenForm {
+    pub const ALL: [NitrogenForm; 3] = [
+        NitrogenForm::Ammonium,
+        NitrogenForm::Nitrate,
+        NitrogenForm::Urea,
+    ];
+
+    /// Название формы: прилагательное к слову "азот", со строчной буквы
+    pub fn title(self) -> &'static str {
+        match self {
+            NitrogenForm::Ammonium => "аммонийный",
+            NitrogenForm::Nitrate => "нитратный",
+            NitrogenForm::Urea => "амидный",
+        }
+    }
+
+    /// То же в родительном падеже ("доля аммонийного азота")
+    pub fn genitive_title(self) -> &'static str {
+        match self {
+            NitrogenForm::Ammonium => "аммонийного",
+            NitrogenForm::Nitrate => "нитратного",
+            NitrogenForm::Urea => "амидного",
+        }
+    }
+
+    fn urlencode(self) -> &'static str {
+        match self {
+            NitrogenForm::Ammonium => "NH4",
+            NitrogenForm::Nitrate => "NO3",
+            NitrogenForm::Urea => "urea",
+        }
+    }
+
+    fn from_url(text: &str) -> Option<Self> {
+        NitrogenForm::ALL
+            .into_iter()
+            .find(|form| form.urlencode() == text)
+    }
+}
+
+/// Содержание азота каждой формы, % от массы удобрения (или концентрация в смеси).
+/// В сумме - весь азот удобрения, если формы известны для всего азота.
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct NitrogenForms {
+    pub ammonium: f64,
+    pub nitrate: f64,
+    pub urea: f64,
+}
+
+impl NitrogenForms {
+    /// Азот всех известных форм
+    pub fn total(&self) -> f64 {
+        self.ammonium + self.nitrate + self.urea
+    }
+}
+
+impl std::ops::Index<NitrogenForm> for NitrogenForms {
+    type Output = f64;
+
+    fn index(&self, index: NitrogenForm) -> &Self::Output {
+        match index {
+            NitrogenForm::Ammonium => &self.ammonium,
+            NitrogenForm::Nitrate => &self.nitrate,
+            NitrogenForm::Urea => &self.urea,
+        }
+    }
+}
+
+impl std::ops::IndexMut<NitrogenForm> for NitrogenForms {
+    fn index_mut(&mut self, index: NitrogenForm) -> &mut Self::Output {
+        match index {
+            NitrogenForm::Ammonium => &mut self.ammonium,
+            NitrogenForm::Nitrate => &mut self.nitrate,
+            NitrogenForm::Urea => &mut self.urea,
+        }
+    }
+}
+
+/// Предельная доля одной из форм азота (обычно аммонийной или амидной) в азоте смеси.
+/// Азот удобрений, формы которого неизвестны (добавленных вручную, уже смешанного),
+/// в ограничении не участвует: доля считается только среди азота известных форм.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) struct NitrogenFormLimit {
+    pub form: NitrogenForm,
+    /// Наибольшая доля формы, % азота известных форм
+    pub max_share: f64,
+}
+
+impl NitrogenFormLimit {
+    /// Коэффициент удобрения в ограничении $(F_1 - s * N_1) * X_1 + .. <= 0$, где $F_i$ -
+    /// содержание азота ограничиваемой формы в удобрении, $N_i$ - азота всех известных форм
+    /// (оба в % от массы удобрения), а $s$ - предельная доля в долях единицы
+    pub fn coefficient(&self, fertilizer: &Fertilizer) -> f64 {
+        fertilizer.nitrogen_forms.map_or(0.0, |forms| {
+            forms[self.form] - self.max_share / 100.0 * forms.total()
+        })
+    }
+
+    /// Кодирует ограничение в нечто вроде `NH4:50`
+    pub fn urlencode(&self) -> String {
+        format!("{}:{}", self.form.urlencode(), self.max_share)
+    }
+
+    pub fn from_url(text: &str) -> Option<Self> {
+        let (form, share) = text.split_once(':')?;
+        let max_share = share.parse::<f64>().ok()?;
+        (0.0..=100.0)
+            .contains(&max_share)
+            .then_some(Self { form: NitrogenForm::from_url(form)?, max_share })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{NitrogenForm, NitrogenFormLimit};
+    use crate::calculator::consts::PERMANENT_FERTILIZERS;
+    use crate::calculator::ElemName;
+
+    /// Формы азота перманентных удобрений в сумме дают весь их азот
+    #[test]
+    fn test_permanent_forms_are_complete() {
+        for fertilizer in PERMANENT_FERTILIZERS {
+            match fertilizer.nitrogen_forms {
+                Some(forms) => assert!(
+                    (forms.total() - fertilizer[ElemName::Nitrogen]).abs() < 1e-9,
+                    "{}",
+                    fertilizer.name
+                ),
+                None => assert_eq!(fertilizer[ElemName::Nitrogen], 0.0, "{}", fertilizer.name),
+            }
+        }
+    }
+
+    #[test]
+    fn test_limit_url() {
+        let limit = NitrogenFormLimit { form: NitrogenForm::Urea, max_share: 30.0 };
+        assert_eq!(limit.urlencode(), "urea:30");
+        assert_eq!(NitrogenFormLimit::from_url(&limit.urlencode()), Some(limit));
+        assert_eq!(NitrogenFormLimit::from_url("NH4:150"), None);
+        assert_eq!(NitrogenFormLimit::from_url("N2:10"), None);
+    }
+}
@@ -0,0 +1,306 @@
+//! Справочник элементов питания. Всё, что отличает один элемент от другого (обозначение,
+//! форма, в которой он указывается на упаковке, название, цвет в таблицах), хранится здесь,
+//! а составы удобрений, количества и дефициты - это просто значения, сопоставленные
+//! каждой записи справочника (`NutrientMap`).
+
+use std::fmt;
+
+use serde::de::{IgnoredAny, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::ElemRange;
+
+/// Описание одного элемента питания
+#[derive(Debug, PartialEq)]
+pub(crate) struct NutrientInfo {
+    /// Обозначение элемента в ссылках и в формулах (CaO и Na2O обозначаются как Ca и Na)
+    pub symbol: &'static str,
+    /// Форма, в которой содержание элемента указывается на упаковке удобрения.
+    /// Цифры при выводе становятся нижними индексами.
+    pub oxide: &'static str,
+    /// Массовая доля самого элемента в форме `oxide` (единица, если они совпадают):
+    /// 10% P2O5 - это 4.36% фосфора
+    pub elemental_share: f64,
+    /// Название в именительном падеже, со строчной буквы
+    pub name: &'static str,
+    /// Название в родительном падеже ("не хватает азота"), со строчной буквы
+    pub genitive_name: &'static str,
+    /// CSS-класс, задающий цвет элемента
+    pub css_class: &'static str,
+    /// CSS-класс поля ввода содержания элемента
+    pub input_css_class: &'static str,
+    /// Соотношение к фосфору, предлагаемое по умолчанию для элементов, соотношение которых
+    /// ограничивается по желанию. У макроэлементов соотношения задаются всегда, отдельно.
+    pub default_ratio: Option<ElemRange>,
+}
+
+/// Число элементов в справочнике
+pub(crate) const NUTRIENT_COUNT: usize = 8;
+
+/// Справочник элементов. Порядок записей совпадает с идентификаторами `ElemName`,
+/// в нём же элементы выводятся в таблицах и записываются в ссылки.
+pub(crate) const NUTRIENTS: [NutrientInfo; NUTRIENT_COUNT] = [
+    NutrientInfo {
+        symbol: "N",
+        oxide: "N",
+        elemental_share: 1.0,
+        name: "азот",
+        genitive_name: "азота",
+        css_class: "nutrient-N",
+        input_css_class: "nutrient-N-input",
+        default_ratio: None,
+    },
+    NutrientInfo {
+        symbol: "P",
+        oxide: "P2O5",
+        elemental_share: 0.4364,
+        name: "фосфор",
+        genitive_name: "фосфора",
+        css_class: "nutrient-P",
+        input_css_class: "nutrient-P-input",
+        default_ratio: None,
+    },
+    NutrientInfo {
+        symbol: "K",
+        oxide: "K2O",
+        elemental_share: 0.8301,
+        name: "калий",
+        genitive_name: "калия",
+        css_class: "nutrient-K",
+        input_css_class: "nutrient-K-input",
+        default_ratio: None,
+    },
+    NutrientInfo {
+        symbol: "Mg",
+        oxide: "MgO",
+        elemental_share: 0.6030,
+        name: "магний",
+        genitive_name: "магния",
+        css_class: "nutrient-Mg",
+        input_css_class: "nutrient-Mg-input",
+        default_ratio: None,
+    },
+    NutrientInfo {
+        symbol: "Ca",
+        oxide: "CaO",
+        elemental_share: 0.7147,
+        name: "кальций",
+        genitive_name: "кальция",
+        css_class: "nutrient-Ca",
+        input_css_class: "nutrient-Ca-input",
+        default_ratio: Some(ElemRange { from: 0.5, to: 2.0 }),
+    },
+    NutrientInfo {
+        symbol: "S",
+        oxide: "S",
+        elemental_share: 1.0,
+        name: "сера",
+        genitive_name: "серы",
+        css_class: "nutrient-S",
+        input_css_class: "nutrient-S-input",
+        default_ratio: Some(ElemRange { from: 0.3, to: 1.5 }),
+    },
+    NutrientInfo {
+        symbol: "Cl",
+        oxide: "Cl",
+        elemental_share: 1.0,
+        name: "хлор",
+        genitive_name: "хлора",
+        css_class: "nutrient-Cl",
+        input_css_class: "nutrient-Cl-input",
+        default_ratio: Some(ElemRange { from: 0.01, to: 0.5 }),
+    },
+    NutrientInfo {
+        symbol: "Na",
+        oxide: "Na2O",
+        elemental_share: 0.7419,
+        name: "натрий",
+        genitive_name: "натрия",
+        css_class: "nutrient-Na",
+        input_css_class: "nutrient-Na-input",
+        default_ratio: Some(ElemRange { from: 0.01, to: 0.3 }),
+    },
+];
+
+/// Идентификатор элемента питания - номер его записи в `NUTRIENTS`.
+/// Используется для индексации в составах удобрений, дефицитах, и т.п.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub(crate) struct ElemName(usize);
+
+#[allow(non_upper_case_globals)]
+impl ElemName {
+    pub const Nitrogen: ElemName = ElemName(0);
+    pub const Phosphorus: ElemName = ElemName(1);
+    pub const Potassium: ElemName = ElemName(2);
+    pub const Magnesium: ElemName = ElemName(3);
+    pub const Calcium: ElemName = ElemName(4);
+    pub const Sulfur: ElemName = ElemName(5);
+    pub const Chlorine: ElemName = ElemName(6);
+
+    /// Все элементы справочника, по порядку
+    pub const ALL: [ElemName; NUTRIENT_COUNT] = {
+        let mut all = [ElemName(0); NUTRIENT_COUNT];
+        let mut idx = 0;
+        while idx < NUTRIENT_COUNT {
+            all[idx] = ElemName(idx);
+            idx += 1;
+        }
+        all
+    };
+
+    /// Макроэлементы, соотношения которых к фосфору задаются всегда
+    pub const MACRO: [ElemName; 4] = [
+        ElemName::Nitrogen,
+        ElemName::Phosphorus,
+        ElemName::Potassium,
+        ElemName::Magnesium,
+    ];
+
+    /// Вторичные элементы: те, соотношения которых к фосфору можно ограничить по желанию
+    pub fn secondary() -> impl Iterator<Item = ElemName> {
+        ElemName::ALL
+            .into_iter()
+            .filter(|element| element.info().default_ratio.is_some())
+    }
+
+    pub fn info(self) -> &'static NutrientInfo {
+        &NUTRIENTS[self.0]
+    }
+
+    pub fn symbol(self) -> &'static str {
+        self.info().symbol
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<ElemName> {
+        ElemName::ALL
+            .into_iter()
+            .find(|element| element.symbol() == symbol)
+    }
+}
+
+/// Значения, сопоставленные каждому элементу справочника
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub(crate) struct NutrientMap<T>([T; NUTRIENT_COUNT]);
+
+impl<T> NutrientMap<T> {
+    pub fn iter(&self) -> impl Iterator<Item = (ElemName, &T)> + '_ {
+        ElemName::ALL.into_iter().zip(self.0.iter())
+    }
+}
+
+impl NutrientMap<f64> {
+    /// Содержания, в которых указаны лишь ненулевые элементы
+    pub const fn with(amounts: &[(ElemName, f64)]) -> Self {
+        let mut result = [0.0; NUTRIENT_COUNT];
+        let mut idx = 0;
+        while idx < amounts.len() {
+            let (element, amount) = amounts[idx];
+            result[element.0] = amount;
+            idx += 1;
+        }
+        Self(result)
+    }
+}
+
+impl<T> std::ops::Index<ElemName> for NutrientMap<T> {
+    type Output = T;
+
+    fn index(&self, index: ElemName) -> &Self::Output {
+        &self.0[index.0]
+    }
+}
+
+impl<T> std::ops::IndexMut<ElemName> for NutrientMap<T> {
+    fn index_mut(&mut self, index: ElemName) -> &mut Self::Output {
+        &mut self.0[index.0]
+    }
+}
+
+/// Содержания сериализуются как поля `N`, `P`, ... - так удобрения хранились и раньше,
+/// когда каждый элемент был отдельным полем структуры
+impl Serialize for NutrientMap<f64> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(NUTRIENT_COUNT))?;
+        for (element, amount) in self.iter() {
+            map.serialize_entry(element.symbol(), amount)?;
+        }
+        map.end()
+    }
+}
+
+/// Отсутствующие элементы считаются нулевыми, а незнакомые поля (например, флаги хлора
+/// и серы из старой истории удобрений) пропускаются
+impl<'de> Deserialize<'de> for NutrientMap<f64> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AmountsVisitor;
+
+        impl<'de> Visitor<'de> for AmountsVisitor {
+            type Value = NutrientMap<f64>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map of nutrient symbols to amounts")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut result = NutrientMap::default();
+                while let Some(key) = access.next_key::<String>()? {
+                    match ElemName::from_symbol(&key) {
+                        Some(element) => result[element] = access.next_value()?,
+                        None => {
+                            access.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(result)
+            }
+        }
+
+        deserializer.deserialize_map(AmountsVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ElemName, NutrientMap, NUTRIENTS};
+    use crate::calculator::consts::PERMANENT_FERTILIZERS;
+    use crate::calculator::Fertilizer;
+
+    #[test]
+    fn test_registry_is_consistent() {
+        for (idx, element) in ElemName::ALL.into_iter().enumerate() {
+            assert_eq!(element.info(), &NUTRIENTS[idx]);
+            assert_eq!(ElemName::from_symbol(element.symbol()), Some(element));
+        }
+        for element in ElemName::MACRO {
+            assert!(element.info().default_ratio.is_none());
+        }
+        assert!(ElemName::secondary().all(|element| !ElemName::MACRO.contains(&element)));
+    }
+
+    /// Удобрения в истории добавленных хранятся так же, как и до появления справочника
+    #[test]
+    fn test_fertilizer_serialization() {
+        let azofoska = PERMANENT_FERTILIZERS[2].clone();
+        let json = serde_json::to_value(&azofoska).unwrap();
+        assert_eq!(json["N"], 16.0);
+        assert_eq!(json["Cl"], 8.0);
+        let restored: Fertilizer = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, azofoska);
+
+        let legacy: Fertilizer = serde_json::from_str(
+            r#"{"name":"Старое","N":10.0,"P":20.0,"K":5.5,"Mg":1.0,
+                "with_Cl":true,"with_S":false,"limit":null,"id":100}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            legacy.content,
+            NutrientMap::with(&[
+                (ElemName::Nitrogen, 10.0),
+                (ElemName::Phosphorus, 20.0),
+                (ElemName::Potassium, 5.5),
+                (ElemName::Magnesium, 1.0),
+            ])
+        );
+    }
+}
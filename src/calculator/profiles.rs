@@ -0,0 +1,172 @@
+//! Профили смесей. Методы Митлайдера и Угаровой используют несколько смесей с разным
+//! назначением: заправку гряд перед посадкой, еженедельные подкормки, полив рассады.
+//! Профиль задаёт соотношения элементов, которые калькулятор предлагает по умолчанию,
+//! и то, какие таблицы микроудобрений и доз внесения имеют смысл для готовой смеси.
+
+use super::consts;
+use super::crops::Crop;
+use super::formatted_solution::MicroFertInfo;
+use super::{ElemName, ElemRatios};
+
+/// Назначение смеси
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum MixtureProfile {
+    /// Смесь №2 для еженедельных подкормок на грядах. Старые ссылки считают именно её.
+    #[default]
+    WeeklyFeed,
+    /// Заправка гряд перед посадкой по Митлайдеру. Сама смесь №1 (известь с бором)
+    /// не содержит ни азота, ни фосфора и не рассчитывается; вместе с ней в гряду
+    /// вносится смесь №2, которой здесь не должно недоставать кальция.
+    PrePlant,
+    /// Смесь №2а Угаровой для полива рассады: те же соотношения NPK, что у смеси №2,
+    /// но полный набор микроэлементов
+    Seedling2a,
+    /// Соотношения пользователя, показываются все таблицы
+    Custom,
+}
+
+/// Таблица доз микроудобрений из книг Угаровой и Митлайдера
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MicroFertTable {
+    /// Смесь №2 Угаровой: только молибден и бор
+    Ugarova2,
+    /// Смесь №2а Угаровой для рассады
+    Ugarova2a,
+    /// Weekly Feed по Митлайдеру
+    Mittleider,
+}
+
+impl MicroFertTable {
+    /// Количества микроудобрений на дозу стандартной смеси (`consts::STD_N_QUANTITY`)
+    pub fn basis(self) -> &'static [MicroFertInfo] {
+        match self {
+            MicroFertTable::Ugarova2 => &consts::MICROFERTS,
+            MicroFertTable::Ugarova2a => &consts::MICROFERTS_2A,
+            MicroFertTable::Mittleider => &consts::MICROFERTS_MIT,
+        }
+    }
+}
+
+impl MixtureProfile {
+    pub const ALL: [MixtureProfile; 4] = [
+        MixtureProfile::WeeklyFeed,
+        MixtureProfile::PrePlant,
+        MixtureProfile::Seedling2a,
+        MixtureProfile::Custom,
+    ];
+
+    /// Название профиля для переключателя
+    pub fn title(self) -> &'static str {
+        match self {
+            MixtureProfile::WeeklyFeed => "Смесь №2 для еженедельных подкормок",
+            MixtureProfile::PrePlant => "Заправка гряд перед посадкой (вместе со смесью №1)",
+            MixtureProfile::Seedling2a => "Смесь №2а для полива рассады",
+            MixtureProfile::Custom => "Своя смесь",
+        }
+    }
+
+    /// Заголовок страницы с рассчитанной смесью
+    pub fn heading(self) -> &'static str {
+        match self {
+            MixtureProfile::WeeklyFeed => "Вариант смеси №2 по методу Митлайдера",
+            MixtureProfile::PrePlant => "Вариант смеси для заправки гряд по методу Митлайдера",
+            MixtureProfile::Seedling2a => "Вариант смеси №2а для рассады по Угаровой",
+            MixtureProfile::Custom => "Вариант смеси по методу Митлайдера",
+        }
+    }
+
+    /// Название смеси в родительном падеже, для фраз вида "для приготовления ... смеси №2"
+    pub fn mixture_name(self) -> &'static str {
+        match self {
+            MixtureProfile::WeeklyFeed => "смеси удобрений №2",
+            MixtureProfile::PrePlant => "смеси удобрений для заправки гряд",
+            MixtureProfile::Seedling2a => "смеси удобрений №2а",
+            MixtureProfile::Custom => "смеси удобрений",
+        }
+    }
+
+    pub fn urlencode(self) -> &'static str {
+        match self {
+            MixtureProfile::WeeklyFeed => "weekly",
+            MixtureProfile::PrePlant => "preplant",
+            MixtureProfile::Seedling2a => "2a",
+            MixtureProfile::Custom => "custom",
+        }
+    }
+
+    pub fn from_url(text: &str) -> Option<Self> {
+        MixtureProfile::ALL
+            .into_iter()
+            .find(|profile| profile.urlencode() == text)
+    }
+
+    /// Соотношения, с которыми рассчитывается смесь профиля для культуры. Для своей смеси -
+    /// `None`: выбор профиля не трогает уже заданные пользователем соотношения.
+    pub fn ratios(self, crop: Crop) -> Option<ElemRatios> {
+        match self {
+            MixtureProfile::WeeklyFeed | MixtureProfile::Seedling2a => Some(crop.ratios()),
+            MixtureProfile::PrePlant => {
+                let mut ratios = crop.ratios();
+                ratios
+                    .secondary
+                    .set(ElemName::Calcium, ElemName::Calcium.info().default_ratio);
+                Some(ratios)
+            }
+            MixtureProfile::Custom => None,
+        }
+    }
+
+    /// Таблицы микроудобрений, которые стоит показать для смеси профиля
+    pub fn microfert_tables(self) -> &'static [MicroFertTable] {
+        match self {
+            MixtureProfile::WeeklyFeed => &[MicroFertTable::Ugarova2, MicroFertTable::Mittleider],
+            MixtureProfile::PrePlant => &[MicroFertTable::Mittleider],
+            MixtureProfile::Seedling2a => &[MicroFertTable::Ugarova2a, MicroFertTable::Mittleider],
+            MixtureProfile::Custom => &[
+                MicroFertTable::Ugarova2,
+                MicroFertTable::Ugarova2a,
+                MicroFertTable::Mittleider,
+            ],
+        }
+    }
+
+    /// Нужны ли дозы внесения на грядах
+    pub fn has_ground_dozes(self) -> bool {
+        self != MixtureProfile::Seedling2a
+    }
+
+    /// Нужны ли дозы для полива рассады
+    pub fn has_seedling_dozes(self) -> bool {
+        matches!(self, MixtureProfile::Seedling2a | MixtureProfile::Custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MixtureProfile;
+    use crate::calculator::crops::Crop;
+    use crate::calculator::ElemName;
+
+    #[test]
+    fn test_profile_url() {
+        for profile in MixtureProfile::ALL {
+            assert_eq!(MixtureProfile::from_url(profile.urlencode()), Some(profile));
+        }
+        assert_eq!(MixtureProfile::from_url("mix1"), None);
+    }
+
+    #[test]
+    fn test_profile_ratios() {
+        for profile in MixtureProfile::ALL {
+            assert!(profile
+                .ratios(Crop::default())
+                .is_none_or(|ratios| ratios.all_valid()));
+            assert!(!profile.microfert_tables().is_empty());
+            assert!(profile.has_ground_dozes() || profile.has_seedling_dozes());
+        }
+        let pre_plant = MixtureProfile::PrePlant.ratios(Crop::Tomato).unwrap();
+        assert!(pre_plant.secondary.get(ElemName::Calcium).is_some());
+        assert_eq!(pre_plant.k_to_p, Crop::Tomato.ratios().k_to_p);
+        assert_eq!(MixtureProfile::Custom.ratios(Crop::Tomato), None);
+    }
+}
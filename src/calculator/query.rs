@@ -1,31 +1,242 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
 use crate::calculator::formatted_solution::FormattedSolution;
-use crate::calculator::modified_simplex::{LPTask, SimplexError};
-use crate::calculator::{Amounts, Deficites, ElemRange, Fertilizer};
-use std::iter::repeat;
+use crate::calculator::modified_simplex::{LPSolution, LPTask, SimplexError};
+#[cfg(test)]
+use crate::calculator::ElemRatios;
+use crate::calculator::{
+    Amounts, Deficite, Deficites, ElemName, ElemRange, ElemRangeName, Fertilizer,
+    FertilizerMinimum, NutrientMap, SecondaryRatios, TargetRatios,
+};
 
-use super::consts;
+use super::alternatives::AlternativeSolution;
+use super::crops::Crop;
+use super::lp_solver::SolverBackend;
+use super::max_batch::MaxBatch;
 use super::modified_simplex::ConstraintOp;
+use super::nitrogen::{NitrogenForm, NitrogenFormLimit};
+use super::profiles::MixtureProfile;
+use super::{alternatives, cardinality, consts, max_batch};
+
+/// Имя параметра ссылки для границы соотношения вторичного элемента к фосфору
+/// (`PCa_from`, `PS_to` и т.п.)
+fn secondary_param_name(element: ElemName, is_from: bool) -> String {
+    let bound = if is_from { "from" } else { "to" };
+    format!("P{}_{}", element.symbol(), bound)
+}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(non_snake_case)]
 pub(crate) struct MixtureQuery {
     pub fertilizers: Vec<Fertilizer>,
     pub N_ratio: ElemRange,
     pub K_ratio: ElemRange,
     pub Mg_ratio: ElemRange,
+    /// Необязательные соотношения к фосфору кальция, серы, хлора и натрия.
+    /// Остаются жёсткими и в режиме идеальных соотношений.
+    pub secondary_ratios: SecondaryRatios,
+    /// Необязательный предел доли одной из форм азота (например, аммонийного для рассады)
+    pub nitrogen_form_limit: Option<NitrogenFormLimit>,
+    /// Назначение смеси: от него зависят таблицы микроудобрений и доз внесения
+    pub profile: MixtureProfile,
+    /// Культура: от неё зависят дозы внесения смеси на грядах
+    pub crop: Crop,
+    pub mass: f64,
+    pub objective: MixtureObjective,
+    /// Наибольшее число различных удобрений в смеси (если не указано - без ограничений)
+    pub max_ingredients: Option<usize>,
+    /// Уже приготовленная часть смеси, которую нужно дополнить до массы `mass`
+    pub existing: Option<ExistingMixture>,
+    /// Идеальные соотношения элементов к фосфору. Если заданы, диапазоны соотношений
+    /// не учитываются, а подбирается смесь с соотношениями, ближайшими к идеальным.
+    pub target: Option<TargetRatios>,
+    /// Решатель задач линейного программирования, которым подбирается смесь
+    pub solver: SolverBackend,
+}
+
+/// Смесь известного состава, которая уже есть (например, недоиспользованное ведро).
+/// Её масса фиксирована, а удобрения подбираются так, чтобы в заданные соотношения
+/// попала вся партия целиком.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct ExistingMixture {
+    /// Масса уже смешанного, кг
     pub mass: f64,
+    /// Содержание макроэлементов, %. Остальные элементы в уже смешанном не учитываются.
+    pub content: Amounts,
+}
+
+impl ExistingMixture {
+    /// Уже смешанное как удобрение: оно уже оплачено, поэтому без цены
+    pub fn as_fertilizer(&self) -> Fertilizer {
+        Fertilizer {
+            name: "Уже смешанное".into(), content: self.content, ..Default::default()
+        }
+    }
+
+    /// Кодирует смесь в нечто вроде `existing=mass:3,N:10,P:5,K:8,Mg:1`
+    fn urlencode(&self) -> String {
+        let mut result = format!("mass:{}", self.mass);
+        for element in ElemName::MACRO {
+            let amount = self.content[element];
+            write!(&mut result, ",{}:{}", element.symbol(), amount)
+                .expect("Content must be serializable");
+        }
+        result
+    }
+
+    fn from_url(text: &str) -> Option<Self> {
+        let mut mass = None;
+        let mut result = Self::default();
+        let mut parsed = NutrientMap::<bool>::default();
+        for param_str in text.split(',') {
+            let (param, amount_str) = param_str.split_once(':')?;
+            let amount = MixtureQuery::parse_amount(amount_str);
+            match ElemName::from_symbol(param) {
+                Some(element) if ElemName::MACRO.contains(&element) => {
+                    result.content[element] = amount?;
+                    parsed[element] = true;
+                }
+                _ if param == "mass" => mass = amount,
+                _ => return None,
+            }
+        }
+        if !ElemName::MACRO.iter().all(|element| parsed[*element]) {
+            return None;
+        }
+        result.mass = mass?;
+        Some(result)
+    }
+}
+
+/// Что именно оптимизируется при подборе смеси (при заданном количестве питательных веществ)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) enum MixtureObjective {
+    /// Минимальная масса смеси, то есть максимальная концентрация
+    #[default]
+    Mass,
+    /// Минимальная стоимость удобрений
+    Cost,
+    /// Минимальная масса балласта - всего, кроме самих питательных веществ
+    Ballast,
+}
+
+impl MixtureObjective {
+    /// Доля массы смеси, добавляемая к стоимости, чтобы из одинаково дешёвых смесей
+    /// (например, целиком из уже купленных удобрений) выбиралась самая концентрированная
+    const COST_MASS_TIE_BREAK: f64 = 1e-6;
+
+    fn urlencode(self) -> &'static str {
+        match self {
+            Self::Mass => "mass",
+            Self::Cost => "cost",
+            Self::Ballast => "ballast",
+        }
+    }
+
+    fn from_url(text: &str) -> Option<Self> {
+        match text {
+            "mass" => Some(Self::Mass),
+            "cost" => Some(Self::Cost),
+            "ballast" => Some(Self::Ballast),
+            _ => None,
+        }
+    }
+
+    /// Вклад одного килограмма удобрения в оптимизируемую функцию
+    pub fn coefficient(self, fertilizer: &Fertilizer) -> f64 {
+        match self {
+            Self::Mass => 1.0,
+            Self::Cost => fertilizer.price.unwrap_or_default() + Self::COST_MASS_TIE_BREAK,
+            Self::Ballast => fertilizer.ballast(),
+        }
+    }
+}
+
+/// Условие, которое пользователь задал для смеси.
+/// Нужно, чтобы объяснять пользователю, что именно ограничивает смесь.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TaskConstraint {
+    /// Остаток удобрения (индекс в списке удобрений задачи)
+    Limit(usize),
+    /// Минимальное количество удобрения в смеси (индекс в списке удобрений задачи)
+    Minimum(usize),
+    /// Нижняя граница соотношения элемента к фосфору
+    RatioFrom(ElemRangeName),
+    /// Верхняя граница соотношения элемента к фосфору
+    RatioTo(ElemRangeName),
+    /// Нижняя граница соотношения вторичного элемента к фосфору
+    SecondaryFrom(ElemName),
+    /// Верхняя граница соотношения вторичного элемента к фосфору
+    SecondaryTo(ElemName),
+    /// Предельная доля формы азота в азоте смеси
+    NitrogenShare(NitrogenForm),
+    /// Масса всей партии, в которую входит уже приготовленная часть смеси
+    Existing,
+    /// Идеальное соотношение элемента к фосфору (условие мягкое, его нельзя нарушить)
+    Target(ElemRangeName),
+    /// Нормировка смеси на единицу фосфора
+    Phosphorus,
 }
 
-/// Добавляет в систему сразу две строки ограничений вида
-///
-///     c1 * x1 + c2 * x2 + ... >= from
-///     c1 * x1 + c2 * x2 + ... <= to
-fn add_range_constraints(task: &mut LPTask, constraints: &[f64], range: &ElemRange) {
-    task.add_constr(constraints, ConstraintOp::GreaterOrEqual, range.from);
-    task.add_constr(constraints, ConstraintOp::LessOrEqual, range.to);
+/// Смысл строки ограничений в задаче, построенной `MixtureQuery::build_labeled_task`.
+/// Соотношение элемента к фосфору записывается одной двусторонней строкой.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TaskRow {
+    Limit(usize),
+    Minimum(usize),
+    Ratio(ElemRangeName),
+    SecondaryRatio(ElemName),
+    NitrogenShare(NitrogenForm),
+    Existing,
+    Target(ElemRangeName),
+    Phosphorus,
+}
+
+impl TaskRow {
+    /// Какая из границ строки связывает решение, судя по знаку её двойственной оценки:
+    /// при минимизации оценка нижней границы неотрицательна, а верхней - неположительна
+    pub fn binding_constraint(self, dual: f64) -> TaskConstraint {
+        match self {
+            Self::Limit(fert_idx) => TaskConstraint::Limit(fert_idx),
+            Self::Minimum(fert_idx) => TaskConstraint::Minimum(fert_idx),
+            Self::Ratio(range_name) if dual < 0.0 => TaskConstraint::RatioTo(range_name),
+            Self::Ratio(range_name) => TaskConstraint::RatioFrom(range_name),
+            Self::SecondaryRatio(element) if dual < 0.0 => TaskConstraint::SecondaryTo(element),
+            Self::SecondaryRatio(element) => TaskConstraint::SecondaryFrom(element),
+            Self::NitrogenShare(form) => TaskConstraint::NitrogenShare(form),
+            Self::Existing => TaskConstraint::Existing,
+            Self::Target(range_name) => TaskConstraint::Target(range_name),
+            Self::Phosphorus => TaskConstraint::Phosphorus,
+        }
+    }
 }
 
 impl MixtureQuery {
+    pub fn ratio(&self, range_name: ElemRangeName) -> &ElemRange {
+        match range_name {
+            ElemRangeName::Nitrogen => &self.N_ratio,
+            ElemRangeName::Potassium => &self.K_ratio,
+            ElemRangeName::Magnesium => &self.Mg_ratio,
+        }
+    }
+
+    /// Диапазон соотношения вторичного элемента к фосфору.
+    /// Вызывается только для элементов, диапазон которых задан.
+    pub fn secondary_ratio(&self, element: ElemName) -> ElemRange {
+        self.secondary_ratios
+            .get(element)
+            .expect("Secondary ratio must be set")
+    }
+
+    pub fn ratio_mut(&mut self, range_name: ElemRangeName) -> &mut ElemRange {
+        match range_name {
+            ElemRangeName::Nitrogen => &mut self.N_ratio,
+            ElemRangeName::Potassium => &mut self.K_ratio,
+            ElemRangeName::Magnesium => &mut self.Mg_ratio,
+        }
+    }
+
     fn parse_amount(text: &str) -> Option<f64> {
         text.replace(',', ".")
             .parse::<f64>()
@@ -43,77 +254,140 @@ impl MixtureQuery {
 
     /// Разбирает строки вида "Азофоска:N:16.0,P:16.0,K:16.0,Mg:0.0" в соответсвующие структуры,
     /// удобрений, если это возможно.
-    #[allow(non_snake_case)]
     fn parse_fertilizer_from_query(
         fert_str: &str,
         permanent_fertilizers: &[Fertilizer],
     ) -> Option<Fertilizer> {
         let (name, remainder) = fert_str.split_once(':')?;
-        let (mut N, mut P, mut K, mut Mg, mut Cl, mut S, mut limit) =
-            (None, None, None, None, None, None, None);
+        let mut result = Fertilizer { name: name.replace('+', " ").into(), ..Default::default() };
+        let mut parsed = NutrientMap::<bool>::default();
         for elem_amount_str in remainder.split(',') {
             let (element_str, amount_str) = elem_amount_str.split_once(':')?;
-            match element_str {
-                "Cl" => Cl = Some(Self::parse_bool(amount_str)?),
-                "S" => S = Some(Self::parse_bool(amount_str)?),
-                _ => {
+            match ElemName::from_symbol(element_str) {
+                Some(element) => {
+                    // В старых ссылках хлор и сера были лишь флагами "есть" и "нет".
+                    // "Есть" без количества - всё равно что не указано вовсе.
+                    match Self::parse_bool(amount_str) {
+                        Some(true) => {}
+                        Some(false) => parsed[element] = true,
+                        None => {
+                            result[element] = Self::parse_amount(amount_str)?;
+                            parsed[element] = true;
+                        }
+                    }
+                }
+                None => {
                     let amount = Self::parse_amount(amount_str);
                     match element_str {
-                        "N" => N = amount,
-                        "P" => P = amount,
-                        "K" => K = amount,
-                        "Mg" => Mg = amount,
-                        "lim" => limit = amount,
+                        "lim" => result.limit = amount,
+                        "min" => result.minimum = amount.map(FertilizerMinimum::Mass),
+                        "minshare" => result.minimum = amount.map(FertilizerMinimum::Share),
+                        "price" => result.price = amount,
                         _ => return None,
                     }
                 }
             }
         }
-        let mut result = Fertilizer {
-            limit,
-            name: name.replace('+', " ").into(),
-            N: N?,
-            P: P?,
-            K: K?,
-            Mg: Mg?,
-            with_Cl: Cl.unwrap_or_default(),
-            with_S: S.unwrap_or_default(),
-            id: Fertilizer::new_id(),
-        };
+        // Без макроэлементов удобрения не бывает
+        if !ElemName::MACRO.iter().all(|element| parsed[*element]) {
+            return None;
+        }
         // старый legom не умел включать содержание серы в состав удобрения
         // в GET-запросе, но в базе этот флаг был, и данные выцеплялись оттуда.
         // Здесь мы делаем аналогичный трюк, для сохранения работоспособности ссылок.
         // Будем дополнительно уточнять для каждого распарсенного удобрения
         // если он присутствует в предопределённой таблице,
-        // и вытаскивать оттуда уточнения по хлору и сере, если возможно.
-        if Cl.is_none() || S.is_none() {
-            let result_content_id = result.content_id();
-            if let Some(permanent_match) = permanent_fertilizers
-                .iter()
-                .find(|f| f.content_id() == result_content_id)
-            {
-                if Cl.is_none() {
-                    result.with_Cl = permanent_match.with_Cl;
-                }
-                if S.is_none() {
-                    result.with_S = permanent_match.with_S;
-                }
+        // и вытаскивать оттуда содержание вторичных элементов, если в ссылке его нет.
+        let result_content_id = result.content_id();
+        if let Some(permanent_match) = permanent_fertilizers
+            .iter()
+            .find(|f| f.content_id() == result_content_id)
+        {
+            for (element, _) in parsed.iter().filter(|(_, parsed)| !**parsed) {
+                result[element] = permanent_match[element];
             }
+            // Формы азота в ссылки не записываются вовсе
+            result.nitrogen_forms = permanent_match.nitrogen_forms;
         }
         Some(result)
     }
 
+    /// Разбирает идеальные соотношения вида `N:1.8,K:1.8,Mg:0.35`
+    fn parse_target(text: &str) -> Option<TargetRatios> {
+        let mut target = TargetRatios { n_to_p: 0.0, k_to_p: 0.0, mg_to_p: 0.0 };
+        let mut parsed = [false; 3];
+        for param_str in text.split(',') {
+            let (param, amount_str) = param_str.split_once(':')?;
+            let (range_name, idx) = match param {
+                "N" => (ElemRangeName::Nitrogen, 0),
+                "K" => (ElemRangeName::Potassium, 1),
+                "Mg" => (ElemRangeName::Magnesium, 2),
+                _ => return None,
+            };
+            target[range_name] = Self::parse_amount(amount_str)?;
+            parsed[idx] = true;
+        }
+        parsed.iter().all(|p| *p).then_some(target)
+    }
+
+    /// Разбирает имена параметров вида `PCa_from` и `PS_to` - границы соотношений
+    /// вторичных элементов. Возвращает элемент и то, нижняя ли это граница.
+    fn parse_secondary_param(param: &str) -> Option<(ElemName, bool)> {
+        let (symbol, bound) = param.strip_prefix('P')?.split_once('_')?;
+        let element = ElemName::from_symbol(symbol)?;
+        element.info().default_ratio?;
+        match bound {
+            "from" => Some((element, true)),
+            "to" => Some((element, false)),
+            _ => None,
+        }
+    }
+
     pub fn from_query_map(query: &[(String, String)]) -> Option<Self> {
         const NON_FERT_PARAMS: usize = 7;
         let mut fertilizers =
             Vec::<Fertilizer>::with_capacity(query.len().max(NON_FERT_PARAMS) - NON_FERT_PARAMS);
         let (mut mg_from, mut mg_to, mut n_from, mut n_to, mut k_from, mut k_to, mut weight) =
             (None, None, None, None, None, None, None);
+        // старые ссылки не содержат цели оптимизации, тогда смесь была самой концентрированной
+        let mut objective = MixtureObjective::Mass;
+        let mut max_ingredients = None;
+        let mut existing = None;
+        let mut target = None;
+        let mut nitrogen_form_limit = None;
+        // старые ссылки не содержат профиля, тогда считалась только смесь №2
+        let mut profile = MixtureProfile::default();
+        let mut crop = Crop::default();
+        let mut solver = SolverBackend::default();
+        let mut secondary_bounds = NutrientMap::<(Option<f64>, Option<f64>)>::default();
         for (param, data) in query {
             if param == "fert" {
                 let fertilizer =
                     Self::parse_fertilizer_from_query(data, consts::PERMANENT_FERTILIZERS)?;
                 fertilizers.push(fertilizer);
+            } else if param == "objective" {
+                objective = MixtureObjective::from_url(data)?;
+            } else if param == "max_ferts" {
+                max_ingredients = Some(data.parse::<usize>().ok()?);
+            } else if param == "existing" {
+                existing = Some(ExistingMixture::from_url(data)?);
+            } else if param == "target" {
+                target = Some(Self::parse_target(data)?);
+            } else if param == "n_form_max" {
+                nitrogen_form_limit = Some(NitrogenFormLimit::from_url(data)?);
+            } else if param == "profile" {
+                profile = MixtureProfile::from_url(data)?;
+            } else if param == "crop" {
+                crop = Crop::from_url(data)?;
+            } else if param == "solver" {
+                solver = SolverBackend::from_url(data)?;
+            } else if let Some((element, is_from)) = Self::parse_secondary_param(param) {
+                let amount = Some(Self::parse_amount(data)?);
+                if is_from {
+                    secondary_bounds[element].0 = amount;
+                } else {
+                    secondary_bounds[element].1 = amount;
+                }
             } else {
                 let amount = Self::parse_amount(data);
                 match param.as_str() {
@@ -130,59 +404,178 @@ impl MixtureQuery {
                 }
             }
         }
+        let mut secondary_ratios = SecondaryRatios::default();
+        for (element, bounds) in secondary_bounds.iter() {
+            match *bounds {
+                (Some(from), Some(to)) => {
+                    secondary_ratios.set(element, Some(ElemRange::try_new(from, to)?))
+                }
+                (None, None) => {}
+                _ => return None,
+            }
+        }
         Some(Self {
             fertilizers,
             N_ratio: ElemRange::try_new(n_from?, n_to?)?,
             Mg_ratio: ElemRange::try_new(mg_from?, mg_to?)?,
             K_ratio: ElemRange::try_new(k_from?, k_to?)?,
             mass: weight?,
+            objective,
+            max_ingredients,
+            existing,
+            target,
+            secondary_ratios,
+            nitrogen_form_limit,
+            profile,
+            crop,
+            solver,
         })
     }
 
-    pub fn to_url_query(&self) -> Option<Vec<(&'static str, String)>> {
+    pub fn to_url_query(&self) -> Option<Vec<(Cow<'static, str>, String)>> {
         let mut output = if self.fertilizers.is_empty() {
             return None;
         } else {
             let mut output =
-                Vec::<(&'static str, String)>::with_capacity(self.fertilizers.len() + 7);
+                Vec::<(Cow<'static, str>, String)>::with_capacity(self.fertilizers.len() + 7);
             for fert in self.fertilizers.iter().map(|f| f.urlencode()) {
-                output.push(("fert", fert));
+                output.push(("fert".into(), fert));
             }
             output
         };
-        output.push(("PMg_from", self.Mg_ratio.from.to_string()));
-        output.push(("PMg_to", self.Mg_ratio.to.to_string()));
-        output.push(("NP_from", self.N_ratio.from.to_string()));
-        output.push(("NP_to", self.N_ratio.to.to_string()));
-        output.push(("PK_from", self.K_ratio.from.to_string()));
-        output.push(("PK_to", self.K_ratio.to.to_string()));
-        output.push(("need_weight", self.mass.to_string()));
+        output.push(("PMg_from".into(), self.Mg_ratio.from.to_string()));
+        output.push(("PMg_to".into(), self.Mg_ratio.to.to_string()));
+        output.push(("NP_from".into(), self.N_ratio.from.to_string()));
+        output.push(("NP_to".into(), self.N_ratio.to.to_string()));
+        output.push(("PK_from".into(), self.K_ratio.from.to_string()));
+        output.push(("PK_to".into(), self.K_ratio.to.to_string()));
+        for (element, range) in self.secondary_ratios.iter() {
+            output.push((
+                secondary_param_name(element, true).into(),
+                range.from.to_string(),
+            ));
+            output.push((
+                secondary_param_name(element, false).into(),
+                range.to.to_string(),
+            ));
+        }
+        if let Some(limit) = self.nitrogen_form_limit {
+            output.push(("n_form_max".into(), limit.urlencode()));
+        }
+        output.push(("need_weight".into(), self.mass.to_string()));
+        if self.objective != MixtureObjective::Mass {
+            output.push(("objective".into(), self.objective.urlencode().to_string()));
+        }
+        if self.profile != MixtureProfile::default() {
+            output.push(("profile".into(), self.profile.urlencode().to_string()));
+        }
+        if self.crop != Crop::default() {
+            output.push(("crop".into(), self.crop.urlencode().to_string()));
+        }
+        if self.solver != SolverBackend::default() {
+            output.push(("solver".into(), self.solver.urlencode().to_string()));
+        }
+        if let Some(max_ingredients) = self.max_ingredients {
+            output.push(("max_ferts".into(), max_ingredients.to_string()));
+        }
+        if let Some(existing) = self.existing {
+            output.push(("existing".into(), existing.urlencode()));
+        }
+        if let Some(target) = self.target {
+            output.push((
+                "target".into(),
+                format!(
+                    "N:{},K:{},Mg:{}",
+                    target.n_to_p, target.k_to_p, target.mg_to_p
+                ),
+            ));
+        }
         Some(output)
     }
 
+    /// Все условия, которые накладывает на смесь этот запрос, в порядке строк задачи
+    pub fn constraints(&self) -> Vec<TaskConstraint> {
+        let mut constraints = Vec::new();
+        for (fert_idx, fertilizer) in self.fertilizers.iter().enumerate() {
+            if fertilizer.limit.is_some() {
+                constraints.push(TaskConstraint::Limit(fert_idx));
+            }
+            if fertilizer.minimum.is_some() {
+                constraints.push(TaskConstraint::Minimum(fert_idx));
+            }
+        }
+        if self.target.is_none() {
+            for range_name in ElemRangeName::ALL {
+                constraints.push(TaskConstraint::RatioFrom(range_name));
+                constraints.push(TaskConstraint::RatioTo(range_name));
+            }
+        }
+        for (element, _) in self.secondary_ratios.iter() {
+            constraints.push(TaskConstraint::SecondaryFrom(element));
+            constraints.push(TaskConstraint::SecondaryTo(element));
+        }
+        if let Some(limit) = self.nitrogen_form_limit {
+            constraints.push(TaskConstraint::NitrogenShare(limit.form));
+        }
+        if self.existing.is_some() {
+            constraints.push(TaskConstraint::Existing);
+        }
+        // Идеальные соотношения всегда выполнимы, и противоречить ничему не могут
+        constraints.push(TaskConstraint::Phosphorus);
+        constraints
+    }
+
     /// По указанным параметрам, строит систему ограничений - задачу линейного программирования
     pub fn build_task(self: &MixtureQuery, extra_fertilizers: &[Fertilizer]) -> LPTask {
-        const P_NEIGHBOR: f64 = 1e-9;
-        const P_RATIO: ElemRange = ElemRange { from: 1.0 - P_NEIGHBOR, to: 1.0 + P_NEIGHBOR };
-        // ferts_count = len(checked_ferts)
-        let num_ferts = self.fertilizers.len() + extra_fertilizers.len();
-        let func_vec: Vec<f64> = repeat(1.0).take(num_ferts).collect();
-        let mut task = LPTask::new(&func_vec);
-        let mut n_constr = Vec::with_capacity(num_ferts);
-        let mut p_constr = Vec::with_capacity(num_ferts);
-        let mut k_constr = Vec::with_capacity(num_ferts);
-        let mut mg_constr = Vec::with_capacity(num_ferts);
-        let mut fert_constraints_buffer = vec![1.0f64; num_ferts]; // все кроме одного будут = 1.0
-        for (fert_idx, fertilizer) in self
+        self.build_labeled_task(extra_fertilizers).0
+    }
+
+    /// То же, что и `build_task`, но дополнительно возвращает смысл каждой строки ограничений.
+    /// Уже приготовленная часть смеси, если она есть, становится переменной задачи
+    /// после `extra_fertilizers`. В режиме идеальных соотношений за ней следуют
+    /// `deviation_variables_count` переменных отклонения от них.
+    pub fn build_labeled_task(&self, extra_fertilizers: &[Fertilizer]) -> (LPTask, Vec<TaskRow>) {
+        let existing_fertilizer = self.existing.map(|existing| existing.as_fertilizer());
+        let num_ferts = self.fertilizers.len()
+            + extra_fertilizers.len()
+            + usize::from(existing_fertilizer.is_some());
+        let num_vars = num_ferts + self.deviation_variables_count();
+        let mut func_vec: Vec<f64> = self
             .fertilizers
             .iter()
             .chain(extra_fertilizers.iter())
-            .enumerate()
-        {
-            n_constr.push(fertilizer.N / 100.0);
-            p_constr.push(fertilizer.P / 100.0);
-            k_constr.push(fertilizer.K / 100.0);
-            mg_constr.push(fertilizer.Mg / 100.0);
+            .chain(existing_fertilizer.iter())
+            .map(|fertilizer| self.objective.coefficient(fertilizer))
+            .collect();
+        if let Some(target) = self.target {
+            // Относительное отклонение от цели должно быть дороже любого удобрения
+            let max_coefficient = func_vec.iter().copied().fold(0.0, f64::max);
+            for range_name in ElemRangeName::ALL {
+                let weight = consts::TARGET_DEVIATION_PRIORITY * max_coefficient
+                    / target[range_name].max(consts::MIN_TARGET_RATIO);
+                // Отклонения вверх и вниз
+                func_vec.extend([weight, weight]);
+            }
+        }
+        let mut task = LPTask::new(&func_vec).with_backend(self.solver);
+        let mut labels = Vec::<TaskRow>::new();
+        let all_fertilizers = || {
+            self.fertilizers
+                .iter()
+                .chain(extra_fertilizers.iter())
+                .chain(existing_fertilizer.iter())
+        };
+        // Доля элемента в каждом удобрении: строка ограничения на его количество
+        // в смеси (при фосфоре, равном единице, это соотношение элемента к фосфору)
+        let content_constr = |element: ElemName| {
+            let mut constr = vec![0.0f64; num_vars];
+            for (fert_idx, fertilizer) in all_fertilizers().enumerate() {
+                constr[fert_idx] = fertilizer[element] / 100.0;
+            }
+            constr
+        };
+        let mut fert_constraints_buffer = vec![0.0f64; num_vars];
+        for (fert_idx, fertilizer) in all_fertilizers().enumerate() {
             if let Some(limit) = fertilizer.limit {
                 // Добавляем уравнение, которое отражает ограниченную долю удобрения "n" в смеси.
                 // Известно, что $R_n = limit_n / mass$ - это максимально возможная доля удобрения
@@ -190,23 +583,124 @@ impl MixtureQuery {
                 // Также известно, что $mass = X_1 + X_2 + .. + X_n$.
                 // Объединяя, получаем неравенство $X_n / (X_1 + X_2 + .. + X_n) <= R$.
                 // После преобразования, имеем каноническую форму ограничения для системы:
-                // $X_1 + X_2 + .. + (1 - 1/R) * X_n >= 0$
+                // $R * X_1 + R * X_2 + .. + (R - 1) * X_n >= 0$
+                // (без деления на R, чтобы нулевой остаток не давал бесконечных коэффициентов).
+                // Границей переменной $X_n <= limit$ это стало бы, только если закрепить
+                // масштаб: $X_1 + X_2 + .. + X_n = mass$ вместо единицы фосфора. Но тогда
+                // стоимость и балласт на единицу фосфора - это отношение двух линейных функций,
+                // и задача перестаёт быть линейной; линейной её делает как раз свободный
+                // масштаб. Отдельная переменная полной массы $M$ тоже не помогает:
+                // $X_n <= R * M$ - по-прежнему строка, просто с двумя коэффициентами.
+                // Там, где масштаб закреплён (округление, наибольшая партия), остатки
+                // и минимумы - границы переменных.
                 let max_rate = limit / self.mass;
-                fert_constraints_buffer[fert_idx] = 1.0 - 1.0 / max_rate; // остальные уже заполнены
+                fert_constraints_buffer[..num_ferts].fill(max_rate);
+                fert_constraints_buffer[fert_idx] = max_rate - 1.0;
                 task.add_constr(&fert_constraints_buffer, ConstraintOp::GreaterOrEqual, 0.0);
-                fert_constraints_buffer[fert_idx] = 1.0; // восстанавливаем заливку для нового цикла
+                labels.push(TaskRow::Limit(fert_idx));
             }
+            if let Some(minimum) = fertilizer.minimum {
+                // Аналогично, доля удобрения не меньше $r$: $X_n >= r * (X_1 + X_2 + .. + X_n)$,
+                // то есть $-r * X_1 - r * X_2 - .. + (1 - r) * X_n >= 0$. Границей переменной
+                // это не записать по той же причине, что и остаток.
+                let min_rate = minimum.rate(self.mass);
+                fert_constraints_buffer[..num_ferts].fill(-min_rate);
+                fert_constraints_buffer[fert_idx] = 1.0 - min_rate;
+                task.add_constr(&fert_constraints_buffer, ConstraintOp::GreaterOrEqual, 0.0);
+                labels.push(TaskRow::Minimum(fert_idx));
+            }
+        }
+        if let Some(existing) = self.existing {
+            // Доля уже смешанного во всей партии известна точно:
+            // $-r * X_1 - r * X_2 - .. + (1 - r) * X_e = 0$, где $r = existing.mass / mass$
+            let existing_rate = existing.mass / self.mass;
+            fert_constraints_buffer[..num_ferts].fill(-existing_rate);
+            fert_constraints_buffer[num_ferts - 1] = 1.0 - existing_rate;
+            task.add_constr(&fert_constraints_buffer, ConstraintOp::Equal, 0.0);
+            labels.push(TaskRow::Existing);
         }
-        add_range_constraints(&mut task, &n_constr, &self.N_ratio);
-        add_range_constraints(&mut task, &k_constr, &self.K_ratio);
-        add_range_constraints(&mut task, &mg_constr, &self.Mg_ratio);
-        add_range_constraints(&mut task, &p_constr, &P_RATIO);
-        task
+        for (range_idx, range_name) in ElemRangeName::ALL.into_iter().enumerate() {
+            let mut constr = content_constr(range_name.element());
+            match self.target {
+                Some(target) => {
+                    // Соотношение (при фосфоре, равном единице) плюс отклонения:
+                    // $E_1 * X_1 + .. + E_n * X_n - D^+ + D^- = target$
+                    let deviation_idx = num_ferts + 2 * range_idx;
+                    constr[deviation_idx] = -1.0;
+                    constr[deviation_idx + 1] = 1.0;
+                    task.add_constr(&constr, ConstraintOp::Equal, target[range_name]);
+                    labels.push(TaskRow::Target(range_name));
+                }
+                None => {
+                    let range = self.ratio(range_name);
+                    task.add_range_constr(&constr, range.from, range.to);
+                    labels.push(TaskRow::Ratio(range_name));
+                }
+            }
+        }
+        for (element, range) in self.secondary_ratios.iter() {
+            // Так же, как и для макроэлементов, но без отклонений: в режиме
+            // идеальных соотношений переменные отклонения в строку не входят
+            task.add_range_constr(&content_constr(element), range.from, range.to);
+            labels.push(TaskRow::SecondaryRatio(element));
+        }
+        if let Some(limit) = self.nitrogen_form_limit {
+            // Азота ограничиваемой формы не больше доли $s$ от азота известных форм:
+            // $(F_1 - s * N_1) * X_1 + .. + (F_n - s * N_n) * X_n <= 0$
+            let mut constr = vec![0.0f64; num_vars];
+            for (fert_idx, fertilizer) in all_fertilizers().enumerate() {
+                constr[fert_idx] = limit.coefficient(fertilizer) / 100.0;
+            }
+            task.add_constr(&constr, ConstraintOp::LessOrEqual, 0.0);
+            labels.push(TaskRow::NitrogenShare(limit.form));
+        }
+        // Всё считается на единицу фосфора
+        task.add_constr(
+            &content_constr(ElemName::Phosphorus),
+            ConstraintOp::Equal,
+            1.0,
+        );
+        labels.push(TaskRow::Phosphorus);
+        (task, labels)
+    }
+
+    /// Число переменных отклонения от идеальных соотношений в задаче из `build_task`
+    pub fn deviation_variables_count(&self) -> usize {
+        if self.target.is_some() {
+            2 * ElemRangeName::ALL.len()
+        } else {
+            0
+        }
+    }
+
+    /// Переводит решение задачи из `build_task` (в расчёте на единицу фосфора)
+    /// в килограммы удобрений для смеси заданной массы
+    pub fn scale_factor(&self, solution: &LPSolution) -> f64 {
+        let num_mixture_vars = solution.params.len() - self.deviation_variables_count();
+        self.mass / solution.params[..num_mixture_vars].iter().sum::<f64>()
     }
 
     pub fn find_solution(&self) -> Result<FormattedSolution, SimplexError> {
-        let lp_solution = self.build_task(&[]).solve_min()?;
-        Ok(FormattedSolution::new(self, &lp_solution))
+        let (lp_solution, labels) = match self.max_ingredients {
+            Some(max_count) => cardinality::solve_with_max_ingredients(self, max_count)?,
+            None => {
+                let (task, labels) = self.build_labeled_task(&[]);
+                (task.solve_min()?, labels)
+            }
+        };
+        Ok(FormattedSolution::new(self, &lp_solution, &labels))
+    }
+
+    /// Наибольшая партия смеси, которую можно приготовить из остатков удобрений
+    /// (масса `mass` при этом не учитывается)
+    pub fn find_max_batch(&self) -> Result<MaxBatch, SimplexError> {
+        max_batch::find_max_batch(self)
+    }
+
+    /// Основной рецепт и рецепты из других наборов удобрений, которые хуже него
+    /// не более чем на `consts::ALTERNATIVE_TOLERANCE`
+    pub fn find_alternative_solutions(&self) -> Vec<AlternativeSolution> {
+        alternatives::find_alternative_solutions(self, consts::ALTERNATIVE_TOLERANCE)
     }
 
     fn fake_fert_concentration<F>(&self, elem_extractor: F) -> f64
@@ -228,35 +722,647 @@ impl MixtureQuery {
             .unwrap_or(2e-3)
     }
 
+    /// Псевдо-удобрения для макроэлементов и для вторичных элементов с заданными
+    /// соотношениями (недостача остальных элементов ничему не мешает)
+    fn fake_ferts(&self) -> Vec<Fertilizer> {
+        ElemName::MACRO
+            .into_iter()
+            .chain(self.secondary_ratios.iter().map(|(element, _)| element))
+            .map(|element| {
+                let mut fake_fert = Fertilizer::default();
+                fake_fert[element] = self.fake_fert_concentration(|f| f[element]);
+                fake_fert
+            })
+            .collect()
+    }
+
+    /// Решает задачу, дополненную удобрениями `extra_fertilizers` и псевдо-удобрениями.
+    /// Возвращает недостающее и общее количество каждого элемента в смеси (кг),
+    /// а также вес каждого из `extra_fertilizers` в ней.
+    fn solve_with_fake_ferts(
+        &self,
+        extra_fertilizers: &[Fertilizer],
+    ) -> Option<(Amounts, Amounts, Vec<f64>)> {
+        let fake_ferts = self.fake_ferts();
+        let all_extra: Vec<Fertilizer> = extra_fertilizers
+            .iter()
+            .chain(fake_ferts.iter())
+            .cloned()
+            .collect();
+        // Недостача ищется по массе: псевдо-удобрения вдвое разбавленнее самых бедных
+        // настоящих и по массе всегда хуже них, а цены у них нет, так что по стоимости
+        // они оказались бы выгоднее любого настоящего удобрения
+        let query = MixtureQuery { objective: MixtureObjective::Mass, ..self.clone() };
+        let solution = query.build_task(&all_extra).solve_min().ok()?;
+        let scale_factor = query.scale_factor(&solution);
+        let weights: Vec<f64> = solution.params.iter().map(|x| x * scale_factor).collect();
+
+        let mut lacking = Amounts::default();
+        let mut total = Amounts::default();
+        let fake_from = self.fertilizers.len() + extra_fertilizers.len();
+        for (fert_idx, (fert, weight)) in self
+            .fertilizers
+            .iter()
+            .chain(all_extra.iter())
+            .zip(weights.iter())
+            .enumerate()
+        {
+            for element in ElemName::ALL {
+                let amount = weight * fert[element] / 100.0;
+                total[element] += amount;
+                if fert_idx >= fake_from {
+                    lacking[element] += amount;
+                }
+            }
+        }
+        if let Some(existing) = self.existing {
+            let existing_fertilizer = existing.as_fertilizer();
+            for element in ElemName::ALL {
+                total[element] += existing.mass * existing_fertilizer[element] / 100.0;
+            }
+        }
+        Some((
+            lacking,
+            total,
+            weights[self.fertilizers.len()..fake_from].to_vec(),
+        ))
+    }
+
     /// Вычислет дефицит каждого элемента через добавление псевдо-удобрений,
     /// каждое содержащее только один элемент питания в экстремально низкой
     /// концентрации. Если это удобрение будет выбрано, значит данного элемента
     /// отчаянно недостаёт. Также будет понятно, насколько велика недостача.
+    /// Для каждого недостающего элемента подбираются постоянные удобрения, добавление
+    /// любого из которых (без ограничения количества) эту недостачу устраняет.
     pub fn find_deficites(&self) -> Deficites {
-        let fake_npkmg_ferts = [
-            Fertilizer { N: self.fake_fert_concentration(|f| f.N), ..Default::default() },
-            Fertilizer { P: self.fake_fert_concentration(|f| f.P), ..Default::default() },
-            Fertilizer { K: self.fake_fert_concentration(|f| f.K), ..Default::default() },
-            Fertilizer { Mg: self.fake_fert_concentration(|f| f.Mg), ..Default::default() },
-        ];
+        let mut deficites = Deficites::default();
+        let Some((lacking, total, _)) = self.solve_with_fake_ferts(&[]) else {
+            return deficites;
+        };
+        for element in ElemName::ALL {
+            if lacking[element] > 0.0 {
+                deficites[element] = Some(Deficite {
+                    amount: lacking[element],
+                    percent: 100.0 * lacking[element] / total[element],
+                    remedies: Vec::new(),
+                });
+            }
+        }
+        if !deficites.any() {
+            return deficites;
+        }
 
-        let mut lacking = Amounts::default();
-        if let Ok(solution) = self.build_task(&fake_npkmg_ferts).solve_min() {
-            let scale_factor = self.mass / solution.function_value;
-            for (i, fert) in fake_npkmg_ferts.iter().enumerate() {
-                // let name,composition = fert
-                let weight = scale_factor * solution.params[self.fertilizers.len() + i];
-                lacking.N += weight * fert.N / 100.0;
-                lacking.P += weight * fert.P / 100.0;
-                lacking.K += weight * fert.K / 100.0;
-                lacking.Mg += weight * fert.Mg / 100.0;
+        for (perm_idx, candidate) in consts::PERMANENT_FERTILIZERS.iter().enumerate() {
+            // Удобрение, которого и так сколько угодно, ничего не исправит
+            let already_unlimited = self
+                .fertilizers
+                .iter()
+                .any(|fert| fert.name == candidate.name && fert.limit.is_none());
+            if already_unlimited {
+                continue;
+            }
+            let candidate = Fertilizer { limit: None, ..candidate.clone() };
+            let Some((lacking, _, weights)) = self.solve_with_fake_ferts(&[candidate]) else {
+                continue;
+            };
+            for element in ElemName::ALL {
+                if let Some(deficite) = &mut deficites[element] {
+                    if lacking[element] <= 0.0 && weights[0] > consts::MIN_SIGNIFICANT_CHANGE {
+                        deficite.remedies.push((perm_idx, weights[0]));
+                    }
+                }
+            }
+        }
+        for element in ElemName::ALL {
+            if let Some(deficite) = &mut deficites[element] {
+                deficite
+                    .remedies
+                    .sort_by(|(_, w1), (_, w2)| w1.total_cmp(w2));
+            }
+        }
+        deficites
+    }
+}
+
+#[cfg(test)]
+impl MixtureQuery {
+    /// Запрос для тестов: соотношения по умолчанию, 10 кг смеси и никаких других условий.
+    /// Остальные поля тесты задают синтаксисом `..MixtureQuery::test_default(fertilizers)`.
+    pub(crate) fn test_default(fertilizers: Vec<Fertilizer>) -> Self {
+        let ratios = ElemRatios::default();
+        MixtureQuery {
+            fertilizers,
+            N_ratio: ratios.n_to_p,
+            K_ratio: ratios.k_to_p,
+            Mg_ratio: ratios.mg_to_p,
+            secondary_ratios: Default::default(),
+            nitrogen_form_limit: None,
+            profile: Default::default(),
+            crop: Default::default(),
+            mass: 10.0,
+            objective: Default::default(),
+            max_ingredients: None,
+            existing: None,
+            target: None,
+            solver: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ExistingMixture, MixtureObjective, MixtureQuery, TaskConstraint};
+    use crate::calculator::consts::PERMANENT_FERTILIZERS;
+    use crate::calculator::crops::Crop;
+    use crate::calculator::lp_solver::SolverBackend;
+    use crate::calculator::modified_simplex::SimplexError;
+    use crate::calculator::nitrogen::{NitrogenForm, NitrogenFormLimit};
+    use crate::calculator::profiles::{MicroFertTable, MixtureProfile};
+    use crate::calculator::{
+        Amounts, ElemName, ElemRange, ElemRatios, Fertilizer, FertilizerMinimum, TargetRatios,
+    };
+
+    /// Все перманентные удобрения, дюжина добавленных и пара ограничений на остатки.
+    /// Полный перебор базисов для задачи такого размера был бы невозможен.
+    fn big_query() -> MixtureQuery {
+        let mut fertilizers = PERMANENT_FERTILIZERS.to_vec();
+        fertilizers[2].limit = Some(1.0);
+        fertilizers[13].limit = Some(0.5);
+        for i in 0..12 {
+            let shift = i as f64;
+            fertilizers.push(Fertilizer {
+                name: format!("Удобрение {}", i).into(),
+                content: Amounts::with(&[
+                    (ElemName::Nitrogen, 5.0 + shift),
+                    (ElemName::Phosphorus, 20.0 - shift),
+                    (ElemName::Potassium, 3.0 * shift),
+                    (ElemName::Magnesium, shift % 3.0),
+                ]),
+                limit: (i % 4 == 0).then_some(2.0),
+                ..Default::default()
+            });
+        }
+        MixtureQuery::test_default(fertilizers)
+    }
+
+    fn permanent(id: usize, limit: Option<f64>) -> Fertilizer {
+        Fertilizer { limit, ..PERMANENT_FERTILIZERS[id].clone() }
+    }
+
+    fn assert_valid_solution(query: &MixtureQuery) {
+        let solution = query.find_solution().expect("Mixture must be solvable");
+        let total: f64 = solution
+            .components
+            .iter()
+            .map(|(_, weight)| weight)
+            .sum::<f64>()
+            + query.existing.map_or(0.0, |existing| existing.mass);
+        assert!((total - query.mass).abs() < 1e-9);
+        assert!((solution.total_weight - query.mass).abs() < 1e-9);
+        for (fertilizer, weight) in solution.components.iter() {
+            assert!(*weight >= 0.0);
+            if let Some(limit) = fertilizer.limit {
+                assert!(*weight <= limit + 1e-9);
+            }
+            if let Some(minimum) = fertilizer.minimum {
+                assert!(*weight >= minimum.rate(query.mass) * query.mass - 1e-9);
             }
         }
-        Deficites {
-            N: lacking.N > 0.0,
-            P: lacking.P > 0.0,
-            K: lacking.K > 0.0,
-            Mg: lacking.Mg > 0.0,
+        let ratios = [query.N_ratio, query.K_ratio, query.Mg_ratio];
+        for ((_, ratio), range) in solution.relation.iter().zip(ratios) {
+            assert!(range.from - 1e-6 <= *ratio && *ratio <= range.to + 1e-6);
         }
     }
+
+    #[test]
+    fn test_big_mixture() {
+        assert_valid_solution(&big_query());
+    }
+
+    /// Узкие диапазоны и удобрения, в которых нет части элементов, дают вырожденные вершины,
+    /// в которых выбор наибольшей оценки z - c зацикливался до `TooManySteps`.
+    #[test]
+    fn test_degenerate_mixtures() {
+        assert_valid_solution(&MixtureQuery {
+            N_ratio: ElemRange { from: 1.854, to: 1.914 },
+            K_ratio: ElemRange { from: 1.476, to: 1.486 },
+            Mg_ratio: ElemRange { from: 0.42, to: 0.43 },
+            ..MixtureQuery::test_default(vec![
+                permanent(0, None),
+                permanent(1, Some(2.0)),
+                permanent(5, None),
+                permanent(6, None),
+                permanent(9, Some(1.6)),
+                permanent(10, None),
+                permanent(12, None),
+                permanent(15, Some(2.5)),
+            ])
+        });
+        assert_valid_solution(&MixtureQuery {
+            N_ratio: ElemRange { from: 1.08, to: 1.14 },
+            K_ratio: ElemRange { from: 2.628, to: 2.738 },
+            Mg_ratio: ElemRange { from: 0.2975, to: 0.3075 },
+            ..MixtureQuery::test_default(vec![
+                permanent(1, None),
+                permanent(2, Some(1.0)),
+                permanent(4, Some(1.7)),
+                permanent(5, Some(2.9)),
+                permanent(6, Some(0.4)),
+                permanent(9, None),
+                permanent(10, None),
+                permanent(11, None),
+                permanent(14, Some(1.4)),
+            ])
+        });
+    }
+
+    /// Вырожденная и при этом действительно несовместная задача: азот, кроме как с фосфором,
+    /// можно получить лишь из магниевой селитры, но тогда магния будет слишком много.
+    #[test]
+    fn test_degenerate_infeasible_mixture() {
+        let query = MixtureQuery {
+            N_ratio: ElemRange { from: 2.178, to: 2.288 },
+            K_ratio: ElemRange { from: 1.008, to: 1.018 },
+            Mg_ratio: ElemRange { from: 0.329, to: 0.339 },
+            ..MixtureQuery::test_default(
+                [4, 6, 7, 9, 10, 14, 15]
+                    .into_iter()
+                    .map(|id| permanent(id, None))
+                    .collect(),
+            )
+        };
+        assert!(matches!(
+            query.build_task(&[]).solve_min(),
+            Err(SimplexError::NotFeasible)
+        ));
+    }
+
+    #[test]
+    fn test_magnesium_deficite() {
+        let mut query = MixtureQuery {
+            fertilizers: [0, 8, 10]
+                .into_iter()
+                .map(|id| permanent(id, None))
+                .collect(),
+            ..big_query()
+        };
+        let deficites = query.find_deficites();
+        assert!(
+            deficites[ElemName::Nitrogen].is_none()
+                && deficites[ElemName::Phosphorus].is_none()
+                && deficites[ElemName::Potassium].is_none()
+        );
+        let deficite = deficites[ElemName::Magnesium]
+            .as_ref()
+            .expect("There is no magnesium at all");
+        assert!(deficite.amount > 0.0);
+        assert!(deficite.percent > 99.0 && deficite.percent <= 100.0 + 1e-9);
+        for pair in deficite.remedies.windows(2) {
+            assert!(pair[0].1 <= pair[1].1);
+        }
+        let (best_idx, _) = deficite.remedies[0];
+        assert!(PERMANENT_FERTILIZERS[best_idx][ElemName::Magnesium] > 0.0);
+
+        query.fertilizers.push(permanent(best_idx, None));
+        assert!(!query.find_deficites().any());
+        assert_valid_solution(&query);
+    }
+
+    #[test]
+    fn test_cost_objective() {
+        let mut query = big_query();
+        for (i, fertilizer) in query.fertilizers.iter_mut().enumerate() {
+            fertilizer.price = Some(50.0 + 37.0 * (i % 7) as f64);
+        }
+        let cheapest = |query: &MixtureQuery| {
+            let solution = query.find_solution().expect("Mixture must be solvable");
+            solution.total_price.expect("All prices are known")
+        };
+        let mass_price = cheapest(&query);
+        query.objective = MixtureObjective::Cost;
+        assert_valid_solution(&query);
+        assert!(cheapest(&query) <= mass_price + 1e-9);
+        query.objective = MixtureObjective::Ballast;
+        assert_valid_solution(&query);
+    }
+
+    #[test]
+    fn test_cost_objective_deficite() {
+        // Псевдо-удобрения бесплатны, но не должны вытеснять из смеси настоящие
+        let with_prices = |mut fertilizers: Vec<Fertilizer>| {
+            for (i, fertilizer) in fertilizers.iter_mut().enumerate() {
+                fertilizer.price = Some(50.0 + 37.0 * (i % 7) as f64);
+            }
+            fertilizers
+        };
+        let mut query = MixtureQuery { objective: MixtureObjective::Cost, ..big_query() };
+        query.fertilizers = with_prices(query.fertilizers);
+        assert!(!query.find_deficites().any());
+
+        query.fertilizers = with_prices([0, 8, 10].map(|id| permanent(id, None)).to_vec());
+        let deficites = query.find_deficites();
+        assert!(
+            deficites[ElemName::Nitrogen].is_none()
+                && deficites[ElemName::Phosphorus].is_none()
+                && deficites[ElemName::Potassium].is_none()
+        );
+        assert!(deficites[ElemName::Magnesium].is_some());
+    }
+
+    #[test]
+    fn test_minimum_usage() {
+        let mut query = big_query();
+        let unconstrained = query.find_solution().expect("Mixture must be solvable");
+        let unused_idx = unconstrained
+            .components
+            .iter()
+            .position(|(_, weight)| *weight == 0.0)
+            .expect("Some fertilizers are not used");
+        query.fertilizers[unused_idx].minimum = Some(FertilizerMinimum::Mass(1.5));
+        assert_valid_solution(&query);
+        let solution = query.find_solution().unwrap();
+        assert!((solution.components[unused_idx].1 - 1.5).abs() < 1e-9);
+        // Принуждение к лишнему удобрению не может улучшить смесь
+        let binding = solution
+            .bindings
+            .iter()
+            .find(|b| b.constraint == TaskConstraint::Minimum(unused_idx))
+            .expect("Minimum must be binding");
+        assert_eq!(binding.value, 1.5);
+
+        query.fertilizers[unused_idx].minimum = Some(FertilizerMinimum::Share(25.0));
+        assert_valid_solution(&query);
+        let solution = query.find_solution().unwrap();
+        assert!((solution.components[unused_idx].1 - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_existing_mixture() {
+        let mut query = big_query();
+        // Ведро смеси с избытком азота: дополнение должно его разбавить
+        let existing = ExistingMixture {
+            mass: 4.0,
+            content: Amounts::with(&[
+                (ElemName::Nitrogen, 20.0),
+                (ElemName::Phosphorus, 5.0),
+                (ElemName::Potassium, 5.0),
+            ]),
+        };
+        query.existing = Some(existing);
+        assert_valid_solution(&query);
+        let solution = query.find_solution().unwrap();
+        let added: f64 = solution.components.iter().map(|(_, weight)| weight).sum();
+        assert!((added - 6.0).abs() < 1e-9);
+        // Концентрация - для всей партии, вместе с уже смешанным
+        let nitrogen: f64 = solution
+            .components
+            .iter()
+            .map(|(fertilizer, weight)| fertilizer[ElemName::Nitrogen] * weight)
+            .sum::<f64>()
+            + existing.content[ElemName::Nitrogen] * existing.mass;
+        assert!((solution.concentration[ElemName::Nitrogen] - nitrogen / query.mass).abs() < 1e-9);
+    }
+
+    /// Кодирует запрос в параметры ссылки и разбирает их обратно
+    fn url_roundtrip(query: &MixtureQuery) -> MixtureQuery {
+        let url_query: Vec<(String, String)> = query
+            .to_url_query()
+            .expect("Query has fertilizers")
+            .into_iter()
+            .map(|(param, value)| (param.to_string(), value))
+            .collect();
+        MixtureQuery::from_query_map(&url_query).expect("URL must be parsable")
+    }
+
+    /// Ссылка сохраняет запрос целиком, кроме внутренних номеров удобрений
+    fn assert_roundtrip(query: &MixtureQuery) {
+        let mut parsed = url_roundtrip(query);
+        for (parsed, fertilizer) in parsed.fertilizers.iter_mut().zip(query.fertilizers.iter()) {
+            parsed.id = fertilizer.id;
+        }
+        assert_eq!(&parsed, query);
+    }
+
+    #[test]
+    fn test_url_roundtrip() {
+        let mut query = big_query();
+        assert_roundtrip(&query);
+        query.objective = MixtureObjective::Cost;
+        query.fertilizers[3].price = Some(120.5);
+        query.fertilizers[4].minimum = Some(FertilizerMinimum::Mass(0.5));
+        query.fertilizers[5].minimum = Some(FertilizerMinimum::Share(12.5));
+        query.existing = Some(ExistingMixture {
+            mass: 2.5,
+            content: Amounts::with(&[
+                (ElemName::Nitrogen, 10.0),
+                (ElemName::Phosphorus, 8.0),
+                (ElemName::Potassium, 6.5),
+                (ElemName::Magnesium, 1.0),
+            ]),
+        });
+        assert_roundtrip(&query);
+        query.target = Some(TargetRatios { n_to_p: 1.8, k_to_p: 1.75, mg_to_p: 0.35 });
+        assert_roundtrip(&query);
+        let sodium = ElemName::from_symbol("Na").expect("Sodium is in the registry");
+        query.fertilizers[1][ElemName::Calcium] = 12.5;
+        query.fertilizers[1][sodium] = 0.3;
+        query
+            .secondary_ratios
+            .set(ElemName::Sulfur, Some(ElemRange { from: 0.2, to: 1.1 }));
+        assert_roundtrip(&query);
+        query.nitrogen_form_limit =
+            Some(NitrogenFormLimit { form: NitrogenForm::Ammonium, max_share: 40.0 });
+        assert_roundtrip(&query);
+        query.profile = MixtureProfile::Seedling2a;
+        assert_roundtrip(&query);
+        query.crop = Crop::Tomato;
+        assert_roundtrip(&query);
+        query.solver = SolverBackend::Tableau;
+        assert_roundtrip(&query);
+    }
+
+    #[test]
+    fn test_solver_backends() {
+        let query = big_query();
+        let total_mass = |query: &MixtureQuery| -> f64 {
+            let solution = query.find_solution().expect("Solution must exist");
+            solution.components.iter().map(|(_, weight)| weight).sum()
+        };
+        let default_mass = total_mass(&query);
+        for solver in SolverBackend::ALL {
+            let query = MixtureQuery { solver, ..query.clone() };
+            assert_eq!(query.build_task(&[]).backend, solver);
+            assert!((total_mass(&query) - default_mass).abs() < 1e-6);
+        }
+    }
+
+    /// В старых ссылках хлор и сера были флагами, а содержание бралось из встроенной таблицы
+    #[test]
+    fn test_legacy_fertilizer_url() {
+        let kcl = MixtureQuery::parse_fertilizer_from_query(
+            "Хлорид+калия:N:0,P:0,K:60,Mg:0,Cl:t,S:f",
+            PERMANENT_FERTILIZERS,
+        )
+        .expect("Legacy fertilizer must be parsable");
+        assert_eq!(kcl[ElemName::Chlorine], 47.0);
+        assert_eq!(kcl[ElemName::Sulfur], 0.0);
+        let custom = MixtureQuery::parse_fertilizer_from_query(
+            "Своё:N:10,P:10,K:10,Mg:0,Cl:t,S:t",
+            PERMANENT_FERTILIZERS,
+        )
+        .expect("Legacy fertilizer must be parsable");
+        assert_eq!(
+            (
+                custom[ElemName::Chlorine],
+                custom[ElemName::Sulfur],
+                custom[ElemName::Calcium]
+            ),
+            (0.0, 0.0, 0.0)
+        );
+        assert!(MixtureQuery::parse_fertilizer_from_query("Своё:N:10,P:10,K:10", &[]).is_none());
+    }
+
+    #[test]
+    fn test_secondary_ratio() {
+        let mut query = MixtureQuery {
+            fertilizers: [0, 4, 5, 9, 10, 12, 14, 15]
+                .into_iter()
+                .map(|id| permanent(id, None))
+                .collect(),
+            ..big_query()
+        };
+        let calcium = ElemRange { from: 0.8, to: 2.0 };
+        query.secondary_ratios.set(ElemName::Calcium, Some(calcium));
+        assert_valid_solution(&query);
+        let solution = query.find_solution().expect("Mixture must be solvable");
+        let ca_to_p = solution.concentration[ElemName::Calcium]
+            / solution.concentration[ElemName::Phosphorus];
+        assert!(calcium.from - 1e-6 <= ca_to_p && ca_to_p <= calcium.to + 1e-6);
+        assert!(solution
+            .components
+            .iter()
+            .any(|(fertilizer, _)| fertilizer.name == PERMANENT_FERTILIZERS[5].name));
+
+        query
+            .fertilizers
+            .retain(|fertilizer| fertilizer[ElemName::Calcium] == 0.0);
+        let deficites = query.find_deficites();
+        let deficite = deficites[ElemName::Calcium]
+            .as_ref()
+            .expect("There is no calcium at all");
+        assert!(deficite.percent > 99.0);
+        let (best_idx, _) = deficite.remedies[0];
+        assert!(PERMANENT_FERTILIZERS[best_idx][ElemName::Calcium] > 0.0);
+    }
+
+    #[test]
+    fn test_nitrogen_form_limit() {
+        let mut query = MixtureQuery {
+            fertilizers: [0, 4, 10, 14]
+                .into_iter()
+                .map(|id| permanent(id, None))
+                .collect(),
+            profile: MixtureProfile::Seedling2a,
+            ..big_query()
+        };
+        let ammonium_share = |query: &MixtureQuery| {
+            let solution = query.find_solution().expect("Mixture must be solvable");
+            let forms = solution.nitrogen_forms;
+            assert!(forms.total() <= solution.concentration[ElemName::Nitrogen] + 1e-9);
+            let has_remark = solution
+                .remarks
+                .iter()
+                .any(|remark| remark.class == "warning");
+            (
+                100.0 * forms[NitrogenForm::Ammonium] / forms.total(),
+                has_remark,
+            )
+        };
+        let (unlimited_share, unlimited_remark) = ammonium_share(&query);
+        assert!(unlimited_share > 50.0);
+        assert!(unlimited_remark);
+        // Взрослым растениям аммонийный азот не вредит, и для подкормок гряд смесь та же
+        let weekly_feed = MixtureQuery { profile: MixtureProfile::WeeklyFeed, ..query.clone() };
+        assert_eq!(ammonium_share(&weekly_feed), (unlimited_share, false));
+
+        let max_share = 40.0;
+        query.nitrogen_form_limit =
+            Some(NitrogenFormLimit { form: NitrogenForm::Ammonium, max_share });
+        // Без калийной и магниевой селитры нитратного азота меньше аммонийного
+        assert_eq!(query.find_solution(), Err(SimplexError::NotFeasible));
+        query.fertilizers.push(permanent(12, None));
+        query.fertilizers.push(permanent(15, None));
+        assert_valid_solution(&query);
+        let (limited_share, limited_remark) = ammonium_share(&query);
+        assert!(limited_share <= max_share + 1e-6);
+        assert!(!limited_remark);
+    }
+
+    #[test]
+    fn test_target_reachable() {
+        let mut query = big_query();
+        let target = TargetRatios::middle_of(&ElemRatios::default());
+        query.target = Some(target);
+        let solution = query
+            .find_solution()
+            .expect("Target mode is always solvable");
+        let total: f64 = solution.components.iter().map(|(_, weight)| weight).sum();
+        assert!((total - query.mass).abs() < 1e-9);
+        for (range_name, deviation) in solution.target_deviation.unwrap() {
+            assert!(deviation.abs() < 1e-6, "{:?}: {}", range_name, deviation);
+        }
+    }
+
+    #[test]
+    fn test_target_closest_mixture() {
+        // Из азофоски и сульфата магния N:P и K:P всегда равны единице
+        let mut query = big_query();
+        query.fertilizers = vec![permanent(2, None), permanent(14, None)];
+        assert_eq!(query.find_solution(), Err(SimplexError::NotFeasible));
+        query.target = Some(TargetRatios { n_to_p: 1.8, k_to_p: 1.5, mg_to_p: 0.3 });
+        let solution = query
+            .find_solution()
+            .expect("Target mode is always solvable");
+        let total: f64 = solution.components.iter().map(|(_, weight)| weight).sum();
+        assert!((total - query.mass).abs() < 1e-9);
+        let expected = [-0.8, -0.5, 0.0];
+        for ((_, deviation), expected) in solution.target_deviation.unwrap().iter().zip(expected) {
+            assert!((deviation - expected).abs() < 1e-6);
+        }
+    }
+
+    /// Готовая смесь показывает только таблицы, относящиеся к её профилю
+    #[test]
+    fn test_profile_sections() {
+        let mut query = big_query();
+        let solution = query.find_solution().expect("Mixture must be solvable");
+        let tables: Vec<_> = solution
+            .microferts
+            .iter()
+            .map(|(table, _)| *table)
+            .collect();
+        assert_eq!(
+            tables,
+            [MicroFertTable::Ugarova2, MicroFertTable::Mittleider]
+        );
+        assert!(!solution.ground_dozes.is_empty());
+        assert!(solution.seedling_dozes.is_empty());
+        // Томатам на тех же почвах нужно больше смеси
+        let tomato_solution = MixtureQuery { crop: Crop::Tomato, ..query.clone() }
+            .find_solution()
+            .expect("Mixture must be solvable");
+        assert!(tomato_solution.ground_dozes[0].1.from > solution.ground_dozes[0].1.from);
+
+        query.profile = MixtureProfile::Seedling2a;
+        let solution = query.find_solution().expect("Mixture must be solvable");
+        assert_eq!(solution.microferts[0].0, MicroFertTable::Ugarova2a);
+        assert!(solution.ground_dozes.is_empty());
+        assert!(!solution.seedling_dozes.is_empty());
+
+        query.profile = MixtureProfile::Custom;
+        let solution = query.find_solution().expect("Mixture must be solvable");
+        assert_eq!(solution.microferts.len(), 3);
+        assert!(!solution.ground_dozes.is_empty());
+        assert!(!solution.seedling_dozes.is_empty());
+    }
 }
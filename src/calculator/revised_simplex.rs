@@ -0,0 +1,921 @@
+//! Модифицированный (revised) симплекс-метод для больших каталогов удобрений.
+//!
+//! В отличие от `inverse_simplex::InverseBasisSimplex`, обратная матрица базиса
+//! не вычисляется заново на каждой итерации. Базисная матрица раскладывается в LU
+//! (с выбором ведущего элемента по столбцу), а каждая смена базиса дописывает к разложению
+//! мультипликативную поправку (eta-матрицу, "product form of the inverse"). После
+//! `REFACTOR_INTERVAL` поправок базис раскладывается заново, чтобы не накапливать
+//! ошибки округления. Столбцы ограничений хранятся разреженно: в типичном удобрении
+//! заметная часть элементов отсутствует, а дополнительные и искусственные переменные
+//! содержат ровно по одному ненулевому коэффициенту.
+//!
+//! Границы переменных учитываются напрямую: небазисная переменная находится на одной из своих
+//! границ и может перейти на другую, не меняя базиса. Двусторонняя строка получает одну
+//! дополнительную переменную, ограниченную сверху шириной диапазона, так что ни границы,
+//! ни диапазоны не увеличивают размер базиса.
+//!
+//! Строк в задаче немного (по одной на каждое соотношение элементов и на каждый остаток),
+//! а столбцов - столько, сколько удобрений в каталоге, поэтому основное время уходит на
+//! вычисление оценок небазисных столбцов, которое здесь стоит O(число ненулевых элементов).
+//!
+//! Найденный оптимальный базис (`OptimalBasis`) используется и для анализа чувствительности:
+//! по нему, не решая задачу заново, видно, в каких пределах может меняться её параметр.
+
+use std::collections::HashSet;
+
+use crate::calculator::lp_solver::LPSolver;
+use crate::calculator::modified_simplex::{
+    ConstraintOp, LPSolution, LPTask, PivotRule, SimplexError, Tolerances, MAX_DEGENERATE_STEPS,
+    MAX_STEPS,
+};
+
+/// Число поправок к LU-разложению, после которого базис раскладывается заново
+const REFACTOR_INTERVAL: usize = 32;
+
+/// Разреженный столбец: пары (номер строки, значение)
+type SparseColumn = Vec<(usize, f64)>;
+
+fn dot(dense: &[f64], column: &SparseColumn) -> f64 {
+    column.iter().map(|(row, value)| dense[*row] * value).sum()
+}
+
+/// Задача в виде `A * X = b, lower <= X <= upper` с разреженными столбцами `A`. Как и в плотной
+/// стандартной форме, за основными переменными идут дополнительные, а за ними - искусственные.
+/// Дополнительная переменная двусторонней строки ограничена сверху шириной диапазона.
+struct SparseForm {
+    num_rows: usize,
+    columns: Vec<SparseColumn>,
+    b: Vec<f64>,
+    costs: Vec<f64>,
+    lower: Vec<f64>,
+    upper: Vec<f64>,
+    first_artificial: usize,
+    initial_basis: Vec<usize>,
+}
+
+impl SparseForm {
+    fn new(task: &LPTask) -> Self {
+        let num_rows = task.constr.len();
+        let num_vars = task.func_vec.len();
+        let mut columns: Vec<SparseColumn> = vec![Vec::new(); num_vars];
+        for (i, row) in task.constr.iter().enumerate() {
+            for (j, value) in row.left.iter().enumerate() {
+                if *value != 0.0 {
+                    columns[j].push((i, *value));
+                }
+            }
+        }
+        let (mut lower, mut upper): (Vec<f64>, Vec<f64>) = task.bounds.iter().copied().unzip();
+        // Небазисные основные переменные начинают с нижних границ. Дополнительная переменная
+        // строки становится базисной, если может принять нужное для этого значение,
+        // иначе строке нужна искусственная переменная.
+        let mut initial_basis = vec![0; num_rows];
+        let mut artificial_rows = Vec::new();
+        for (i, row) in task.constr.iter().enumerate() {
+            let residual = row.right - dot(&lower, &row_column(task, i));
+            let fict = match row.op {
+                ConstraintOp::Greater | ConstraintOp::GreaterOrEqual => Some((-1.0, f64::INFINITY)),
+                ConstraintOp::Less | ConstraintOp::LessOrEqual => Some((1.0, f64::INFINITY)),
+                ConstraintOp::Range => Some((1.0, row.range)),
+                ConstraintOp::Equal => None,
+            };
+            let mut has_basic = false;
+            if let Some((coef, fict_upper)) = fict {
+                let value = residual / coef;
+                if value >= 0.0 && value <= fict_upper {
+                    initial_basis[i] = columns.len();
+                    has_basic = true;
+                }
+                columns.push(vec![(i, coef)]);
+                lower.push(0.0);
+                upper.push(fict_upper);
+            }
+            if !has_basic {
+                artificial_rows.push((i, if residual < 0.0 { -1.0 } else { 1.0 }));
+            }
+        }
+        let first_artificial = columns.len();
+        for (i, coef) in artificial_rows {
+            initial_basis[i] = columns.len();
+            columns.push(vec![(i, coef)]);
+            lower.push(0.0);
+            upper.push(f64::INFINITY);
+        }
+        let costs = task
+            .func_vec
+            .iter()
+            .copied()
+            .chain(std::iter::repeat_n(0.0, columns.len() - num_vars))
+            .collect();
+        Self {
+            num_rows,
+            columns,
+            b: task.constr.iter().map(|row| row.right).collect(),
+            costs,
+            lower,
+            upper,
+            first_artificial,
+            initial_basis,
+        }
+    }
+
+    fn is_artificial(&self, col: usize) -> bool {
+        col >= self.first_artificial
+    }
+
+    fn dense_column(&self, col: usize) -> Vec<f64> {
+        let mut dense = vec![0.0; self.num_rows];
+        for (row, value) in self.columns[col].iter() {
+            dense[*row] = *value;
+        }
+        dense
+    }
+}
+
+/// Строка ограничений задачи в виде разреженного столбца
+fn row_column(task: &LPTask, row: usize) -> SparseColumn {
+    task.constr[row]
+        .left
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, value)| *value != 0.0)
+        .collect()
+}
+
+/// LU-разложение `P * B = L * U` базисной матрицы с выбором ведущего элемента по столбцу.
+/// Обе треугольные матрицы хранятся в одной плотной матрице (по строкам),
+/// единичная диагональ `L` не хранится.
+struct LuFactors {
+    size: usize,
+    lu: Vec<f64>,
+    /// `perm[i]` - какая строка `B` стала i-й строкой после перестановок
+    perm: Vec<usize>,
+}
+
+impl LuFactors {
+    fn new(
+        form: &SparseForm,
+        basis_cols: &[usize],
+        pivot_tolerance: f64,
+    ) -> Result<Self, SimplexError> {
+        let size = form.num_rows;
+        let mut lu = vec![0.0; size * size];
+        for (pos, col) in basis_cols.iter().enumerate() {
+            for (row, value) in form.columns[*col].iter() {
+                lu[row * size + pos] = *value;
+            }
+        }
+        let mut perm: Vec<usize> = (0..size).collect();
+        for k in 0..size {
+            let pivot_row = (k..size)
+                .max_by(|i1, i2| lu[i1 * size + k].abs().total_cmp(&lu[i2 * size + k].abs()))
+                .expect("The range is not empty");
+            if lu[pivot_row * size + k].abs() < pivot_tolerance {
+                return Err(SimplexError::SingularBasis);
+            }
+            if pivot_row != k {
+                for j in 0..size {
+                    lu.swap(pivot_row * size + j, k * size + j);
+                }
+                perm.swap(pivot_row, k);
+            }
+            let pivot = lu[k * size + k];
+            for i in k + 1..size {
+                let factor = lu[i * size + k] / pivot;
+                lu[i * size + k] = factor;
+                if factor != 0.0 {
+                    for j in k + 1..size {
+                        lu[i * size + j] -= factor * lu[k * size + j];
+                    }
+                }
+            }
+        }
+        Ok(Self { size, lu, perm })
+    }
+
+    /// Решает систему `B * x = b`
+    fn solve(&self, b: &[f64]) -> Vec<f64> {
+        let (size, lu) = (self.size, &self.lu);
+        let mut x: Vec<f64> = self.perm.iter().map(|row| b[*row]).collect();
+        for i in 0..size {
+            for k in 0..i {
+                x[i] -= lu[i * size + k] * x[k];
+            }
+        }
+        for i in (0..size).rev() {
+            for k in i + 1..size {
+                x[i] -= lu[i * size + k] * x[k];
+            }
+            x[i] /= lu[i * size + i];
+        }
+        x
+    }
+
+    /// Решает систему `B^T * y = c`
+    fn solve_transposed(&self, c: &[f64]) -> Vec<f64> {
+        let (size, lu) = (self.size, &self.lu);
+        let mut z = c.to_vec();
+        for i in 0..size {
+            for k in 0..i {
+                z[i] -= lu[k * size + i] * z[k];
+            }
+            z[i] /= lu[i * size + i];
+        }
+        for i in (0..size).rev() {
+            for k in i + 1..size {
+                z[i] -= lu[k * size + i] * z[k];
+            }
+        }
+        let mut y = vec![0.0; size];
+        for (i, row) in self.perm.iter().enumerate() {
+            y[*row] = z[i];
+        }
+        y
+    }
+}
+
+/// Мультипликативная поправка к обратной матрице базиса после ввода в позицию `pos`
+/// столбца, который в прежнем базисе выражался вектором `column` (`B^-1 * a`)
+struct Eta {
+    pos: usize,
+    column: Vec<f64>,
+}
+
+/// Обратная матрица базиса в мультипликативной форме: `B^-1 = E_k * ... * E_1 * B_0^-1`
+struct BasisFactor {
+    lu: LuFactors,
+    etas: Vec<Eta>,
+}
+
+impl BasisFactor {
+    /// Вычисляет `B^-1 * a`
+    fn ftran(&self, a: &[f64]) -> Vec<f64> {
+        let mut x = self.lu.solve(a);
+        for Eta { pos, column } in self.etas.iter() {
+            let x_pos = x[*pos] / column[*pos];
+            for (i, x_i) in x.iter_mut().enumerate() {
+                if i == *pos {
+                    *x_i = x_pos;
+                } else {
+                    *x_i -= column[i] * x_pos;
+                }
+            }
+        }
+        x
+    }
+
+    /// Вычисляет `c * B^-1` (для строки `c`)
+    fn btran(&self, c: &[f64]) -> Vec<f64> {
+        let mut v = c.to_vec();
+        for Eta { pos, column } in self.etas.iter().rev() {
+            let others: f64 = column
+                .iter()
+                .zip(v.iter())
+                .enumerate()
+                .filter(|(i, _)| i != pos)
+                .map(|(_, (d, v))| d * v)
+                .sum();
+            v[*pos] = (v[*pos] - others) / column[*pos];
+        }
+        self.lu.solve_transposed(&v)
+    }
+}
+
+/// Текущее базисное решение. Позиции базисных переменных не меняются при смене базиса:
+/// исключаемая переменная уступает свою позицию вводимой. Небазисные переменные находятся
+/// на одной из своих границ.
+struct SimplexState<'a> {
+    form: &'a SparseForm,
+    tolerances: Tolerances,
+    basis_cols: Vec<usize>,
+    is_basic: Vec<bool>,
+    factor: BasisFactor,
+    /// Значения всех переменных, как базисных, так и небазисных
+    x: Vec<f64>,
+    lower: Vec<f64>,
+    upper: Vec<f64>,
+}
+
+impl<'a> SimplexState<'a> {
+    fn new(form: &'a SparseForm, tolerances: Tolerances) -> Result<Self, SimplexError> {
+        let basis_cols = form.initial_basis.clone();
+        let mut is_basic = vec![false; form.columns.len()];
+        for col in basis_cols.iter() {
+            is_basic[*col] = true;
+        }
+        let lu = LuFactors::new(form, &basis_cols, tolerances.pivot)?;
+        let mut state = Self {
+            form,
+            tolerances,
+            basis_cols,
+            is_basic,
+            factor: BasisFactor { lu, etas: Vec::new() },
+            x: form.lower.clone(),
+            lower: form.lower.clone(),
+            upper: form.upper.clone(),
+        };
+        state.update_basic_values();
+        Ok(state)
+    }
+
+    /// Прижимает значение переменной к границе, если оно отличается от неё на ошибку округления
+    fn snap(&self, col: usize, value: f64) -> f64 {
+        if (value - self.lower[col]).abs() <= self.tolerances.zero {
+            self.lower[col]
+        } else if (value - self.upper[col]).abs() <= self.tolerances.zero {
+            self.upper[col]
+        } else {
+            value
+        }
+    }
+
+    /// Пересчитывает базисные переменные по значениям небазисных: `B^-1 * (b - N * X_N)`
+    fn update_basic_values(&mut self) {
+        let mut rhs = self.form.b.clone();
+        for (col, column) in self.form.columns.iter().enumerate() {
+            if !self.is_basic[col] && self.x[col] != 0.0 {
+                for (row, value) in column.iter() {
+                    rhs[*row] -= value * self.x[col];
+                }
+            }
+        }
+        for (pos, value) in self.factor.ftran(&rhs).into_iter().enumerate() {
+            let col = self.basis_cols[pos];
+            self.x[col] = self.snap(col, value);
+        }
+    }
+
+    fn refactor(&mut self) -> Result<(), SimplexError> {
+        let lu = LuFactors::new(self.form, &self.basis_cols, self.tolerances.pivot)?;
+        self.factor = BasisFactor { lu, etas: Vec::new() };
+        self.update_basic_values();
+        Ok(())
+    }
+
+    /// Сдвигает переменную `col` на `step` (со знаком), пересчитывая базисные переменные;
+    /// `column` - это `B^-1 * a_col`
+    fn shift(&mut self, col: usize, step: f64, column: &[f64]) {
+        for (pos, d) in column.iter().enumerate() {
+            let basic = self.basis_cols[pos];
+            self.x[basic] = self.snap(basic, self.x[basic] - step * d);
+        }
+        self.x[col] = self.snap(col, self.x[col] + step);
+    }
+
+    /// Вводит в базис столбец `col` на позицию `pos`, когда исключаемая переменная уже
+    /// выведена на свою границу
+    fn pivot(&mut self, pos: usize, col: usize, column: Vec<f64>) -> Result<(), SimplexError> {
+        self.is_basic[self.basis_cols[pos]] = false;
+        self.is_basic[col] = true;
+        self.basis_cols[pos] = col;
+        self.factor.etas.push(Eta { pos, column });
+        if self.factor.etas.len() >= REFACTOR_INTERVAL {
+            self.refactor()?;
+        }
+        Ok(())
+    }
+
+    fn basis_costs(&self, costs: &[f64]) -> Vec<f64> {
+        self.basis_cols.iter().map(|col| costs[*col]).collect()
+    }
+
+    /// Итерации симплекс-метода с ценами `costs`. Вводить в базис можно только столбцы,
+    /// для которых `allowed` возвращает `true`. Выбор переменных - тот же, что и в
+    /// `inverse_simplex::run_simplex`: правило Данцига с переключением на правило Бленда
+    /// в вырожденных вершинах. Переменная на нижней границе может только расти,
+    /// на верхней - только уменьшаться. Если раньше всех своей границы достигает сама
+    /// вводимая переменная, базис не меняется: она просто переходит на другую границу.
+    fn run(&mut self, costs: &[f64], allowed: impl Fn(usize) -> bool) -> Result<(), SimplexError> {
+        let tolerances = self.tolerances;
+        let mut pivot_rule = PivotRule::Dantzig;
+        let mut degenerate_steps = 0_usize;
+        let mut visited_bases = HashSet::<Vec<usize>>::new();
+        for _ in 0..MAX_STEPS {
+            let y = self.factor.btran(&self.basis_costs(costs));
+            // Оценки z - c небазисных столбцов и направление, в котором переменную
+            // выгодно сдвинуть
+            let candidates = (0..self.form.columns.len())
+                .filter(|col| !self.is_basic[*col] && allowed(*col))
+                .filter(|col| self.lower[*col] < self.upper[*col])
+                .filter_map(|col| {
+                    let z_minus_c = dot(&y, &self.form.columns[col]) - costs[col];
+                    if z_minus_c > tolerances.optimality && self.x[col] < self.upper[col] {
+                        Some((col, z_minus_c, 1.0))
+                    } else if z_minus_c < -tolerances.optimality && self.x[col] > self.lower[col] {
+                        Some((col, -z_minus_c, -1.0))
+                    } else {
+                        None
+                    }
+                });
+            let entering = match pivot_rule {
+                PivotRule::Dantzig => candidates.max_by(|(_, v1, _), (_, v2, _)| v1.total_cmp(v2)),
+                PivotRule::Bland => candidates.min_by_key(|(col, _, _)| *col),
+            };
+            let Some((entering, _, direction)) = entering else {
+                return Ok(());
+            };
+            let column = self.factor.ftran(&self.form.dense_column(entering));
+            // Насколько можно сдвинуть вводимую переменную, пока базисная переменная
+            // в позиции i не достигнет своей границы
+            let ratios: Vec<(usize, f64)> = (0..column.len())
+                .filter_map(|i| {
+                    let basic = self.basis_cols[i];
+                    let rate = direction * column[i];
+                    if rate > tolerances.pivot {
+                        Some((i, (self.x[basic] - self.lower[basic]).max(0.0) / rate))
+                    } else if rate < -tolerances.pivot && self.upper[basic].is_finite() {
+                        Some((i, (self.upper[basic] - self.x[basic]).max(0.0) / -rate))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            let flip = self.upper[entering] - self.lower[entering];
+            let min_ratio = ratios
+                .iter()
+                .map(|(_, ratio)| *ratio)
+                .min_by(f64::total_cmp)
+                .unwrap_or(f64::INFINITY);
+            if flip <= min_ratio {
+                if flip.is_infinite() {
+                    return Err(SimplexError::Unbounded);
+                }
+                self.shift(entering, direction * flip, &column);
+                degenerate_steps = 0;
+                visited_bases.clear();
+                pivot_rule = PivotRule::Dantzig;
+                continue;
+            }
+            let ties = ratios
+                .iter()
+                .filter(|(_, ratio)| *ratio <= min_ratio + tolerances.zero)
+                .map(|(i, _)| *i);
+            let leaving_pos = match pivot_rule {
+                PivotRule::Dantzig => {
+                    ties.max_by(|i1, i2| column[*i1].abs().total_cmp(&column[*i2].abs()))
+                }
+                PivotRule::Bland => ties.min_by_key(|i| self.basis_cols[*i]),
+            }
+            .expect("The row with the minimal ratio is always among the ties");
+            if min_ratio <= tolerances.zero {
+                let mut basis_key = self.basis_cols.clone();
+                basis_key.sort_unstable();
+                let is_cycle = !visited_bases.insert(basis_key);
+                degenerate_steps += 1;
+                if is_cycle || degenerate_steps > MAX_DEGENERATE_STEPS {
+                    pivot_rule = PivotRule::Bland;
+                }
+            } else {
+                degenerate_steps = 0;
+                visited_bases.clear();
+                pivot_rule = PivotRule::Dantzig;
+            }
+            self.shift(entering, direction * min_ratio, &column);
+            let leaving = self.basis_cols[leaving_pos];
+            self.x[leaving] = if direction * column[leaving_pos] > 0.0 {
+                self.lower[leaving]
+            } else {
+                self.upper[leaving]
+            };
+            self.pivot(leaving_pos, entering, column)?;
+        }
+        Err(SimplexError::TooManySteps)
+    }
+
+    /// Выводит из базиса оставшиеся после первого этапа (нулевые) искусственные переменные,
+    /// так же, как `inverse_simplex::drive_out_artificials`. Оставшиеся в базисе искусственные
+    /// переменные закрепляются в нуле, чтобы на втором этапе они не могли вырасти.
+    fn drive_out_artificials(&mut self) -> Result<(), SimplexError> {
+        for col in self.form.first_artificial..self.form.columns.len() {
+            self.upper[col] = 0.0;
+        }
+        for pos in 0..self.basis_cols.len() {
+            let artificial = self.basis_cols[pos];
+            if !self.form.is_artificial(artificial) {
+                continue;
+            }
+            let mut unit = vec![0.0; self.form.num_rows];
+            unit[pos] = 1.0;
+            let pivot_row = self.factor.btran(&unit);
+            let replacement = (0..self.form.first_artificial)
+                .filter(|col| !self.is_basic[*col])
+                .map(|col| (col, dot(&pivot_row, &self.form.columns[col]).abs()))
+                .filter(|(_, v)| *v > self.tolerances.pivot)
+                .max_by(|(_, v1), (_, v2)| v1.total_cmp(v2))
+                .map(|(col, _)| col);
+            if let Some(col) = replacement {
+                let column = self.factor.ftran(&self.form.dense_column(col));
+                self.x[artificial] = 0.0;
+                self.pivot(pos, col, column)?;
+            }
+        }
+        self.refactor()
+    }
+}
+
+/// Решатель по умолчанию: двухэтапный модифицированный симплекс-метод с LU-разложением базиса
+/// и границами переменных
+pub(crate) struct RevisedSimplex;
+
+impl RevisedSimplex {
+    /// Находит оптимальный базис задачи, представленной в виде `form`
+    fn optimize<'a>(form: &'a SparseForm, task: &LPTask) -> Result<SimplexState<'a>, SimplexError> {
+        if task.bounds.iter().any(|(lower, upper)| lower > upper) {
+            return Err(SimplexError::NotFeasible);
+        }
+        let mut simplex = SimplexState::new(form, task.tolerances)?;
+        if form.first_artificial < form.columns.len() {
+            let phase1_costs: Vec<f64> = (0..form.columns.len())
+                .map(|col| if form.is_artificial(col) { 1.0 } else { 0.0 })
+                .collect();
+            simplex.run(&phase1_costs, |_| true)?;
+            let phase1_value: f64 = simplex.x[form.first_artificial..].iter().sum();
+            if phase1_value > task.tolerances.feasibility {
+                return Err(SimplexError::NotFeasible);
+            }
+            simplex.drive_out_artificials()?;
+        }
+        simplex.run(&form.costs, |col| !form.is_artificial(col))?;
+        Ok(simplex)
+    }
+
+    /// Решает задачу и вместе с решением возвращает её оптимальный базис
+    pub(crate) fn solve_with_basis(
+        &self,
+        task: &LPTask,
+    ) -> Result<(LPSolution, OptimalBasis), SimplexError> {
+        let form = SparseForm::new(task);
+        let simplex = Self::optimize(&form, task)?;
+        let solution = simplex.solution(task);
+        let (basis_cols, is_basic, x, upper) = (
+            simplex.basis_cols,
+            simplex.is_basic,
+            simplex.x,
+            simplex.upper,
+        );
+        let lu = LuFactors::new(&form, &basis_cols, task.tolerances.pivot)?;
+        let basis =
+            OptimalBasis { form, tolerances: task.tolerances, basis_cols, is_basic, lu, x, upper };
+        Ok((solution, basis))
+    }
+}
+
+impl SimplexState<'_> {
+    /// Решение задачи в текущем (оптимальном) базисе
+    fn solution(&self, task: &LPTask) -> LPSolution {
+        let form = self.form;
+        let c_b = self.basis_costs(&form.costs);
+        let duals = self.factor.btran(&c_b);
+        let num_vars = task.func_vec.len();
+        LPSolution {
+            function_value: form
+                .costs
+                .iter()
+                .zip(self.x.iter())
+                .map(|(c, x)| c * x)
+                .sum(),
+            params: self.x[..num_vars].to_vec(),
+            reduced_costs: (0..num_vars)
+                .map(|col| form.costs[col] - dot(&duals, &form.columns[col]))
+                .collect(),
+            duals: duals
+                .iter()
+                .zip(task.constr.iter())
+                .map(|(y, row)| y * row.scale)
+                .collect(),
+        }
+    }
+}
+
+impl LPSolver for RevisedSimplex {
+    fn solve_min(&self, task: &LPTask) -> Result<LPSolution, SimplexError> {
+        let form = SparseForm::new(task);
+        Ok(Self::optimize(&form, task)?.solution(task))
+    }
+}
+
+/// Многочлен `c0 + c1 * t + c2 * t^2` от величины изменения параметра
+type Quadratic = [f64; 3];
+
+/// Вещественные корни многочлена (устойчивая к потере точности формула для квадратного
+/// уравнения)
+fn real_roots([c0, c1, c2]: Quadratic) -> Vec<f64> {
+    if c2 == 0.0 {
+        return if c1 == 0.0 {
+            Vec::new()
+        } else {
+            vec![-c0 / c1]
+        };
+    }
+    let discriminant = c1 * c1 - 4.0 * c2 * c0;
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+    let q = -0.5 * (c1 + c1.signum() * discriminant.sqrt());
+    if q == 0.0 {
+        vec![0.0]
+    } else {
+        vec![q / c2, c0 / q]
+    }
+}
+
+/// Наибольший отрезок, содержащий ноль, на котором многочлен неотрицателен.
+/// Коэффициенты в пределах `tolerance` от нуля считаются нулевыми: в вырожденной вершине
+/// условие выполняется как равенство, и всё решает знак производной.
+fn nonnegative_range(poly: Quadratic, tolerance: f64) -> (f64, f64) {
+    let [c0, c1, c2] = poly.map(|c| if c.abs() <= tolerance { 0.0 } else { c });
+    if c0 < 0.0 {
+        return (0.0, 0.0);
+    }
+    let value = |t: f64| c0 + t * (c1 + t * c2);
+    let roots = real_roots([c0, c1, c2]);
+    let bound = |direction: f64| {
+        let root = roots
+            .iter()
+            .map(|root| root * direction)
+            .filter(|root| *root > 0.0)
+            .min_by(f64::total_cmp)
+            .unwrap_or(f64::INFINITY);
+        // Между нулём и ближайшим корнем знак многочлена не меняется
+        let probe = if root.is_finite() { 0.5 * root } else { 1.0 };
+        if value(direction * probe) < 0.0 {
+            0.0
+        } else {
+            direction * root
+        }
+    };
+    (bound(-1.0), bound(1.0))
+}
+
+/// Оптимальный базис решённой задачи. Позволяет, не решая задачу заново, найти, в каких
+/// пределах может меняться её параметр, пока этот базис остаётся допустимым и оптимальным
+/// (классический анализ чувствительности по правым частям, ценам и коэффициентам).
+pub(crate) struct OptimalBasis {
+    form: SparseForm,
+    tolerances: Tolerances,
+    basis_cols: Vec<usize>,
+    is_basic: Vec<bool>,
+    lu: LuFactors,
+    x: Vec<f64>,
+    /// Верхние границы переменных; оставшиеся искусственные переменные закреплены в нуле
+    upper: Vec<f64>,
+}
+
+impl OptimalBasis {
+    /// Находит отрезок значений `t`, при которых базис остаётся оптимальным для задачи
+    /// `task + t * (shifted - task)`. Задача `task` - та, для которой базис найден, а `shifted` -
+    /// та же задача с параметром, увеличенным на единицу: у неё те же строки и переменные.
+    /// Изменение параметра может затрагивать правые части, ширину диапазонов, цены и не больше
+    /// одного столбца ограничений. С изменением столбца базисная матрица меняется на матрицу
+    /// ранга один, и по формуле Шермана-Моррисона базисные переменные и оценки небазисных
+    /// столбцов, умноженные на `1 + alpha * t`, оказываются многочленами не выше второй степени.
+    /// Если строки задач устроены по-разному (например, диапазон с совпавшими концами стал
+    /// равенством), базис другую задачу не описывает, и отрезок вырождается в точку.
+    pub fn stable_range(&self, task: &LPTask, shifted: &LPTask) -> (f64, f64) {
+        let kind = |op: ConstraintOp| match op {
+            ConstraintOp::Equal => 0,
+            ConstraintOp::Range => 1,
+            _ => 2,
+        };
+        if task
+            .constr
+            .iter()
+            .zip(shifted.constr.iter())
+            .any(|(row, shifted_row)| kind(row.op) != kind(shifted_row.op))
+        {
+            return (0.0, 0.0);
+        }
+        let form = &self.form;
+        let num_rows = form.num_rows;
+        let num_vars = task.func_vec.len();
+        let num_cols = form.columns.len();
+
+        // Изменения задачи в тех единицах, в которых хранятся строки `task`
+        let mut changed_column = None::<(usize, Vec<f64>)>;
+        let mut d_b = vec![0.0; num_rows];
+        let mut d_lower = vec![0.0; num_cols];
+        let mut d_upper = vec![0.0; num_cols];
+        let bound_change = |from: f64, to: f64| if from == to { 0.0 } else { to - from };
+        for (var, ((lower, upper), (shifted_lower, shifted_upper))) in
+            task.bounds.iter().zip(shifted.bounds.iter()).enumerate()
+        {
+            d_lower[var] = bound_change(*lower, *shifted_lower);
+            d_upper[var] = bound_change(*upper, *shifted_upper);
+        }
+        let mut slack = num_vars;
+        for (i, (row, shifted_row)) in task.constr.iter().zip(shifted.constr.iter()).enumerate() {
+            let k = row.scale / shifted_row.scale;
+            for var in 0..num_vars {
+                let d = shifted_row.left[var] * k - row.left[var];
+                if d.abs() <= self.tolerances.zero {
+                    continue;
+                }
+                let (col, column) =
+                    changed_column.get_or_insert_with(|| (var, vec![0.0; num_rows]));
+                assert_eq!(
+                    *col, var,
+                    "Параметр должен входить в один столбец ограничений"
+                );
+                column[i] = d;
+            }
+            // Для диапазона при смене знака меняются местами его концы
+            let right = match shifted_row.op {
+                ConstraintOp::Range if k < 0.0 => k * (shifted_row.right - shifted_row.range),
+                _ => k * shifted_row.right,
+            };
+            d_b[i] = right - row.right;
+            if !matches!(row.op, ConstraintOp::Equal) {
+                if matches!(row.op, ConstraintOp::Range) {
+                    d_upper[slack] = k.abs() * shifted_row.range - row.range;
+                }
+                slack += 1;
+            }
+        }
+        let d_costs: Vec<f64> = (0..num_cols)
+            .map(|col| {
+                if col < num_vars {
+                    shifted.func_vec[col] - task.func_vec[col]
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+
+        // Небазисные переменные остаются на своих границах, которые тоже могут двигаться
+        let nonbasic_shift = |col: usize| {
+            if self.x[col] == form.lower[col] {
+                d_lower[col]
+            } else {
+                d_upper[col]
+            }
+        };
+        let mut r0 = form.b.clone();
+        let mut r1 = d_b;
+        let mut r2 = vec![0.0; num_rows];
+        for col in (0..num_cols).filter(|col| !self.is_basic[*col]) {
+            let dx = nonbasic_shift(col);
+            for (row, value) in form.columns[col].iter() {
+                r0[*row] -= value * self.x[col];
+                r1[*row] -= value * dx;
+            }
+            if let Some((_, column)) = changed_column.as_ref().filter(|(var, _)| *var == col) {
+                for (i, d) in column.iter().enumerate() {
+                    r1[i] -= d * self.x[col];
+                    r2[i] -= d * dx;
+                }
+            }
+        }
+        let p0 = self.lu.solve(&r0);
+        let p1 = self.lu.solve(&r1);
+        let p2 = self.lu.solve(&r2);
+
+        // Изменение столбца базисной переменной: B(t) = B + t * d * e_k^T
+        let basic_change = changed_column.as_ref().and_then(|(var, column)| {
+            let pos = self.basis_cols.iter().position(|col| col == var)?;
+            Some((pos, self.lu.solve(column)))
+        });
+        let (alpha, w, v) = match &basic_change {
+            Some((pos, w)) => {
+                let mut unit = vec![0.0; num_rows];
+                unit[*pos] = 1.0;
+                (w[*pos], w.clone(), self.lu.solve_transposed(&unit))
+            }
+            None => (0.0, vec![0.0; num_rows], vec![0.0; num_rows]),
+        };
+        let basic_pos = basic_change.as_ref().map_or(0, |(pos, _)| *pos);
+        let times_denominator = |value: f64, change: f64| -> Quadratic {
+            [value, alpha * value + change, alpha * change]
+        };
+
+        let mut conditions = vec![([1.0, alpha, 0.0], self.tolerances.zero)];
+        // Базисные переменные, умноженные на знаменатель, в пределах своих границ
+        for (pos, col) in self.basis_cols.iter().enumerate() {
+            let basic: Quadratic = [
+                p0[pos],
+                p1[pos] + alpha * p0[pos] - w[pos] * p0[basic_pos],
+                p2[pos] + alpha * p1[pos] - w[pos] * p1[basic_pos],
+            ];
+            let tolerance = self.tolerances.feasibility;
+            let lower = times_denominator(form.lower[*col], d_lower[*col]);
+            conditions.push(([0, 1, 2].map(|n| basic[n] - lower[n]), tolerance));
+            if self.upper[*col].is_finite() {
+                let upper = times_denominator(self.upper[*col], d_upper[*col]);
+                conditions.push(([0, 1, 2].map(|n| upper[n] - basic[n]), tolerance));
+            }
+        }
+
+        // Двойственные оценки, умноженные на знаменатель
+        let c_b: Vec<f64> = self.basis_cols.iter().map(|col| form.costs[*col]).collect();
+        let d_c_b: Vec<f64> = self.basis_cols.iter().map(|col| d_costs[*col]).collect();
+        let y0 = self.lu.solve_transposed(&c_b);
+        let dy = self.lu.solve_transposed(&d_c_b);
+        let w_c = w.iter().zip(c_b.iter()).map(|(w, c)| w * c).sum::<f64>();
+        let w_dc = w.iter().zip(d_c_b.iter()).map(|(w, c)| w * c).sum::<f64>();
+        let y1: Vec<f64> = (0..num_rows)
+            .map(|i| dy[i] + alpha * y0[i] - v[i] * w_c)
+            .collect();
+        let y2: Vec<f64> = (0..num_rows).map(|i| alpha * dy[i] - v[i] * w_dc).collect();
+        // Оценки небазисных столбцов: на нижней границе неотрицательны, на верхней - неположительны
+        for (col, column) in form.columns[..form.first_artificial].iter().enumerate() {
+            if self.is_basic[col] || form.lower[col] == self.upper[col] {
+                continue;
+            }
+            let mut reduced = times_denominator(form.costs[col], d_costs[col]);
+            reduced[0] -= dot(&y0, column);
+            reduced[1] -= dot(&y1, column);
+            reduced[2] -= dot(&y2, column);
+            if let Some((_, d)) = changed_column.as_ref().filter(|(var, _)| *var == col) {
+                let d_y0 = d.iter().zip(y0.iter()).map(|(d, y)| d * y).sum::<f64>();
+                let d_y1 = d.iter().zip(y1.iter()).map(|(d, y)| d * y).sum::<f64>();
+                reduced[1] -= d_y0;
+                reduced[2] -= d_y1;
+            }
+            if self.x[col] != form.lower[col] {
+                reduced = reduced.map(|c| -c);
+            }
+            conditions.push((reduced, self.tolerances.optimality));
+        }
+
+        conditions
+            .into_iter()
+            .map(|(poly, tolerance)| nonnegative_range(poly, tolerance))
+            .fold(
+                (f64::NEG_INFINITY, f64::INFINITY),
+                |(from, to), (lower, upper)| (from.max(lower), to.min(upper)),
+            )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use crate::calculator::consts::PERMANENT_FERTILIZERS;
+    use crate::calculator::inverse_simplex::InverseBasisSimplex;
+    use crate::calculator::lp_solver::test::large_catalog_query;
+    use crate::calculator::modified_simplex::{ConstraintOp, LPTask};
+
+    use super::RevisedSimplex;
+
+    fn measure(task: &LPTask, solve: fn(&LPTask) -> bool) -> Duration {
+        const REPEATS: u32 = 5;
+        let start = Instant::now();
+        for _ in 0..REPEATS {
+            assert!(solve(task));
+        }
+        start.elapsed() / REPEATS
+    }
+
+    /// Сравнение скорости с прежней реализацией:
+    /// `cargo test --release bench_catalog_sizes -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_catalog_sizes() {
+        for count in [0, 50, 100, 200, 400] {
+            let task = large_catalog_query(count, 42).build_task(&[]);
+            let revised = measure(&task, |task| task.solve_min().is_ok());
+            let inverse = measure(&task, |task| {
+                task.solve_min_with(&InverseBasisSimplex).is_ok()
+            });
+            println!(
+                "{:4} удобрений: LU {:>10.3?}, обращение базиса {:>10.3?}",
+                count + PERMANENT_FERTILIZERS.len(),
+                revised,
+                inverse
+            );
+        }
+    }
+
+    /// Задача `min c1 * x + y` при `a11 * x + 2y >= b1`, `3x + y >= 3`. При `c1 = a11 = 1`,
+    /// `b1 = 2` оптимум - пересечение прямых, и базис остаётся оптимальным при
+    /// `c1` от 0.5 до 3, `b1` от 1 до 6 и `a11` не больше 2.
+    fn two_rows_task(c1: f64, a11: f64, b1: f64) -> LPTask {
+        let mut task = LPTask::new(&[c1, 1.0]);
+        task.add_constr(&[a11, 2.0], ConstraintOp::GreaterOrEqual, b1);
+        task.add_constr(&[3.0, 1.0], ConstraintOp::GreaterOrEqual, 3.0);
+        task
+    }
+
+    #[test]
+    fn test_stable_range() {
+        let task = two_rows_task(1.0, 1.0, 2.0);
+        let (solution, basis) = RevisedSimplex.solve_with_basis(&task).unwrap();
+        assert!((solution.params[0] - 0.8).abs() < 1e-12);
+        assert!((solution.params[1] - 0.6).abs() < 1e-12);
+        let assert_range = |shifted: LPTask, expected: (f64, f64)| {
+            let (from, to) = basis.stable_range(&task, &shifted);
+            assert!(
+                (from - expected.0).abs() < 1e-9 || from == expected.0,
+                "{}",
+                from
+            );
+            assert!((to - expected.1).abs() < 1e-9 || to == expected.1, "{}", to);
+        };
+        assert_range(two_rows_task(2.0, 1.0, 2.0), (-0.5, 2.0));
+        assert_range(two_rows_task(1.0, 1.0, 3.0), (-1.0, 4.0));
+        // Коэффициент меняет нормировку строки, но не границы устойчивости
+        assert_range(two_rows_task(1.0, 2.0, 2.0), (f64::NEG_INFINITY, 1.0));
+        assert_range(two_rows_task(1.0, 0.0, 2.0), (-1.0, f64::INFINITY));
+    }
+}
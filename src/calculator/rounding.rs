@@ -0,0 +1,460 @@
+//! Округление рецепта до весов, которые реально отмерить: каждое удобрение берётся
+//! в количестве, кратном выбранному шагу (10 г, целые мешки по 1 кг и т.п.).
+//! Поиск ведётся методом ветвей и границ поверх `LPTask`, так что соотношения элементов
+//! по-прежнему остаются в заданных пределах.
+
+use crate::calculator::consts::{MAX_BRANCH_NODES, TARGET_ROUNDING_TOLERANCE};
+use crate::calculator::modified_simplex::{ConstraintOp, LPTask, SimplexError};
+use crate::calculator::query::{MixtureObjective, MixtureQuery};
+use crate::calculator::{ElemName, ElemRange, ElemRangeName};
+
+/// Насколько значение может отличаться от целого, чтобы всё ещё считаться целым
+const INTEGRALITY_TOLERANCE: f64 = 1e-6;
+
+/// Ветка не исследуется, если может улучшить уже найденный рецепт меньше, чем на эту величину
+const OBJECTIVE_TOLERANCE: f64 = 1e-9;
+
+/// Относительный запас по фосфору, компенсирующий погрешность точного решения
+const PHOSPHORUS_TOLERANCE: f64 = 1e-9;
+
+/// Рецепт с весами, кратными шагу округления
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RoundedRecipe {
+    /// Веса удобрений (кг), в том же порядке, что и в запросе
+    pub weights: Vec<f64>,
+    /// Масса всей партии, вместе с уже приготовленной частью смеси
+    pub total_weight: f64,
+    /// Соотношения элементов к фосфору в округлённом рецепте
+    pub relation: [(ElemRangeName, f64); 3],
+    /// Насколько соотношения изменились по сравнению с точным рецептом
+    pub deviation: [(ElemRangeName, f64); 3],
+}
+
+/// Соотношения элементов к фосфору во всей партии (с уже приготовленной частью смеси)
+/// при заданных весах удобрений
+fn relation(query: &MixtureQuery, weights: &[f64]) -> [(ElemRangeName, f64); 3] {
+    let existing = query
+        .existing
+        .map(|existing| (existing.as_fertilizer(), existing.mass));
+    let amount = |element: ElemName| -> f64 {
+        query
+            .fertilizers
+            .iter()
+            .zip(weights.iter())
+            .chain(
+                existing
+                    .iter()
+                    .map(|(fertilizer, weight)| (fertilizer, weight)),
+            )
+            .map(|(fertilizer, weight)| fertilizer[element] * weight)
+            .sum()
+    };
+    let p_amount = amount(ElemName::Phosphorus);
+    ElemRangeName::ALL.map(|range_name| (range_name, amount(range_name.element()) / p_amount))
+}
+
+/// Пределы соотношения для округлённого рецепта. В режиме идеальных соотношений
+/// это отрезок между идеалом и соотношением точного рецепта, расширенный
+/// на `TARGET_ROUNDING_TOLERANCE`: округление не должно уводить смесь от идеала заметно
+/// дальше, чем точный рецепт.
+fn rounding_range(
+    query: &MixtureQuery,
+    exact_relation: &[(ElemRangeName, f64); 3],
+    range_name: ElemRangeName,
+) -> ElemRange {
+    let Some(target) = query.target else {
+        return *query.ratio(range_name);
+    };
+    let (_, exact) = exact_relation
+        .iter()
+        .find(|(name, _)| *name == range_name)
+        .expect("Relation contains every range");
+    let target = target[range_name];
+    ElemRange {
+        from: (target.min(*exact) - TARGET_ROUNDING_TOLERANCE).max(0.0),
+        to: target.max(*exact) + TARGET_ROUNDING_TOLERANCE,
+    }
+}
+
+/// Строит задачу в целых шагах: переменные - число шагов каждого удобрения.
+/// Минимизируется та же величина, что и в точном рецепте (масса, стоимость или балласт),
+/// при условии, что фосфора (а значит, благодаря соотношениям, и остальных элементов)
+/// в смеси не меньше, чем в точном рецепте. Уже приготовленная часть смеси входит
+/// в соотношения как постоянное слагаемое. При ограничении числа удобрений
+/// округлённый рецепт составляется из тех же удобрений, что и точный.
+fn build_rounding_task(query: &MixtureQuery, exact_weights: &[f64], step: f64) -> LPTask {
+    let func_vec: Vec<f64> = query
+        .fertilizers
+        .iter()
+        .map(|fertilizer| query.objective.coefficient(fertilizer) * step)
+        .collect();
+    let mut task = LPTask::new(&func_vec).with_backend(query.solver);
+    let exact_relation = relation(query, exact_weights);
+    let ranges = ElemRangeName::ALL.map(|range_name| {
+        (
+            range_name.element(),
+            rounding_range(query, &exact_relation, range_name),
+        )
+    });
+    for (element, range) in ranges.into_iter().chain(query.secondary_ratios.iter()) {
+        let from_constr: Vec<f64> = query
+            .fertilizers
+            .iter()
+            .map(|f| f[element] - range.from * f[ElemName::Phosphorus])
+            .collect();
+        let to_constr: Vec<f64> = query
+            .fertilizers
+            .iter()
+            .map(|f| f[element] - range.to * f[ElemName::Phosphorus])
+            .collect();
+        let (from_existing, to_existing) = query.existing.map_or((0.0, 0.0), |existing| {
+            let fertilizer = existing.as_fertilizer();
+            let steps = existing.mass / step;
+            (
+                (fertilizer[element] - range.from * fertilizer[ElemName::Phosphorus]) * steps,
+                (fertilizer[element] - range.to * fertilizer[ElemName::Phosphorus]) * steps,
+            )
+        });
+        task.add_constr(&from_constr, ConstraintOp::GreaterOrEqual, -from_existing);
+        task.add_constr(&to_constr, ConstraintOp::LessOrEqual, -to_existing);
+    }
+    if let Some(limit) = query.nitrogen_form_limit {
+        // Формы азота уже приготовленной части неизвестны, в ограничении она не участвует
+        let share_constr: Vec<f64> = query
+            .fertilizers
+            .iter()
+            .map(|f| limit.coefficient(f))
+            .collect();
+        task.add_constr(&share_constr, ConstraintOp::LessOrEqual, 0.0);
+    }
+    let p_constr: Vec<f64> = query
+        .fertilizers
+        .iter()
+        .map(|f| f[ElemName::Phosphorus])
+        .collect();
+    let exact_p_steps: f64 = query
+        .fertilizers
+        .iter()
+        .zip(exact_weights.iter())
+        .map(|(f, weight)| f[ElemName::Phosphorus] * weight / step)
+        .sum();
+    task.add_constr(
+        &p_constr,
+        ConstraintOp::GreaterOrEqual,
+        exact_p_steps * (1.0 - PHOSPHORUS_TOLERANCE),
+    );
+    for (fert_idx, fertilizer) in query.fertilizers.iter().enumerate() {
+        let min_steps = fertilizer.minimum.map_or(0.0, |minimum| {
+            let min_weight = minimum.rate(query.mass) * query.mass;
+            (min_weight / step - INTEGRALITY_TOLERANCE).ceil()
+        });
+        let max_steps = fertilizer.limit.map_or(f64::INFINITY, |limit| {
+            (limit / step + INTEGRALITY_TOLERANCE).floor()
+        });
+        // Удобрения, не выбранные при ограничении их числа, не входят и в округлённый рецепт
+        let max_steps = if query.max_ingredients.is_some() && exact_weights[fert_idx] == 0.0 {
+            0.0
+        } else {
+            max_steps
+        };
+        task.set_bounds(fert_idx, min_steps, max_steps);
+    }
+    task
+}
+
+/// Находит переменную, значение которой дальше всего от целого
+fn most_fractional(params: &[f64]) -> Option<usize> {
+    params
+        .iter()
+        .map(|y| (y - y.round()).abs())
+        .enumerate()
+        .filter(|(_, distance)| *distance > INTEGRALITY_TOLERANCE)
+        .max_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+        .map(|(idx, _)| idx)
+}
+
+/// Округляет точный рецепт `exact_weights` (кг) до весов, кратных `step` (кг).
+/// Если за `consts::MAX_BRANCH_NODES` шагов не удалось доказать оптимальность,
+/// возвращается лучший из найденных рецептов.
+pub(crate) fn round_recipe(
+    query: &MixtureQuery,
+    exact_weights: &[f64],
+    step: f64,
+) -> Result<RoundedRecipe, SimplexError> {
+    round_recipe_within(query, exact_weights, step, MAX_BRANCH_NODES)
+}
+
+/// То же, что `round_recipe`, но решается не больше `max_nodes` задач
+fn round_recipe_within(
+    query: &MixtureQuery,
+    exact_weights: &[f64],
+    step: f64,
+    max_nodes: usize,
+) -> Result<RoundedRecipe, SimplexError> {
+    let mut best: Option<(f64, Vec<f64>)> = None;
+    let mut nodes = vec![build_rounding_task(query, exact_weights, step)];
+    let mut nodes_solved = 0;
+    // Бюджет проверяется до того, как задача снята со стека: иначе последняя
+    // нерешённая задача терялась бы, и исчерпание бюджета выглядело бы как несовместность
+    while nodes_solved < max_nodes {
+        let Some(task) = nodes.pop() else {
+            break;
+        };
+        nodes_solved += 1;
+        let Ok(solution) = task.solve_min() else {
+            continue;
+        };
+        // При минимизации массы целевая функция (масса в килограммах) кратна шагу,
+        // поэтому оценку ветки можно округлить вверх до целого числа шагов
+        let bound = if query.objective == MixtureObjective::Mass {
+            (solution.function_value / step - INTEGRALITY_TOLERANCE).ceil() * step
+        } else {
+            solution.function_value
+        };
+        if let Some((best_value, _)) = best {
+            if bound >= best_value - OBJECTIVE_TOLERANCE {
+                continue;
+            }
+        }
+        match most_fractional(&solution.params) {
+            None => {
+                let steps: Vec<f64> = solution.params.iter().map(|y| y.round()).collect();
+                let value = query
+                    .fertilizers
+                    .iter()
+                    .zip(steps.iter())
+                    .map(|(fertilizer, y)| query.objective.coefficient(fertilizer) * step * y)
+                    .sum();
+                best = Some((value, steps));
+            }
+            Some(fert_idx) => {
+                // Ветвление сужает границы переменной, не добавляя строк в задачу
+                let value = solution.params[fert_idx];
+                let (lower, upper) = task.bounds[fert_idx];
+                let mut down = task.clone();
+                down.set_bounds(fert_idx, lower, value.floor());
+                let mut up = task;
+                up.set_bounds(fert_idx, value.ceil(), upper);
+                // Первой исследуется ветка, ближайшая к дробному значению
+                if value - value.floor() < 0.5 {
+                    nodes.extend([up, down]);
+                } else {
+                    nodes.extend([down, up]);
+                }
+            }
+        }
+    }
+
+    let Some((_, steps)) = best else {
+        return Err(if nodes.is_empty() {
+            SimplexError::NotFeasible
+        } else {
+            SimplexError::TooManySteps
+        });
+    };
+    let weights: Vec<f64> = steps.iter().map(|y| y * step).collect();
+    let rounded_relation = relation(query, &weights);
+    let exact_relation = relation(query, exact_weights);
+    let deviation = [0, 1, 2].map(|i| {
+        let (range_name, value) = rounded_relation[i];
+        (range_name, value - exact_relation[i].1)
+    });
+    Ok(RoundedRecipe {
+        total_weight: weights.iter().sum::<f64>()
+            + query.existing.map_or(0.0, |existing| existing.mass),
+        weights,
+        relation: rounded_relation,
+        deviation,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{relation, round_recipe, round_recipe_within};
+    use crate::calculator::consts::{PERMANENT_FERTILIZERS, TARGET_ROUNDING_TOLERANCE};
+    use crate::calculator::modified_simplex::SimplexError;
+    use crate::calculator::query::{ExistingMixture, MixtureQuery};
+    use crate::calculator::{Amounts, ElemName, FertilizerMinimum, TargetRatios};
+
+    fn query(fert_ids: &[usize]) -> MixtureQuery {
+        MixtureQuery::test_default(
+            fert_ids
+                .iter()
+                .map(|id| PERMANENT_FERTILIZERS[*id].clone())
+                .collect(),
+        )
+    }
+
+    fn exact_weights(query: &MixtureQuery) -> Vec<f64> {
+        let solution = query.find_solution().expect("Must be solvable");
+        solution
+            .components
+            .iter()
+            .map(|(_, weight)| *weight)
+            .collect()
+    }
+
+    #[test]
+    fn test_rounded_recipe() {
+        let query = query(&[0, 1, 5, 6, 9, 10, 12, 15]);
+        let exact = exact_weights(&query);
+        for step in [0.001, 0.01, 0.1, 1.0] {
+            let rounded = round_recipe(&query, &exact, step).expect("Must be roundable");
+            for weight in rounded.weights.iter() {
+                let steps = weight / step;
+                assert!(
+                    (steps - steps.round()).abs() < 1e-9,
+                    "{} / {}",
+                    weight,
+                    step
+                );
+            }
+            for (range_name, value) in rounded.relation {
+                let range = query.ratio(range_name);
+                assert!(range.from - 1e-9 <= value && value <= range.to + 1e-9);
+            }
+            // Питательных веществ не меньше, а значит и масса не меньше, чем в точном рецепте
+            assert!(rounded.total_weight >= query.mass - 1e-6);
+        }
+        // Шаг в 1 г почти не должен менять рецепт
+        let rounded = round_recipe(&query, &exact, 0.001).unwrap();
+        assert!(rounded.total_weight - query.mass < 0.01);
+        for (_, deviation) in rounded.deviation {
+            assert!(deviation.abs() < 0.01);
+        }
+    }
+
+    /// Полный перебор весов, кратных шагу, не больше `max_weight` каждого: наименьшая масса
+    /// рецепта с соотношениями в заданных пределах и не меньшим, чем в точном рецепте,
+    /// количеством фосфора
+    fn brute_force_mass(query: &MixtureQuery, exact: &[f64], step: f64, max_weight: f64) -> f64 {
+        let phosphorus = |weights: &[f64]| -> f64 {
+            query
+                .fertilizers
+                .iter()
+                .zip(weights.iter())
+                .map(|(fertilizer, weight)| fertilizer[ElemName::Phosphorus] * weight)
+                .sum()
+        };
+        let exact_phosphorus = phosphorus(exact);
+        let max_steps = (max_weight / step).round() as usize;
+        let num_ferts = query.fertilizers.len();
+        let mut best = f64::INFINITY;
+        let mut steps = vec![0_usize; num_ferts];
+        loop {
+            let weights: Vec<f64> = steps.iter().map(|y| *y as f64 * step).collect();
+            let suitable = phosphorus(&weights) >= exact_phosphorus * (1.0 - 1e-9)
+                && relation(query, &weights).iter().all(|(range_name, value)| {
+                    let range = query.ratio(*range_name);
+                    range.from - 1e-9 <= *value && *value <= range.to + 1e-9
+                });
+            if suitable {
+                best = best.min(weights.iter().sum());
+            }
+            let Some(idx) = steps.iter().position(|y| *y < max_steps) else {
+                return best;
+            };
+            steps[idx] += 1;
+            steps[..idx].fill(0);
+        }
+    }
+
+    #[test]
+    fn test_rounding_is_optimal() {
+        let query = query(&[1, 9, 12, 15]);
+        let exact = exact_weights(&query);
+        for step in [0.25, 0.5, 1.0] {
+            let rounded = round_recipe(&query, &exact, step).expect("Must be roundable");
+            let expected = brute_force_mass(&query, &exact, step, 6.0);
+            assert!(
+                (rounded.total_weight - expected).abs() < 1e-9,
+                "{}: {} != {}",
+                step,
+                rounded.total_weight,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_rounding_keeps_max_ingredients() {
+        let mut query = query(&(0..PERMANENT_FERTILIZERS.len()).collect::<Vec<_>>());
+        query.max_ingredients = Some(4);
+        let exact = exact_weights(&query);
+        for step in [0.01, 0.1, 1.0] {
+            let rounded = round_recipe(&query, &exact, step).expect("Must be roundable");
+            for (weight, exact_weight) in rounded.weights.iter().zip(exact.iter()) {
+                assert!(*exact_weight > 0.0 || *weight == 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rounding_keeps_minimum() {
+        let mut query = query(&[0, 1, 5, 6, 9, 10, 12, 15]);
+        query.fertilizers[1].minimum = Some(FertilizerMinimum::Mass(1.25));
+        let exact = exact_weights(&query);
+        let rounded = round_recipe(&query, &exact, 0.1).expect("Must be roundable");
+        assert!(rounded.weights[1] >= 1.3 - 1e-9);
+    }
+
+    #[test]
+    fn test_rounding_with_existing_mixture() {
+        let mut query = query(&[0, 1, 5, 6, 9, 10, 12, 15]);
+        query.existing = Some(ExistingMixture {
+            mass: 3.0,
+            content: Amounts::with(&[
+                (ElemName::Nitrogen, 18.0),
+                (ElemName::Phosphorus, 4.0),
+                (ElemName::Potassium, 9.0),
+                (ElemName::Magnesium, 0.5),
+            ]),
+        });
+        let exact = exact_weights(&query);
+        let rounded = round_recipe(&query, &exact, 0.1).expect("Must be roundable");
+        for (range_name, value) in rounded.relation {
+            let range = query.ratio(range_name);
+            assert!(range.from - 1e-9 <= value && value <= range.to + 1e-9);
+        }
+        assert!(rounded.total_weight >= query.mass - 1e-6);
+    }
+
+    #[test]
+    fn test_rounding_with_target() {
+        let mut query = query(&[0, 1, 5, 6, 9, 10, 12, 15]);
+        query.target = Some(TargetRatios { n_to_p: 1.8, k_to_p: 1.9, mg_to_p: 0.4 });
+        let exact = exact_weights(&query);
+        let rounded = round_recipe(&query, &exact, 0.1).expect("Must be roundable");
+        for (range_name, value) in rounded.relation {
+            let target = query.target.unwrap()[range_name];
+            assert!((value - target).abs() <= TARGET_ROUNDING_TOLERANCE + 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_not_enough_stock() {
+        let mut query = query(&[0, 1, 5, 6, 9, 10, 12, 15]);
+        let exact = exact_weights(&query);
+        // Остатков удобрений меньше, чем одна порция по 1 кг
+        for fertilizer in query.fertilizers.iter_mut() {
+            fertilizer.limit = Some(0.9);
+        }
+        assert_eq!(
+            round_recipe(&query, &exact, 1.0),
+            Err(SimplexError::NotFeasible)
+        );
+    }
+
+    #[test]
+    fn test_node_budget() {
+        let query = query(&[0, 1, 5, 6, 9, 10, 12, 15]);
+        let exact = exact_weights(&query);
+        // Рецепт не округляется без ветвления, а исчерпанный бюджет - не несовместность
+        for max_nodes in [0, 1] {
+            assert_eq!(
+                round_recipe_within(&query, &exact, 0.1, max_nodes),
+                Err(SimplexError::TooManySteps)
+            );
+        }
+    }
+}
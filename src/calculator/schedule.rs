@@ -0,0 +1,441 @@
+//! План подкормок на сезон: сколько смеси вносить в каждую из еженедельных подкормок,
+//! сколько всего удобрений понадобится купить, и напоминания для календаря.
+
+use std::borrow::Cow;
+
+use super::query::MixtureQuery;
+use super::{consts, ElemRange};
+
+/// Календарная дата (по григорианскому календарю), без часового пояса
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct CivilDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl CivilDate {
+    /// Разбирает дату вида `2024-05-20` (так её возвращает `<input type="date">`)
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.splitn(3, '-');
+        let year = parts.next()?.parse::<i32>().ok()?;
+        let month = parts.next()?.parse::<u32>().ok()?;
+        let day = parts.next()?.parse::<u32>().ok()?;
+        let date = Self { year, month, day };
+        // Несуществующие даты вроде 31 апреля после перевода в дни и обратно меняются
+        (1..=12)
+            .contains(&month)
+            .then(|| Self::from_days(date.to_days()))
+            .filter(|normalized| *normalized == date)
+    }
+
+    /// Номер дня, считая от 1 января 1970 г. (алгоритм Говарда Хиннанта)
+    fn to_days(self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = i64::from(self.month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    fn from_days(days: i64) -> Self {
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self { year: year as i32, month: month as u32, day: day as u32 }
+    }
+
+    pub fn add_days(self, days: i64) -> Self {
+        Self::from_days(self.to_days() + days)
+    }
+
+    /// Дата в формате iCalendar: `20240520`
+    fn ical(self) -> String {
+        format!("{:04}{:02}{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl std::fmt::Display for CivilDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}.{:02}.{}", self.day, self.month, self.year)
+    }
+}
+
+/// Момент времени в UTC с точностью до секунды
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct UtcDateTime {
+    pub date: CivilDate,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl UtcDateTime {
+    /// Момент через `seconds` секунд после начала 1 января 1970 г.
+    pub fn from_unix_seconds(seconds: i64) -> Self {
+        let second_of_day = seconds.rem_euclid(86400) as u32;
+        Self {
+            date: CivilDate::from_days(seconds.div_euclid(86400)),
+            hour: second_of_day / 3600,
+            minute: second_of_day % 3600 / 60,
+            second: second_of_day % 60,
+        }
+    }
+
+    /// Момент в формате iCalendar: `20240520T081500Z`
+    fn ical(self) -> String {
+        format!(
+            "{}T{:02}{:02}{:02}Z",
+            self.date.ical(),
+            self.hour,
+            self.minute,
+            self.second
+        )
+    }
+}
+
+/// Условия, для которых составляется план
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SeasonPlanParams {
+    /// Дата посадки; без неё подкормки нумеруются только по неделям
+    pub planting: Option<CivilDate>,
+    /// Длина гряд (погонных метров)
+    pub bed_length: f64,
+    /// Число еженедельных подкормок за сезон
+    pub feedings: usize,
+    /// Доза внесения (г на погонный метр): `from` - пока растения приживаются
+    /// (первые `consts::ESTABLISHMENT_FEEDINGS` подкормок), `to` - в период роста
+    /// и плодоношения. Для дозы по Митлайдеру обе границы совпадают.
+    pub doze: ElemRange,
+}
+
+/// Одна подкормка
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Feeding {
+    /// Номер недели после посадки, начиная с 1
+    pub week: usize,
+    pub date: Option<CivilDate>,
+    /// Идёт ли ещё приживание растений
+    pub establishment: bool,
+    /// Доза смеси, г на погонный метр
+    pub doze: f64,
+    /// Смесь на все гряды, кг
+    pub mass: f64,
+}
+
+/// План подкормок на сезон и список покупок
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SeasonPlan {
+    pub feedings: Vec<Feeding>,
+    /// Масса смеси на весь сезон, кг
+    pub total_mass: f64,
+    /// Сколько каждого удобрения нужно на сезон (кг), в порядке удобрений рецепта.
+    /// Уже смешанного купить нельзя, поэтому в список оно не входит.
+    pub shopping_list: Vec<(Cow<'static, str>, f64)>,
+}
+
+impl SeasonPlan {
+    /// Составляет план по рецепту `exact_weights` (веса удобрений запроса, кг):
+    /// на сезон удобрения нужны в тех же пропорциях, что и в рецепте
+    /// (без уже смешанного - оно есть только на одну партию)
+    pub fn new(query: &MixtureQuery, exact_weights: &[f64], params: &SeasonPlanParams) -> Self {
+        let feedings: Vec<Feeding> = (1..=params.feedings)
+            .map(|week| {
+                let establishment = week <= consts::ESTABLISHMENT_FEEDINGS;
+                let doze = if establishment {
+                    params.doze.from
+                } else {
+                    params.doze.to
+                };
+                Feeding {
+                    week,
+                    date: params
+                        .planting
+                        .map(|planting| planting.add_days(7 * week as i64)),
+                    establishment,
+                    doze,
+                    mass: doze * params.bed_length / 1000.0,
+                }
+            })
+            .collect();
+        let total_mass: f64 = feedings.iter().map(|feeding| feeding.mass).sum();
+        let recipe_mass: f64 = exact_weights.iter().sum();
+        // Пустой рецепт не задаёт пропорций, и покупать по нему нечего
+        if recipe_mass <= 0.0 {
+            return Self { feedings, total_mass, shopping_list: Vec::new() };
+        }
+        let shopping_list = query
+            .fertilizers
+            .iter()
+            .zip(exact_weights.iter())
+            .filter(|(_, weight)| **weight > 0.0)
+            .map(|(fertilizer, weight)| {
+                (fertilizer.name.clone(), total_mass * weight / recipe_mass)
+            })
+            .collect();
+        Self { feedings, total_mass, shopping_list }
+    }
+
+    /// Календарь iCalendar (RFC 5545) с напоминанием о каждой подкормке в день её проведения.
+    /// `stamp` - момент выгрузки календаря: по стандарту это время создания событий (`DTSTAMP`).
+    /// Без даты посадки календарь составить нельзя.
+    pub fn to_icalendar(&self, title: &str, stamp: UtcDateTime) -> Option<String> {
+        let mut calendar = String::new();
+        let mut line = |text: &str| push_folded_line(&mut calendar, text);
+        line("BEGIN:VCALENDAR");
+        line("VERSION:2.0");
+        line("PRODID:-//Mittleider calculator//Feeding schedule//RU");
+        line("CALSCALE:GREGORIAN");
+        // Календари разных культур и рецептов не должны затирать события друг друга
+        let plan_hash = self.stable_hash(title);
+        for feeding in &self.feedings {
+            let date = feeding.date?;
+            let stage = if feeding.establishment {
+                "приживание"
+            } else {
+                "рост и плодоношение"
+            };
+            line("BEGIN:VEVENT");
+            line(&format!(
+                "UID:feeding-{:016x}-{}-{}@mittleider-calculator",
+                plan_hash,
+                feeding.week,
+                date.ical()
+            ));
+            line(&format!("DTSTAMP:{}", stamp.ical()));
+            line(&format!("DTSTART;VALUE=DATE:{}", date.ical()));
+            line(&format!("DTEND;VALUE=DATE:{}", date.add_days(1).ical()));
+            line(&format!(
+                "SUMMARY:{}",
+                escape_text(&format!(
+                    "Подкормка №{}: {:.2} кг смеси",
+                    feeding.week, feeding.mass
+                ))
+            ));
+            line(&format!(
+                "DESCRIPTION:{}",
+                escape_text(&format!(
+                    "{}\nНеделя {} ({}): {:.0} г на погонный метр гряды, всего {:.2} кг",
+                    title, feeding.week, stage, feeding.doze, feeding.mass
+                ))
+            ));
+            line("BEGIN:VALARM");
+            line("ACTION:DISPLAY");
+            line(&format!("DESCRIPTION:{}", escape_text(title)));
+            line(&format!(
+                "TRIGGER;RELATED=START:PT{}H",
+                consts::FEEDING_REMINDER_HOUR
+            ));
+            line("END:VALARM");
+            line("END:VEVENT");
+        }
+        line("END:VCALENDAR");
+        Some(calendar)
+    }
+
+    /// Хэш FNV-1a названия и рецепта плана: в отличие от `DefaultHasher`,
+    /// он не меняется между запусками и версиями Rust
+    fn stable_hash(&self, title: &str) -> u64 {
+        let recipe = self
+            .shopping_list
+            .iter()
+            .map(|(name, mass)| format!("{}:{:.3}", name, mass))
+            .collect::<Vec<_>>()
+            .join(";");
+        format!("{}|{}", title, recipe)
+            .bytes()
+            .fold(0xcbf29ce484222325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+            })
+    }
+}
+
+/// Экранирует запятые, точки с запятой, обратные косые черты и переводы строк
+/// в текстовом значении iCalendar
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Добавляет строку календаря, перенося её так, чтобы каждая часть была не длиннее 75 байт
+/// (кириллица занимает по 2 байта, а перенос не должен разрывать символ)
+fn push_folded_line(output: &mut String, text: &str) {
+    const MAX_LINE_BYTES: usize = 75;
+    let mut line_bytes = 0;
+    for c in text.chars() {
+        if line_bytes + c.len_utf8() > MAX_LINE_BYTES {
+            output.push_str("\r\n ");
+            // Пробел в начале строки продолжения тоже считается
+            line_bytes = 1;
+        }
+        output.push(c);
+        line_bytes += c.len_utf8();
+    }
+    output.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod test {
+    use super::{push_folded_line, CivilDate, SeasonPlan, SeasonPlanParams, UtcDateTime};
+    use crate::calculator::consts::{ESTABLISHMENT_FEEDINGS, PERMANENT_FERTILIZERS};
+    use crate::calculator::query::{ExistingMixture, MixtureQuery};
+    use crate::calculator::{Amounts, ElemName, ElemRange};
+
+    #[test]
+    fn test_civil_date() {
+        let date = CivilDate::parse("2024-02-27").expect("Date must be parsable");
+        assert_eq!(date.add_days(3), CivilDate { year: 2024, month: 3, day: 1 });
+        assert_eq!(
+            date.add_days(-58),
+            CivilDate { year: 2023, month: 12, day: 31 }
+        );
+        assert_eq!(CivilDate::parse("1970-01-01").unwrap().to_days(), 0);
+        assert_eq!(date.to_string(), "27.02.2024");
+        assert_eq!(CivilDate::parse("2023-02-29"), None);
+        assert_eq!(CivilDate::parse("2024-13-01"), None);
+        assert_eq!(CivilDate::parse("вчера"), None);
+    }
+
+    #[test]
+    fn test_season_plan() {
+        let query = MixtureQuery {
+            existing: Some(ExistingMixture {
+                mass: 2.0,
+                content: Amounts::with(&[
+                    (ElemName::Nitrogen, 10.0),
+                    (ElemName::Phosphorus, 5.0),
+                    (ElemName::Potassium, 10.0),
+                    (ElemName::Magnesium, 1.0),
+                ]),
+            }),
+            ..MixtureQuery::test_default(PERMANENT_FERTILIZERS[..3].to_vec())
+        };
+        let params = SeasonPlanParams {
+            planting: CivilDate::parse("2024-05-20"),
+            bed_length: 20.0,
+            feedings: 6,
+            doze: ElemRange { from: 25.0, to: 40.0 },
+        };
+        let plan = SeasonPlan::new(&query, &[4.0, 0.0, 4.0], &params);
+        assert_eq!(plan.feedings.len(), 6);
+        assert_eq!(
+            plan.feedings[0].date,
+            Some(CivilDate { year: 2024, month: 5, day: 27 })
+        );
+        assert!((plan.feedings[0].mass - 0.5).abs() < 1e-9);
+        assert!((plan.feedings[ESTABLISHMENT_FEEDINGS].mass - 0.8).abs() < 1e-9);
+        let expected_total =
+            0.5 * ESTABLISHMENT_FEEDINGS as f64 + 0.8 * (6 - ESTABLISHMENT_FEEDINGS) as f64;
+        assert!((plan.total_mass - expected_total).abs() < 1e-9);
+        // Неиспользованные удобрения и уже смешанное покупать не нужно,
+        // а пропорции - как в рецепте
+        assert_eq!(plan.shopping_list.len(), 2);
+        assert_eq!(plan.shopping_list[0].0, PERMANENT_FERTILIZERS[0].name);
+        let bought: f64 = plan.shopping_list.iter().map(|(_, mass)| mass).sum();
+        assert!((bought - plan.total_mass).abs() < 1e-9);
+        assert!((plan.shopping_list[0].1 - plan.total_mass * 0.5).abs() < 1e-9);
+
+        // 20 апреля 2024 г., 08:15:30 UTC
+        let stamp = UtcDateTime::from_unix_seconds(1713600930);
+        let calendar = plan
+            .to_icalendar("Смесь №2", stamp)
+            .expect("Planting date is known");
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 6);
+        assert!(calendar.contains("DTSTART;VALUE=DATE:20240527\r\n"));
+        // Все события созданы в момент выгрузки, а не в день подкормки
+        assert_eq!(calendar.matches("DTSTAMP:20240420T081530Z\r\n").count(), 6);
+        assert!(calendar.lines().all(|line| line.len() <= 75));
+        // У календаря другой культуры свои идентификаторы событий
+        let other_calendar = plan
+            .to_icalendar("Смесь №2 (томаты)", stamp)
+            .expect("Planting date is known");
+        let uids = |calendar: &str| -> Vec<String> {
+            calendar
+                .lines()
+                .filter(|line| line.starts_with("UID:"))
+                .map(str::to_owned)
+                .collect()
+        };
+        assert!(uids(&calendar)
+            .iter()
+            .all(|uid| !uids(&other_calendar).contains(uid)));
+        let undated = SeasonPlan::new(
+            &query,
+            &[4.0, 0.0, 4.0],
+            &SeasonPlanParams { planting: None, ..params },
+        );
+        assert_eq!(undated.to_icalendar("Смесь №2", stamp), None);
+    }
+
+    #[test]
+    fn test_unix_seconds() {
+        let epoch = UtcDateTime::from_unix_seconds(0);
+        assert_eq!(epoch.date, CivilDate { year: 1970, month: 1, day: 1 });
+        assert_eq!((epoch.hour, epoch.minute, epoch.second), (0, 0, 0));
+        let before_epoch = UtcDateTime::from_unix_seconds(-1);
+        assert_eq!(
+            before_epoch.date,
+            CivilDate { year: 1969, month: 12, day: 31 }
+        );
+        assert_eq!(before_epoch.ical(), "19691231T235959Z");
+    }
+
+    #[test]
+    fn test_empty_recipe() {
+        // Уже смешанного не осталось, а рецепт пуст: пропорций для покупок нет
+        let query = MixtureQuery {
+            existing: Some(ExistingMixture {
+                mass: 0.0,
+                content: Amounts::with(&[
+                    (ElemName::Nitrogen, 10.0),
+                    (ElemName::Phosphorus, 5.0),
+                    (ElemName::Potassium, 10.0),
+                    (ElemName::Magnesium, 1.0),
+                ]),
+            }),
+            ..MixtureQuery::test_default(PERMANENT_FERTILIZERS[..3].to_vec())
+        };
+        let params = SeasonPlanParams {
+            planting: None,
+            bed_length: 20.0,
+            feedings: 6,
+            doze: ElemRange { from: 25.0, to: 40.0 },
+        };
+        let plan = SeasonPlan::new(&query, &[0.0, 0.0, 0.0], &params);
+        assert_eq!(plan.feedings.len(), 6);
+        assert!(plan.shopping_list.is_empty());
+    }
+
+    #[test]
+    fn test_line_folding() {
+        let mut output = String::new();
+        let text = "DESCRIPTION:".to_string() + &"ж".repeat(100);
+        push_folded_line(&mut output, &text);
+        assert!(output.split("\r\n").all(|line| line.len() <= 75));
+        assert_eq!(output.replace("\r\n ", ""), text + "\r\n");
+    }
+}
@@ -0,0 +1,250 @@
+//! Анализ чувствительности рецепта: в каких пределах могут меняться состав удобрений
+//! и границы соотношений элементов, чтобы оптимальная смесь состояла из тех же удобрений.
+//! Нужен прежде всего потому, что состав на упаковке купленного удобрения нередко
+//! на процент-другой отличается от табличного.
+
+use crate::calculator::cardinality::{solve_with_max_ingredients, support, used_fertilizers};
+use crate::calculator::modified_simplex::{LPTask, SimplexError};
+use crate::calculator::query::MixtureQuery;
+use crate::calculator::revised_simplex::RevisedSimplex;
+use crate::calculator::{ElemName, ElemRangeName};
+
+/// Параметр задачи, устойчивость рецепта к изменению которого исследуется
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SensitivityParam {
+    /// Содержание элемента (в процентах) в удобрении с указанным индексом
+    Content(usize, ElemName),
+    /// Нижняя граница соотношения элемента к фосфору
+    RatioFrom(ElemRangeName),
+    /// Верхняя граница соотношения элемента к фосфору
+    RatioTo(ElemRangeName),
+}
+
+impl SensitivityParam {
+    fn get(self, query: &MixtureQuery) -> f64 {
+        match self {
+            Self::Content(fert_idx, element) => query.fertilizers[fert_idx][element],
+            Self::RatioFrom(range_name) => query.ratio(range_name).from,
+            Self::RatioTo(range_name) => query.ratio(range_name).to,
+        }
+    }
+
+    fn set(self, query: &mut MixtureQuery, value: f64) {
+        match self {
+            Self::Content(fert_idx, element) => query.fertilizers[fert_idx][element] = value,
+            Self::RatioFrom(range_name) => query.ratio_mut(range_name).from = value,
+            Self::RatioTo(range_name) => query.ratio_mut(range_name).to = value,
+        }
+    }
+
+    /// Значения, которые параметр вообще может принимать при остальных неизменных:
+    /// сумма макроэлементов в удобрении не больше 100%, нижняя граница не выше верхней.
+    /// Вторичные элементы в сумму не входят: хлор и сера считаются не в оксидах, и вместе
+    /// с оксидами (например, в хлориде калия) дают больше 100%.
+    fn limits(self, query: &MixtureQuery) -> (f64, f64) {
+        match self {
+            Self::Content(fert_idx, element) => {
+                let fertilizer = &query.fertilizers[fert_idx];
+                let macro_sum: f64 = ElemName::MACRO.iter().map(|e| fertilizer[*e]).sum();
+                let others = if ElemName::MACRO.contains(&element) {
+                    macro_sum - fertilizer[element]
+                } else {
+                    0.0
+                };
+                (0.0, 100.0 - others)
+            }
+            Self::RatioFrom(range_name) => (0.0, query.ratio(range_name).to),
+            Self::RatioTo(range_name) => (query.ratio(range_name).from, f64::INFINITY),
+        }
+    }
+}
+
+/// Диапазон значений параметра, в котором оптимальный базис задачи, а с ним и рецепт смеси,
+/// остаются прежними
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ParamRange {
+    pub param: SensitivityParam,
+    /// Текущее значение параметра
+    pub value: f64,
+    /// Наименьшее значение, при котором рецепт остаётся прежним
+    pub from: f64,
+    /// Наибольшее значение, при котором рецепт остаётся прежним
+    /// (`f64::INFINITY`, если рецепт от увеличения параметра не зависит)
+    pub to: f64,
+}
+
+/// Удобрения, не вошедшие в смесь из не более чем `max_ingredients` удобрений.
+/// Без такого ограничения не исключается ничего.
+fn excluded_fertilizers(query: &MixtureQuery) -> Result<Vec<usize>, SimplexError> {
+    let Some(max_count) = query.max_ingredients else {
+        return Ok(Vec::new());
+    };
+    let (solution, _) = solve_with_max_ingredients(query, max_count)?;
+    let used = support(query, &solution);
+    Ok((0..query.fertilizers.len())
+        .filter(|idx| !used.contains(idx))
+        .collect())
+}
+
+/// Задача для смеси из удобрений, кроме исключённых
+fn build_task(query: &MixtureQuery, excluded: &[usize]) -> LPTask {
+    let mut task = query.build_task(&[]);
+    for fert_idx in excluded {
+        task.set_bounds(*fert_idx, 0.0, 0.0);
+    }
+    task
+}
+
+/// Для каждого элемента каждого входящего в смесь удобрения, а также для каждой границы
+/// соотношений, находит диапазон, в котором рецепт смеси остаётся прежним.
+/// Состав неиспользованных удобрений не анализируется: их всё равно не придётся покупать.
+/// В режиме идеальных соотношений границы диапазонов не используются и не анализируются.
+///
+/// Задача решается один раз: диапазоны находятся по её оптимальному базису
+/// (см. `revised_simplex::OptimalBasis::stable_range`), а не повторными решениями.
+/// Поскольку каждый параметр входит в задачу линейно, её изменение на единицу параметра
+/// получается сравнением с задачей, построенной для соседнего значения. При ограничении
+/// числа удобрений исследуется смесь из удобрений, выбранных методом ветвей и границ:
+/// диапазоны показывают, когда меняется рецепт из этих удобрений, хотя при других
+/// значениях параметра метод ветвей и границ мог бы выбрать и другой набор.
+pub(crate) fn analyze_sensitivity(query: &MixtureQuery) -> Result<Vec<ParamRange>, SimplexError> {
+    let excluded = excluded_fertilizers(query)?;
+    let task = build_task(query, &excluded);
+    let (solution, basis) = RevisedSimplex.solve_with_basis(&task)?;
+
+    let content_params = used_fertilizers(query, &solution)
+        .into_iter()
+        .flat_map(|fert_idx| {
+            // Содержание вторичного элемента важно, только если его соотношение задано
+            ElemName::MACRO
+                .into_iter()
+                .chain(query.secondary_ratios.iter().map(|(element, _)| element))
+                .map(move |element| SensitivityParam::Content(fert_idx, element))
+        });
+    let ratio_params = ElemRangeName::ALL
+        .into_iter()
+        .filter(|_| query.target.is_none())
+        .flat_map(|range_name| {
+            [
+                SensitivityParam::RatioFrom(range_name),
+                SensitivityParam::RatioTo(range_name),
+            ]
+        });
+
+    let result = content_params
+        .chain(ratio_params)
+        .map(|param| {
+            let value = param.get(query);
+            let (lower_limit, upper_limit) = param.limits(query);
+            // Соседнее значение берётся в пределах допустимых, чтобы строки задачи
+            // остались устроены так же
+            let step = if value + 1.0 <= upper_limit {
+                1.0
+            } else {
+                -1.0
+            };
+            let mut shifted = query.clone();
+            param.set(&mut shifted, value + step);
+            let (t_from, t_to) = basis.stable_range(&task, &build_task(&shifted, &excluded));
+            let (from, to) = if step > 0.0 {
+                (value + t_from, value + t_to)
+            } else {
+                (value - t_to, value - t_from)
+            };
+            ParamRange { param, value, from: from.max(lower_limit), to: to.min(upper_limit) }
+        })
+        .collect();
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{analyze_sensitivity, ParamRange};
+    use crate::calculator::cardinality::{
+        solve_excluding, solve_with_max_ingredients, support, used_fertilizers,
+    };
+    use crate::calculator::consts::PERMANENT_FERTILIZERS;
+    use crate::calculator::modified_simplex::SimplexError;
+    use crate::calculator::query::MixtureQuery;
+
+    fn query(fert_ids: &[usize]) -> MixtureQuery {
+        MixtureQuery::test_default(
+            fert_ids
+                .iter()
+                .map(|id| PERMANENT_FERTILIZERS[*id].clone())
+                .collect(),
+        )
+    }
+
+    /// Удобрения, из которых состоит оптимальная смесь без исключённых удобрений
+    fn recipe(query: &MixtureQuery, excluded: &[usize]) -> Result<Vec<usize>, SimplexError> {
+        let (solution, _) = solve_excluding(query, excluded)?;
+        Ok(used_fertilizers(query, &solution))
+    }
+
+    /// Внутри найденного диапазона рецепт прежний. У самых границ диапазона доля
+    /// уходящего из смеси удобрения может оказаться меньше грамма, поэтому они не проверяются.
+    fn assert_ranges_keep_recipe(query: &MixtureQuery, excluded: &[usize], ranges: &[ParamRange]) {
+        let base_recipe = recipe(query, excluded).unwrap();
+        for range in ranges {
+            assert!(
+                range.from <= range.value && range.value <= range.to,
+                "{:?}",
+                range
+            );
+            for bound in [range.from, range.to] {
+                let bound = if bound.is_finite() {
+                    bound
+                } else {
+                    range.value + bound.signum()
+                };
+                for fraction in [0.5, 0.9] {
+                    let mut probe = query.clone();
+                    let value = range.value + fraction * (bound - range.value);
+                    range.param.set(&mut probe, value);
+                    assert_eq!(
+                        recipe(&probe, excluded),
+                        Ok(base_recipe.clone()),
+                        "{:?}",
+                        range
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ranges_keep_recipe() {
+        let query = query(&[0, 1, 5, 6, 9, 10, 12, 15]);
+        let ranges = analyze_sensitivity(&query).unwrap();
+        let used_count = recipe(&query, &[]).unwrap().len();
+        assert_eq!(ranges.len(), 4 * used_count + 6);
+        // Диапазоны найдены не только для связывающих ограничений
+        assert!(ranges.iter().any(|range| range.from < range.value));
+        assert!(ranges.iter().any(|range| range.value < range.to));
+        assert_ranges_keep_recipe(&query, &[], &ranges);
+    }
+
+    #[test]
+    fn test_ranges_with_max_ingredients() {
+        let mut query = query(&(0..PERMANENT_FERTILIZERS.len()).collect::<Vec<_>>());
+        let unlimited_count = recipe(&query, &[]).unwrap().len();
+        let max_count = unlimited_count - 1;
+        query.max_ingredients = Some(max_count);
+        let (solution, _) = solve_with_max_ingredients(&query, max_count).unwrap();
+        let used = support(&query, &solution);
+        let excluded: Vec<usize> = (0..query.fertilizers.len())
+            .filter(|idx| !used.contains(idx))
+            .collect();
+        // Диапазоны относятся к смеси из выбранных удобрений, а не ко всему каталогу
+        let ranges = analyze_sensitivity(&query).unwrap();
+        assert_eq!(ranges.len(), 4 * used.len() + 6);
+        assert_ranges_keep_recipe(&query, &excluded, &ranges);
+    }
+
+    #[test]
+    fn test_infeasible_query() {
+        let query = query(&[4, 6, 7, 9, 10, 14, 15]);
+        assert_eq!(analyze_sensitivity(&query), Err(SimplexError::NotFeasible));
+    }
+}
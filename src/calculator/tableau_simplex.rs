@@ -0,0 +1,199 @@
+//! Независимая реализация симплекс-метода на полной (плотной) симплекс-таблице.
+//! Работает заметно медленнее модифицированного метода, зато устроена предельно просто:
+//! таблица целиком пересчитывается на каждой итерации, а переменные всегда выбираются
+//! по правилу Бленда. Нужна прежде всего для перекрёстной проверки основного решателя.
+
+use crate::calculator::lp_solver::{solve_with_plain_rows, LPSolver};
+use crate::calculator::modified_simplex::{
+    ConstraintOp, LPSolution, LPTask, SimplexError, Tolerances, MAX_STEPS,
+};
+
+/// Решатель на плотной симплекс-таблице
+pub(crate) struct TableauSimplex;
+
+/// Симплекс-таблица: строки ограничений, за которыми следует строка оценок `c - c_B * B^-1 * A`.
+/// Последний столбец - правые части (в строке оценок - значение функции со знаком минус).
+struct Tableau {
+    rows: Vec<Vec<f64>>,
+    /// Номер базисного столбца для каждой строки ограничений
+    basis: Vec<usize>,
+    num_cols: usize,
+    tolerances: Tolerances,
+}
+
+impl Tableau {
+    fn objective_row(&self) -> &[f64] {
+        self.rows.last().expect("There is always the objective row")
+    }
+
+    fn rhs(&self, row: usize) -> f64 {
+        self.rows[row][self.num_cols]
+    }
+
+    fn pivot(&mut self, pivot_row: usize, pivot_col: usize) {
+        let pivot = self.rows[pivot_row][pivot_col];
+        for value in self.rows[pivot_row].iter_mut() {
+            *value /= pivot;
+        }
+        let pivot_values = self.rows[pivot_row].clone();
+        for (i, row) in self.rows.iter_mut().enumerate() {
+            let factor = row[pivot_col];
+            if i == pivot_row || factor == 0.0 {
+                continue;
+            }
+            for (value, pivot_value) in row.iter_mut().zip(pivot_values.iter()) {
+                *value -= factor * pivot_value;
+            }
+        }
+        self.basis[pivot_row] = pivot_col;
+    }
+
+    /// Заполняет строку оценок для цен `costs`
+    fn set_costs(&mut self, costs: &[f64]) {
+        let num_rows = self.basis.len();
+        let mut objective: Vec<f64> = costs.iter().copied().chain([0.0]).collect();
+        for i in 0..num_rows {
+            let basis_cost = costs[self.basis[i]];
+            for (value, row_value) in objective.iter_mut().zip(self.rows[i].iter()) {
+                *value -= basis_cost * row_value;
+            }
+        }
+        self.rows[num_rows] = objective;
+    }
+
+    /// Итерации по правилу Бленда. Вводить в базис можно только столбцы с номерами,
+    /// меньшими `allowed_cols`.
+    fn run(&mut self, allowed_cols: usize) -> Result<(), SimplexError> {
+        for _ in 0..MAX_STEPS {
+            let Some(entering) = (0..allowed_cols)
+                .find(|col| self.objective_row()[*col] < -self.tolerances.optimality)
+            else {
+                return Ok(());
+            };
+            let leaving = (0..self.basis.len())
+                .filter(|i| self.rows[*i][entering] > self.tolerances.pivot)
+                .map(|i| (i, self.rhs(i).max(0.0) / self.rows[i][entering]))
+                .min_by(|(i1, ratio1), (i2, ratio2)| {
+                    ratio1
+                        .total_cmp(ratio2)
+                        .then(self.basis[*i1].cmp(&self.basis[*i2]))
+                })
+                .map(|(i, _)| i)
+                .ok_or(SimplexError::Unbounded)?;
+            self.pivot(leaving, entering);
+        }
+        Err(SimplexError::TooManySteps)
+    }
+}
+
+impl LPSolver for TableauSimplex {
+    fn solve_min(&self, task: &LPTask) -> Result<LPSolution, SimplexError> {
+        solve_with_plain_rows(task, solve_plain)
+    }
+}
+
+/// Решает задачу без двусторонних строк и границ переменных
+fn solve_plain(task: &LPTask) -> Result<LPSolution, SimplexError> {
+    let num_rows = task.constr.len();
+    let num_vars = task.func_vec.len();
+    // Каждой строке - своя начальная базисная переменная: дополнительная, если её
+    // коэффициент и правая часть дают допустимое значение, иначе искусственная
+    let slack_coefs: Vec<Option<f64>> = task
+        .constr
+        .iter()
+        .map(|row| match row.op {
+            ConstraintOp::Less | ConstraintOp::LessOrEqual => Some(1.0),
+            ConstraintOp::Greater | ConstraintOp::GreaterOrEqual => Some(-1.0),
+            ConstraintOp::Equal => None,
+            ConstraintOp::Range => unreachable!("Двусторонние строки заменяются парой неравенств"),
+        })
+        .collect();
+    let num_slacks = slack_coefs.iter().flatten().count();
+    let first_artificial = num_vars + num_slacks;
+    let num_cols = first_artificial + num_rows;
+
+    // Столбец и коэффициент начальной базисной переменной каждой строки
+    let mut initial = Vec::with_capacity(num_rows);
+    let mut rows = Vec::with_capacity(num_rows + 1);
+    let mut slack_col = num_vars;
+    for (i, (row, slack_coef)) in task.constr.iter().zip(slack_coefs.iter()).enumerate() {
+        let mut values = vec![0.0; num_cols + 1];
+        for (j, value) in row.left.iter().enumerate() {
+            values[j] = *value;
+        }
+        let artificial_col = first_artificial + i;
+        values[artificial_col] = 1.0;
+        values[num_cols] = row.right;
+        let mut basic = (artificial_col, 1.0);
+        if let Some(coef) = slack_coef {
+            values[slack_col] = *coef;
+            if *coef > 0.0 || row.right == 0.0 {
+                basic = (slack_col, *coef);
+            }
+            slack_col += 1;
+        }
+        if basic.1 < 0.0 {
+            // Строка с нулевой правой частью: базисной делаем дополнительную переменную
+            for value in values.iter_mut() {
+                *value = -*value;
+            }
+        }
+        initial.push(basic);
+        rows.push(values);
+    }
+    rows.push(vec![0.0; num_cols + 1]);
+    let mut tableau = Tableau {
+        rows,
+        basis: initial.iter().map(|(col, _)| *col).collect(),
+        num_cols,
+        tolerances: task.tolerances,
+    };
+
+    let phase1_costs: Vec<f64> = (0..num_cols)
+        .map(|col| if col >= first_artificial { 1.0 } else { 0.0 })
+        .collect();
+    tableau.set_costs(&phase1_costs);
+    tableau.run(num_cols)?;
+    if -tableau.rhs(num_rows) > task.tolerances.feasibility {
+        return Err(SimplexError::NotFeasible);
+    }
+    for i in 0..num_rows {
+        if tableau.basis[i] < first_artificial {
+            continue;
+        }
+        let replacement = (0..first_artificial)
+            .filter(|col| !tableau.basis.contains(col))
+            .find(|col| tableau.rows[i][*col].abs() > task.tolerances.pivot);
+        if let Some(col) = replacement {
+            tableau.pivot(i, col);
+        }
+    }
+
+    let costs: Vec<f64> = (0..num_cols)
+        .map(|col| task.func_vec.get(col).copied().unwrap_or(0.0))
+        .collect();
+    tableau.set_costs(&costs);
+    tableau.run(first_artificial)?;
+
+    let mut params = vec![0.0; num_vars];
+    for (i, col) in tableau.basis.iter().enumerate() {
+        if *col < num_vars {
+            params[*col] = tableau.rhs(i);
+        }
+    }
+    let objective = tableau.objective_row();
+    // Оценка начальной базисной переменной строки i равна -y_i * (её коэффициент),
+    // так как цена этой переменной нулевая, а в исходной матрице у неё единственный
+    // ненулевой элемент - в строке i.
+    let duals = initial
+        .iter()
+        .zip(task.constr.iter())
+        .map(|((col, coef), row)| -objective[*col] / coef * row.scale)
+        .collect();
+    Ok(LPSolution {
+        function_value: -objective[num_cols],
+        params,
+        duals,
+        reduced_costs: objective[..num_vars].to_vec(),
+    })
+}
@@ -0,0 +1,82 @@
+//! Единицы, в которых указывается содержание элементов. Все расчёты, ссылки и история
+//! добавленных удобрений используют оксидную форму из справочника (P2O5, K2O, MgO...),
+//! как на отечественных упаковках. Элементарная форма (P, K, Mg...) нужна лишь для ввода
+//! составов с иностранных этикеток и лабораторных анализов и для вывода результатов.
+
+use super::ElemName;
+
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub(crate) enum NutrientUnits {
+    /// В формах из справочника элементов (P2O5, K2O, MgO) - канонические единицы
+    #[default]
+    Oxide,
+    /// В пересчёте на сам элемент (P, K, Mg)
+    Elemental,
+}
+
+impl NutrientUnits {
+    /// Сколько единиц этого вида приходится на одну каноническую единицу
+    fn factor(self, element: ElemName) -> f64 {
+        match self {
+            NutrientUnits::Oxide => 1.0,
+            NutrientUnits::Elemental => element.info().elemental_share,
+        }
+    }
+
+    /// Переводит содержание (или количество) элемента из канонических единиц в эти
+    pub fn convert(self, element: ElemName, value: f64) -> f64 {
+        value * self.factor(element)
+    }
+
+    /// Переводит содержание (или количество) элемента из этих единиц в канонические
+    pub fn canonical(self, element: ElemName, value: f64) -> f64 {
+        value / self.factor(element)
+    }
+
+    /// Переводит соотношение элемента к фосфору из канонических единиц в эти
+    pub fn convert_ratio(self, element: ElemName, ratio: f64) -> f64 {
+        ratio * self.factor(element) / self.factor(ElemName::Phosphorus)
+    }
+
+    /// Обозначение элемента в этих единицах: `P2O5` или `P`
+    pub fn formula(self, element: ElemName) -> &'static str {
+        match self {
+            NutrientUnits::Oxide => element.info().oxide,
+            NutrientUnits::Elemental => element.symbol(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::NutrientUnits;
+    use crate::calculator::ElemName;
+
+    #[test]
+    fn test_conversion() {
+        let units = NutrientUnits::Elemental;
+        assert!((units.convert(ElemName::Phosphorus, 46.0) - 20.07).abs() < 0.01);
+        assert!((units.canonical(ElemName::Potassium, 49.8) - 60.0).abs() < 0.01);
+        assert_eq!(units.convert(ElemName::Nitrogen, 34.4), 34.4);
+        for element in ElemName::ALL {
+            let value = 17.3;
+            let canonical = units.canonical(element, value);
+            assert!((units.convert(element, canonical) - value).abs() < 1e-12);
+            assert_eq!(NutrientUnits::Oxide.canonical(element, value), value);
+        }
+    }
+
+    /// Пересчёт соотношения даёт то же, что и соотношение пересчитанных количеств
+    #[test]
+    fn test_ratio_conversion() {
+        let units = NutrientUnits::Elemental;
+        let (potassium, phosphorus) = (18.0, 10.0);
+        let elemental_ratio = units.convert(ElemName::Potassium, potassium)
+            / units.convert(ElemName::Phosphorus, phosphorus);
+        let converted = units.convert_ratio(ElemName::Potassium, potassium / phosphorus);
+        assert!((converted - elemental_ratio).abs() < 1e-12);
+        assert_eq!(units.convert_ratio(ElemName::Phosphorus, 1.0), 1.0);
+        assert_eq!(units.formula(ElemName::Magnesium), "Mg");
+        assert_eq!(NutrientUnits::Oxide.formula(ElemName::Magnesium), "MgO");
+    }
+}
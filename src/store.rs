@@ -1,22 +1,68 @@
 use std::rc::Rc;
 
 use crate::calculator::consts::PERMANENT_FERTILIZERS;
-use crate::calculator::query::MixtureQuery;
-use crate::calculator::{ElemRange, ElemRangeName, ElemRatios, Fertilizer};
+use crate::calculator::crops::Crop;
+use crate::calculator::lp_solver::SolverBackend;
+use crate::calculator::nitrogen::NitrogenFormLimit;
+use crate::calculator::profiles::MixtureProfile;
+use crate::calculator::query::{ExistingMixture, MixtureObjective, MixtureQuery};
+use crate::calculator::{
+    ElemName, ElemRange, ElemRangeName, ElemRatios, Fertilizer, FertilizerMinimum, TargetRatios,
+};
 use yew::Reducible;
 
 /// Хранит идентификаторы "перманентных" удобрений, выбранных пользователем, а также
-/// ограничения, наложенные на любые перманентные удобрения (не важно, выбранные или нет).
-/// Ограничения для не выбранных удобрений позднее будут проигнорированы в подсчётах.
+/// ограничения, минимумы и цены, заданные для любых перманентных удобрений (не важно, выбранных
+/// или нет). Всё это для не выбранных удобрений позднее будет проигнорировано в подсчётах.
 #[derive(Clone, Debug, PartialEq, Default)]
 pub(crate) struct PermanentFertilizersState {
     pub selected: Rc<Vec<usize>>,
     pub limited: Rc<Vec<(usize, f64)>>,
+    pub minimums: Rc<Vec<(usize, FertilizerMinimum)>>,
+    pub prices: Rc<Vec<(usize, f64)>>,
+}
+
+/// Задаёт (или удаляет, если значение не указано) значение, привязанное к удобрению
+fn set_fertilizer_value<T: Clone>(
+    values: &mut Rc<Vec<(usize, T)>>,
+    fert_id: usize,
+    value: Option<T>,
+) {
+    let new_values = Rc::make_mut(values);
+    match (value, new_values.iter().position(|v| v.0 == fert_id)) {
+        (None, None) => {}
+        (None, Some(old_value_index)) => {
+            new_values.remove(old_value_index);
+        }
+        (Some(new_value), None) => {
+            new_values.push((fert_id, new_value));
+        }
+        (Some(new_value), Some(old_value_index)) => {
+            new_values[old_value_index] = (fert_id, new_value);
+        }
+    }
+}
+
+fn get_fertilizer_value<T: Copy>(values: &[(usize, T)], fert_id: usize) -> Option<T> {
+    values
+        .iter()
+        .find(|record| record.0 == fert_id)
+        .map(|record| record.1)
 }
 
 impl PermanentFertilizersState {
-    pub fn new(selected: Vec<usize>, limited: Vec<(usize, f64)>) -> Self {
-        Self { selected: Rc::new(selected), limited: Rc::new(limited) }
+    pub fn new(
+        selected: Vec<usize>,
+        limited: Vec<(usize, f64)>,
+        minimums: Vec<(usize, FertilizerMinimum)>,
+        prices: Vec<(usize, f64)>,
+    ) -> Self {
+        Self {
+            selected: Rc::new(selected),
+            limited: Rc::new(limited),
+            minimums: Rc::new(minimums),
+            prices: Rc::new(prices),
+        }
     }
 
     pub fn toggle_selection(&mut self, fert_id: usize, is_selected: bool) {
@@ -33,33 +79,31 @@ impl PermanentFertilizersState {
     }
 
     pub fn set_limit(&mut self, fert_id: usize, limit: Option<f64>) {
-        let new_permanent_limits = Rc::make_mut(&mut self.limited);
-        match (
-            limit,
-            new_permanent_limits.iter().position(|l| l.0 == fert_id),
-        ) {
-            (None, None) => {}
-            (None, Some(old_limit_index)) => {
-                new_permanent_limits.remove(old_limit_index);
-            }
-            (Some(new_limit), None) => {
-                new_permanent_limits.push((fert_id, new_limit));
-            }
-            (Some(new_limit), Some(old_limit_index)) => {
-                new_permanent_limits[old_limit_index] = (fert_id, new_limit);
-            }
-        }
+        set_fertilizer_value(&mut self.limited, fert_id, limit);
+    }
+
+    pub fn set_minimum(&mut self, fert_id: usize, minimum: Option<FertilizerMinimum>) {
+        set_fertilizer_value(&mut self.minimums, fert_id, minimum);
+    }
+
+    pub fn set_price(&mut self, fert_id: usize, price: Option<f64>) {
+        set_fertilizer_value(&mut self.prices, fert_id, price);
     }
 
     pub fn is_selected(&self, fert_id: usize) -> bool {
-        self.selected.iter().any(|f| *f == fert_id)
+        self.selected.contains(&fert_id)
     }
 
     pub fn get_limit(&self, fert_id: usize) -> Option<f64> {
-        self.limited
-            .iter()
-            .find(|record| record.0 == fert_id)
-            .map(|record| record.1)
+        get_fertilizer_value(&self.limited, fert_id)
+    }
+
+    pub fn get_minimum(&self, fert_id: usize) -> Option<FertilizerMinimum> {
+        get_fertilizer_value(&self.minimums, fert_id)
+    }
+
+    pub fn get_price(&self, fert_id: usize) -> Option<f64> {
+        get_fertilizer_value(&self.prices, fert_id)
     }
 }
 
@@ -70,10 +114,26 @@ pub(crate) struct AppStore {
     pub permanent_fertilizers: Rc<PermanentFertilizersState>,
     /// Список добавленных вручную удобрений
     pub added_fertilizers: Rc<Vec<Fertilizer>>,
+    /// Назначение смеси
+    pub profile: MixtureProfile,
+    /// Культура, для которой подбирается смесь
+    pub crop: Crop,
     /// Диапазон соотношения к фосфору для азота, калия и магния
     pub ratios: Rc<ElemRatios>,
     /// Итоговая масса смеси
     pub mass: f64,
+    /// Что оптимизируется при подборе смеси
+    pub objective: MixtureObjective,
+    /// Наибольшее число различных удобрений в смеси
+    pub max_ingredients: Option<usize>,
+    /// Предельная доля одной из форм азота в смеси
+    pub nitrogen_form_limit: Option<NitrogenFormLimit>,
+    /// Уже приготовленная часть смеси, которую нужно дополнить
+    pub existing: Option<ExistingMixture>,
+    /// Идеальные соотношения к фосфору, если вместо диапазонов задан идеал
+    pub target: Option<TargetRatios>,
+    /// Решатель задач линейного программирования. Выбирается только параметром ссылки.
+    pub solver: SolverBackend,
 }
 
 pub(crate) enum AddedFertilizerAction {
@@ -86,16 +146,28 @@ pub(crate) enum AddedFertilizerAction {
 pub(crate) enum StoreAction {
     ToggleFertilizer(usize, bool),
     UpdatePermanentLimit(usize, Option<f64>),
+    UpdatePermanentMinimum(usize, Option<FertilizerMinimum>),
+    UpdatePermanentPrice(usize, Option<f64>),
     ChangeAdded(AddedFertilizerAction),
+    /// Выбор профиля смеси; соотношения заменяются соотношениями профиля, если они у него есть
+    UpdateProfile(MixtureProfile),
+    /// Выбор культуры; соотношения макроэлементов заменяются рекомендуемыми для неё
+    UpdateCrop(Crop),
     UpdateRatio(ElemRangeName, ElemRange),
+    UpdateSecondaryRatio(ElemName, Option<ElemRange>),
     UpdateMass(f64),
+    UpdateObjective(MixtureObjective),
+    UpdateMaxIngredients(Option<usize>),
+    UpdateNitrogenFormLimit(Option<NitrogenFormLimit>),
+    UpdateExisting(Option<ExistingMixture>),
+    UpdateTarget(Option<TargetRatios>),
 }
 
 impl Reducible for AppStore {
     type Action = StoreAction;
 
     fn reduce(mut self: Rc<Self>, action: Self::Action) -> Rc<Self> {
-        let mut new_self = Rc::make_mut(&mut self);
+        let new_self = Rc::make_mut(&mut self);
         match action {
             StoreAction::ToggleFertilizer(fert_id, is_selected) => {
                 let new_permanent = Rc::make_mut(&mut new_self.permanent_fertilizers);
@@ -118,10 +190,28 @@ impl Reducible for AppStore {
                     }
                 }
             }
+            StoreAction::UpdateProfile(profile) => {
+                new_self.profile = profile;
+                if let Some(ratios) = profile.ratios(new_self.crop) {
+                    new_self.ratios = Rc::new(ratios);
+                }
+            }
+            StoreAction::UpdateCrop(crop) => {
+                new_self.crop = crop;
+                let crop_ratios = crop.ratios();
+                let new_ranges = Rc::make_mut(&mut new_self.ratios);
+                for range_name in ElemRangeName::ALL {
+                    new_ranges[range_name] = crop_ratios[range_name];
+                }
+            }
             StoreAction::UpdateRatio(nutrient, range) => {
                 let new_ranges = Rc::make_mut(&mut new_self.ratios);
                 new_ranges[nutrient] = range;
             }
+            StoreAction::UpdateSecondaryRatio(element, range) => {
+                let new_ranges = Rc::make_mut(&mut new_self.ratios);
+                new_ranges.secondary.set(element, range);
+            }
             StoreAction::UpdateMass(mass) => {
                 new_self.mass = mass;
             }
@@ -129,6 +219,29 @@ impl Reducible for AppStore {
                 let new_permanent = Rc::make_mut(&mut new_self.permanent_fertilizers);
                 new_permanent.set_limit(fert_id, new_limit);
             }
+            StoreAction::UpdatePermanentMinimum(fert_id, new_minimum) => {
+                let new_permanent = Rc::make_mut(&mut new_self.permanent_fertilizers);
+                new_permanent.set_minimum(fert_id, new_minimum);
+            }
+            StoreAction::UpdatePermanentPrice(fert_id, new_price) => {
+                let new_permanent = Rc::make_mut(&mut new_self.permanent_fertilizers);
+                new_permanent.set_price(fert_id, new_price);
+            }
+            StoreAction::UpdateObjective(objective) => {
+                new_self.objective = objective;
+            }
+            StoreAction::UpdateMaxIngredients(max_ingredients) => {
+                new_self.max_ingredients = max_ingredients;
+            }
+            StoreAction::UpdateNitrogenFormLimit(limit) => {
+                new_self.nitrogen_form_limit = limit;
+            }
+            StoreAction::UpdateExisting(existing) => {
+                new_self.existing = existing;
+            }
+            StoreAction::UpdateTarget(target) => {
+                new_self.target = target;
+            }
         }
         self
     }
@@ -140,7 +253,15 @@ impl Default for AppStore {
             mass: 10.0,
             permanent_fertilizers: Default::default(),
             added_fertilizers: Default::default(),
+            profile: Default::default(),
+            crop: Default::default(),
             ratios: Default::default(),
+            objective: Default::default(),
+            max_ingredients: None,
+            nitrogen_form_limit: None,
+            existing: None,
+            target: None,
+            solver: Default::default(),
         }
     }
 }
@@ -149,7 +270,12 @@ impl AppStore {
     pub fn is_valid(&self) -> bool {
         let some_fertilizers_to_process =
             !(self.permanent_fertilizers.selected.is_empty() && self.added_fertilizers.is_empty());
-        some_fertilizers_to_process && self.ratios.all_valid() && self.mass > 0.0
+        let existing_fits = self
+            .existing
+            .is_none_or(|existing| existing.mass <= self.mass);
+        // В режиме идеальных соотношений диапазоны не используются
+        let ratios_valid = self.target.is_some() || self.ratios.all_valid();
+        some_fertilizers_to_process && ratios_valid && self.mass > 0.0 && existing_fits
     }
 
     pub fn to_mixture_query(&self) -> MixtureQuery {
@@ -165,10 +291,13 @@ impl AppStore {
                         let mut updated_copy = permanent_fertilizer.clone();
                         updated_copy.limit = self
                             .permanent_fertilizers
-                            .limited
-                            .iter()
-                            .find(|(id, _)| *id == permanent_fertilizer.id)
-                            .map(|(_, limit)| *limit);
+                            .get_limit(permanent_fertilizer.id);
+                        updated_copy.minimum = self
+                            .permanent_fertilizers
+                            .get_minimum(permanent_fertilizer.id);
+                        updated_copy.price = self
+                            .permanent_fertilizers
+                            .get_price(permanent_fertilizer.id);
                         updated_copy
                     })
             })
@@ -179,13 +308,24 @@ impl AppStore {
             N_ratio: self.ratios.n_to_p,
             K_ratio: self.ratios.k_to_p,
             Mg_ratio: self.ratios.mg_to_p,
+            secondary_ratios: self.ratios.secondary,
+            nitrogen_form_limit: self.nitrogen_form_limit,
+            profile: self.profile,
+            crop: self.crop,
             mass: self.mass,
+            objective: self.objective,
+            max_ingredients: self.max_ingredients,
+            existing: self.existing,
+            target: self.target,
+            solver: self.solver,
         }
     }
 
     pub fn from_mixture_query(query: &MixtureQuery) -> Self {
         let mut selected_fertilizers = Vec::new();
         let mut permanent_ferts_limits = Vec::new();
+        let mut permanent_ferts_minimums = Vec::new();
+        let mut permanent_ferts_prices = Vec::new();
         let mut added_fertilizers = Vec::<Fertilizer>::new();
         for f in query.fertilizers.iter() {
             let fert_content_id = f.content_id();
@@ -195,8 +335,16 @@ impl AppStore {
             {
                 Some(pf) => {
                     selected_fertilizers.push(pf.id);
+                    // Удобрения из запроса получают новые идентификаторы,
+                    // а состояние хранится по идентификаторам перманентных удобрений
                     if let Some(query_fert_limit) = f.limit {
-                        permanent_ferts_limits.push((f.id, query_fert_limit));
+                        permanent_ferts_limits.push((pf.id, query_fert_limit));
+                    }
+                    if let Some(query_fert_minimum) = f.minimum {
+                        permanent_ferts_minimums.push((pf.id, query_fert_minimum));
+                    }
+                    if let Some(query_fert_price) = f.price {
+                        permanent_ferts_prices.push((pf.id, query_fert_price));
                     }
                 }
                 None => added_fertilizers.push(f.clone()),
@@ -206,14 +354,25 @@ impl AppStore {
             permanent_fertilizers: Rc::new(PermanentFertilizersState::new(
                 selected_fertilizers,
                 permanent_ferts_limits,
+                permanent_ferts_minimums,
+                permanent_ferts_prices,
             )),
             added_fertilizers: Rc::new(added_fertilizers),
+            profile: query.profile,
+            crop: query.crop,
             ratios: Rc::new(ElemRatios {
                 n_to_p: query.N_ratio,
                 k_to_p: query.K_ratio,
                 mg_to_p: query.Mg_ratio,
+                secondary: query.secondary_ratios,
             }),
             mass: query.mass,
+            objective: query.objective,
+            max_ingredients: query.max_ingredients,
+            nitrogen_form_limit: query.nitrogen_form_limit,
+            existing: query.existing,
+            target: query.target,
+            solver: query.solver,
         }
     }
 }
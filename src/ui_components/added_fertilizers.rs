@@ -1,9 +1,14 @@
 use std::rc::Rc;
 
-use crate::calculator::{ElemName, Fertilizer};
+use crate::calculator::units::NutrientUnits;
+use crate::calculator::{ElemName, Fertilizer, FertilizerMinimum};
 use crate::store::AddedFertilizerAction;
-use crate::ui_components::html_chunks::{nutrient_input_css_class, CROSS_MARK};
+use crate::ui_components::html_chunks::{
+    capitalized, nutrient_input_css_class, NutrientTitle, CROSS_MARK,
+};
+use crate::ui_components::minimum_input::MinimumInput;
 use crate::ui_components::positive_float_input::PositiveFloatInput;
+use crate::ui_components::units_switch::UnitsSwitch;
 use crate::yew_utils::{include_css, make_element_id, FloatFormat};
 use gloo_storage::{LocalStorage, Storage};
 use stylist::yew::styled_component;
@@ -24,39 +29,52 @@ pub(crate) struct NewFertilizerInputProps {
     ondelete: Callback<usize>,
     onchange: Callback<Fertilizer>,
     edit: Fertilizer,
+    show_price: bool,
 }
 
 fn on_change_percentage_handler(
     edit: Fertilizer,
     onchange: Callback<Fertilizer>,
     element: ElemName,
+    units: NutrientUnits,
 ) -> Callback<Option<f64>> {
     Callback::from(move |new_value: Option<f64>| {
         let mut edit = edit.clone();
         if let Some(new_unpacked_value) = new_value {
-            edit[element] = new_unpacked_value;
+            edit[element] = units.canonical(element, new_unpacked_value);
         }
         onchange.emit(edit)
     })
 }
 
+/// Содержание элемента в единицах ввода. Округляется, чтобы после пересчёта туда и обратно
+/// в поле не появлялись хвосты вроде 19.999999999999996.
+fn displayed_content(edit: &Fertilizer, element: ElemName, units: NutrientUnits) -> f64 {
+    const DISPLAY_PRECISION: f64 = 1e4;
+    (units.convert(element, edit[element]) * DISPLAY_PRECISION).round() / DISPLAY_PRECISION
+}
+
 #[function_component(NewFertilizerInput)]
 pub(crate) fn new_fertilizer_input(
-    NewFertilizerInputProps { ondelete, onchange, edit }: &NewFertilizerInputProps,
+    NewFertilizerInputProps { ondelete, onchange, edit, show_price }: &NewFertilizerInputProps,
 ) -> Html {
     // Поля ввода, к которым мы будем обращаться напрямую
     let name_input_ref = use_node_ref();
-    let cl_input_ref = use_node_ref();
-    let s_input_ref = use_node_ref();
 
     // Идентификаторы всех полей, для связывания с метками <label>
-    let n_input_id = use_memo(make_element_id("new-n-amount"), ());
-    let p_input_id = use_memo(make_element_id("new-p-amount"), ());
-    let k_input_id = use_memo(make_element_id("new-k-amount"), ());
-    let mg_input_id = use_memo(make_element_id("new-mg-amount"), ());
-    let cl_input_id = use_memo(make_element_id("new-cl-contains"), ());
-    let s_input_id = use_memo(make_element_id("new-s-contains"), ());
+    // (у полей содержания элементов к нему добавляется обозначение элемента)
+    let content_input_id = use_memo(make_element_id("new-amount"), ());
     let limit_input_id = use_memo(make_element_id("limit-amount"), ());
+    let minimum_input_id = use_memo(make_element_id("minimum-amount"), ());
+    let price_input_id = use_memo(make_element_id("price-amount"), ());
+
+    // Единицы, в которых вводится содержание элементов. Само удобрение всегда хранится в оксидах.
+    let input_units = use_state(NutrientUnits::default);
+    let units = *input_units;
+    let on_units_change = {
+        let input_units = input_units.clone();
+        Callback::from(move |units| input_units.set(units))
+    };
 
     {
         // Установит фокус на имени удобрения при первом отображении компонента
@@ -78,24 +96,67 @@ pub(crate) fn new_fertilizer_input(
         Callback::from(move |_| ondelete.emit(fert_id))
     };
 
-    let percentage_input_column =
-        |input_id: Rc<AttrValue>, label: &str, element: ElemName| -> Html {
-            let on_value_changed =
-                on_change_percentage_handler(edit.clone(), onchange.clone(), element);
-            html! {
-            <div class="col">
-                <label class="form-label" for={input_id.as_ref()}>{label}</label>
-                <PositiveFloatInput size="10"
-                    class={nutrient_input_css_class(element)}
-                    on_value_change={on_value_changed}
-                    id={input_id.as_ref()}
-                    required={true}
-                    value={ Some(edit[element]) }
-                    enforce_min={Some(0.0)}
-                    enforce_max={Some(100.0)} />
-            </div>
-            }
+    let input_id = |element: ElemName| format!("{}-{}", content_input_id, element.symbol());
+    let input_label = |element: ElemName| -> Html {
+        let info = element.info();
+        html! {
+            <label class="form-label" for={input_id(element)}>
+                {capitalized(info.name)}
+                if units.formula(element) != info.symbol {
+                    {" ("}<NutrientTitle {element} {units} />{")"}
+                }
+                {", %"}
+            </label>
+        }
+    };
+
+    let percentage_input_column = |element: ElemName| -> Html {
+        let on_value_changed =
+            on_change_percentage_handler(edit.clone(), onchange.clone(), element, units);
+        // Поле пересоздаётся при смене единиц (`key`), иначе в нём останется прежний текст
+        html! {
+        <div class="col">
+            {input_label(element)}
+            <PositiveFloatInput size="10"
+                key={format!("{:?}", units)}
+                class={nutrient_input_css_class(element)}
+                on_value_change={on_value_changed}
+                id={input_id(element)}
+                required={true}
+                value={ Some(displayed_content(edit, element, units)) }
+                enforce_min={Some(0.0)}
+                enforce_max={Some(100.0)} />
+        </div>
+        }
+    };
+
+    // Содержание вторичных элементов на упаковке указывают не всегда, поэтому поле
+    // можно оставить пустым - это то же самое, что ноль
+    let secondary_input_column = |element: ElemName| -> Html {
+        let on_value_changed = {
+            let edit = edit.clone();
+            let onchange = onchange.clone();
+            Callback::from(move |new_value: Option<f64>| {
+                let mut edit = edit.clone();
+                edit[element] = units.canonical(element, new_value.unwrap_or_default());
+                onchange.emit(edit)
+            })
         };
+        html! {
+        <div class="col">
+            {input_label(element)}
+            <PositiveFloatInput size="10"
+                key={format!("{:?}", units)}
+                placeholder="0"
+                on_value_change={on_value_changed}
+                id={input_id(element)}
+                required={false}
+                value={ (edit[element] > 0.0).then(|| displayed_content(edit, element, units)) }
+                enforce_min={Some(0.0)}
+                enforce_max={Some(100.0)} />
+        </div>
+        }
+    };
 
     let on_name_changed = {
         let name_input_ref = name_input_ref.clone();
@@ -119,21 +180,22 @@ pub(crate) fn new_fertilizer_input(
         })
     };
 
-    let on_s_cl_checkbox_change = {
+    let on_minimum_changed = {
         let edit = edit.clone();
         let onchange = onchange.clone();
-        let s_input_ref = s_input_ref.clone();
-        let cl_input_ref = cl_input_ref.clone();
-        Callback::from(move |_| {
-            if let (Some(s_input), Some(cl_input)) = (
-                s_input_ref.cast::<HtmlInputElement>(),
-                cl_input_ref.cast::<HtmlInputElement>(),
-            ) {
-                onchange.emit(Fertilizer {
-                    with_Cl: cl_input.checked(),
-                    with_S: s_input.checked(),
-                    ..edit.clone()
-                });
+        Callback::from(move |new_minimum: Option<FertilizerMinimum>| {
+            if edit.minimum != new_minimum {
+                onchange.emit(Fertilizer { minimum: new_minimum, ..edit.clone() });
+            }
+        })
+    };
+
+    let on_price_changed = {
+        let edit = edit.clone();
+        let onchange = onchange.clone();
+        Callback::from(move |new_price: Option<f64>| {
+            if edit.price != new_price {
+                onchange.emit(Fertilizer { price: new_price, ..edit.clone() });
             }
         })
     };
@@ -148,16 +210,16 @@ pub(crate) fn new_fertilizer_input(
                         onchange={on_name_changed}
                         value={ edit.name.as_ref().to_owned() } />
                 </div>
+                <div class="col-auto">
+                    <UnitsSwitch value={units} on_change={on_units_change} />
+                </div>
                 <div class="col-auto">
                     <button type="button" class="new-fert-delete btn btn-danger"
                     onclick={on_delete_pressed}>{CROSS_MARK} { " удалить" }</button>
                 </div>
             </div>
             <div class="row pt-2">
-                {percentage_input_column(n_input_id, "Азот, %", ElemName::Nitrogen)}
-                {percentage_input_column(p_input_id, "Фосфор, %", ElemName::Phosphorus)}
-                {percentage_input_column(k_input_id, "Калий, %", ElemName::Potassium)}
-                {percentage_input_column(mg_input_id, "Магний, %", ElemName::Magnesium)}
+                {for ElemName::MACRO.into_iter().map(percentage_input_column)}
                 <div class="col">
                     <label class="form-label" for={&*limit_input_id}>{"Наличие"}</label>
                     <PositiveFloatInput
@@ -168,37 +230,32 @@ pub(crate) fn new_fertilizer_input(
                         on_value_change={on_limit_changed}
                         value={edit.limit} />
                 </div>
+                <div class="col">
+                    <label class="form-label" for={&*minimum_input_id}>{"Минимум"}</label>
+                    <MinimumInput
+                        size="6"
+                        id={minimum_input_id.as_ref()}
+                        value={edit.minimum}
+                        on_change={on_minimum_changed} />
+                </div>
+                if *show_price {
+                    <div class="col">
+                        <label class="form-label" for={&*price_input_id}>{"Цена"}</label>
+                        <PositiveFloatInput
+                            size="10"
+                            placeholder="₽/кг"
+                            required={false}
+                            id={price_input_id.as_ref()}
+                            on_value_change={on_price_changed}
+                            value={edit.price} />
+                    </div>
+                }
             </div>
             <div class="row pt-2">
-                <div class="col-auto">
+                <div class="col-12 form-text">
                 {"Если знаете:"}
                 </div>
-                <div class="col-auto">
-                    <div class="form-check">
-                        <input type="checkbox" class="form-check-input"
-                            id={&*cl_input_id}
-                            ref={&cl_input_ref}
-                            checked={edit.with_Cl}
-                            onchange={&on_s_cl_checkbox_change}
-                            />
-                        <label class="form-check-label" for={&*cl_input_id}>
-                        {"Содержит хлориды"}
-                        </label>
-                    </div>
-                </div>
-                <div class="col-auto">
-                    <div class="form-check">
-                        <input type="checkbox" class="form-check-input"
-                            id={&*s_input_id}
-                            ref={&s_input_ref}
-                            checked={edit.with_S}
-                            onchange={&on_s_cl_checkbox_change}
-                            />
-                        <label class="form-check-label" for={&*s_input_id}>
-                        {"Содержит сульфаты"}
-                        </label>
-                    </div>
-                </div>
+                {for ElemName::secondary().map(secondary_input_column)}
             </div>
         </div>
     }
@@ -208,6 +265,8 @@ pub(crate) fn new_fertilizer_input(
 pub(crate) struct AddedFertilizersProps {
     pub on_change: Callback<AddedFertilizerAction>,
     pub fertilizers: Rc<Vec<Fertilizer>>,
+    /// Показывать ли поля для ввода цен удобрений
+    pub show_prices: bool,
 }
 
 /// Возвращает историю всех ранее добавленных вручную удобрений,
@@ -233,6 +292,7 @@ fn top_history_of_added_fertilizers(exclusion_list: &[Fertilizer]) -> Vec<Fertil
             } else {
                 fertilizer.re_id();
                 fertilizer.limit = None;
+                fertilizer.minimum = None;
                 Some(fertilizer)
             }
         })
@@ -297,7 +357,12 @@ pub(crate) fn AddedFertilizers(props: &AddedFertilizersProps) -> Html {
         let ondelete = on_delete.clone();
         let onchange = on_edit.clone();
         html! {
-            <NewFertilizerInput key={fertilizer.id} edit={fertilizer.clone()} {ondelete} {onchange} />
+            <NewFertilizerInput
+                key={fertilizer.id}
+                edit={fertilizer.clone()}
+                show_price={props.show_prices}
+                {ondelete}
+                {onchange} />
         }
     });
 
@@ -319,10 +384,13 @@ pub(crate) fn AddedFertilizers(props: &AddedFertilizersProps) -> Html {
                     html! {
                         <li><a class="dropdown-item" onclick={on_recent_add}>
                             {&fert.name}{" ("}
-                            {FloatFormat::new(fert.N, 2)}{"-"}
-                            {FloatFormat::new(fert.P, 2)}{"-"}
-                            {FloatFormat::new(fert.K, 2)}{"-"}
-                            {FloatFormat::new(fert.Mg, 2)}{")"}
+                            {for ElemName::MACRO.into_iter().enumerate().map(|(idx, element)| html! {
+                                <>
+                                    if idx > 0 {{"-"}}
+                                    {FloatFormat::new(fert[element], 2)}
+                                </>
+                            })}
+                            {")"}
                         </a></li>
                     }
                 })
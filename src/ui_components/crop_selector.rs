@@ -0,0 +1,42 @@
+use yew::prelude::*;
+
+use crate::calculator::crops::Crop;
+use crate::yew_utils::make_element_id;
+
+#[derive(Properties, PartialEq)]
+pub(crate) struct CropSelectorProps {
+    pub value: Crop,
+    pub on_change: Callback<Crop>,
+}
+
+/// Выбор культуры. Культура подставляет свои соотношения азота, калия и магния к фосфору
+/// и определяет дозы внесения смеси на грядах.
+#[function_component(CropSelector)]
+pub(crate) fn crop_selector(props: &CropSelectorProps) -> Html {
+    let group_name = use_memo(make_element_id("crop"), ());
+
+    let crop_option = |crop: Crop| -> Html {
+        let input_id = format!("{}-{:?}", group_name, crop);
+        let onchange = {
+            let on_change = props.on_change.clone();
+            Callback::from(move |_| on_change.emit(crop))
+        };
+        html! {
+            <div class="form-check form-check-inline">
+                <input type="radio" class="form-check-input"
+                    name={(*group_name).clone()}
+                    id={input_id.clone()}
+                    checked={props.value == crop}
+                    {onchange} />
+                <label class="form-check-label" for={input_id}>{crop.title()}</label>
+            </div>
+        }
+    };
+
+    html! {
+        <div class="mb-3">
+            <span class="me-3">{"Культура:"}</span>
+            {for Crop::ALL.into_iter().map(crop_option)}
+        </div>
+    }
+}
@@ -3,33 +3,154 @@ use std::rc::Rc;
 use yew::prelude::*;
 
 use crate::{
-    calculator::{consts::PERMANENT_FERTILIZERS, Deficites, ElemName, Fertilizer},
-    ui_components::html_chunks::nutrient_css_class,
+    calculator::{
+        consts::PERMANENT_FERTILIZERS,
+        infeasibility::{find_conflicting_constraints, ConflictingConstraint},
+        query::{MixtureQuery, TaskConstraint},
+        Deficite, Deficites, ElemName, FertilizerMinimum,
+    },
+    ui_components::html_chunks::{
+        capitalized, nutrient_css_class, ratio_title, secondary_ratio_title,
+    },
+    yew_utils::FloatFormat,
 };
 
 #[derive(PartialEq, Properties)]
 pub(crate) struct DeficiteDescriptionProps {
     pub deficites: Rc<Deficites>,
+    pub query: Rc<MixtureQuery>,
     pub on_calc_another: Callback<()>,
 }
 
-fn top_fertilizers_for_elem(fertilizers: &[Fertilizer], element: ElemName) -> AttrValue {
-    let mut matches: Vec<_> = fertilizers
+/// Удобрения, восполняющие недостачу, начиная с тех, которых понадобится меньше всего
+fn render_remedies(deficite: &Deficite) -> Html {
+    if deficite.remedies.is_empty() {
+        return html! {"Ни одно из известных удобрений по отдельности не помогает"};
+    }
+    let remedies = deficite.remedies.iter().map(|(perm_idx, weight)| {
+        html! {
+            <li>
+                {&PERMANENT_FERTILIZERS[*perm_idx].name}
+                <small class="text-muted">
+                    {" (около "}{FloatFormat::new(*weight, 2)}{" кг в смеси)"}
+                </small>
+            </li>
+        }
+    });
+    html! {<ul class="list-unstyled mb-0">{for remedies}</ul>}
+}
+
+/// Округляет предлагаемое значение до сотых в ту сторону, в которую ограничение ослабляется,
+/// чтобы введённое пользователем значение действительно было достаточным
+fn round_relaxed(relaxed: f64, relax_down: bool) -> f64 {
+    if relax_down {
+        (relaxed * 100.0).floor() / 100.0
+    } else {
+        (relaxed * 100.0).ceil() / 100.0
+    }
+}
+
+fn render_conflict(query: &MixtureQuery, conflict: &ConflictingConstraint) -> Html {
+    let (description, action, unit) = match conflict.constraint {
+        TaskConstraint::Limit(fert_idx) => (
+            format!("Остаток удобрения \"{}\"", query.fertilizers[fert_idx].name),
+            "увеличьте хотя бы до ",
+            " кг",
+        ),
+        TaskConstraint::Minimum(fert_idx) => {
+            let fertilizer = &query.fertilizers[fert_idx];
+            (
+                format!("Минимум удобрения \"{}\"", fertilizer.name),
+                "снизьте хотя бы до ",
+                match fertilizer.minimum {
+                    Some(FertilizerMinimum::Share(_)) => "%",
+                    _ => " кг",
+                },
+            )
+        }
+        TaskConstraint::RatioFrom(range_name) => (
+            format!("Нижняя граница соотношения {}", ratio_title(range_name)),
+            "снизьте хотя бы до ",
+            "",
+        ),
+        TaskConstraint::RatioTo(range_name) => (
+            format!("Верхняя граница соотношения {}", ratio_title(range_name)),
+            "поднимите хотя бы до ",
+            "",
+        ),
+        TaskConstraint::SecondaryFrom(element) => (
+            format!(
+                "Нижняя граница соотношения {}",
+                secondary_ratio_title(element)
+            ),
+            "снизьте хотя бы до ",
+            "",
+        ),
+        TaskConstraint::SecondaryTo(element) => (
+            format!(
+                "Верхняя граница соотношения {}",
+                secondary_ratio_title(element)
+            ),
+            "поднимите хотя бы до ",
+            "",
+        ),
+        TaskConstraint::NitrogenShare(form) => (
+            format!("Предельная доля {} азота", form.genitive_title()),
+            "поднимите хотя бы до ",
+            "%",
+        ),
+        TaskConstraint::Existing => (
+            format!(
+                "Масса всей смеси, вместе с уже смешанными {} кг",
+                FloatFormat::new(query.existing.map_or(0.0, |existing| existing.mass), 3)
+            ),
+            "увеличьте хотя бы до ",
+            " кг",
+        ),
+        TaskConstraint::Phosphorus | TaskConstraint::Target(_) => return html! {},
+    };
+    let relax_down = matches!(
+        conflict.constraint,
+        TaskConstraint::RatioFrom(_)
+            | TaskConstraint::SecondaryFrom(_)
+            | TaskConstraint::Minimum(_)
+    );
+    html! {
+        <li>
+            {description}
+            if let Some(relaxation) = conflict.relaxation {
+                {" ("}{FloatFormat::new(relaxation.value, 3)}{unit}{"): "}{action}
+                <strong>
+                    {FloatFormat::new(round_relaxed(relaxation.relaxed, relax_down), 2)}{unit}
+                </strong>
+            } else {
+                {" (ослабить только это условие недостаточно)"}
+            }
+        </li>
+    }
+}
+
+fn render_conflicts(query: &MixtureQuery, conflicts: &[ConflictingConstraint]) -> Html {
+    let only_phosphorus = conflicts
         .iter()
-        .filter(|fertilizer| {
-            let concentration = fertilizer[element];
-            concentration > 0.0
-        })
-        .collect();
-    matches.sort_by(|fert1, fert2| f64::total_cmp(&fert1[element], &fert2[element]));
-    let mut result = String::new();
-    for (i, m) in matches.iter().enumerate() {
-        if i != 0 {
-            result.push_str(", ")
+        .all(|conflict| conflict.constraint == TaskConstraint::Phosphorus);
+    html! {
+        if conflicts.is_empty() {
+        } else if only_phosphorus {
+            <p>{"Ни в одном из выбранных удобрений нет фосфора."}</p>
+        } else {
+            <>
+                <h3>{"Какие условия противоречат друг другу"}</h3>
+                <p class="help">
+                    {"Все эти условия одновременно выполнить нельзя, но достаточно ослабить \
+                      любое одно из них:"}
+                </p>
+                <ul>
+                    {for conflicts.iter().map(|conflict| render_conflict(query, conflict))}
+                </ul>
+            </>
         }
-        result.push_str(&m.name)
     }
-    AttrValue::from(result)
 }
 
 /// Объясняет, почему смесь не может быть составлена. В норме этот компонент никогда
@@ -40,8 +161,10 @@ fn top_fertilizers_for_elem(fertilizers: &[Fertilizer], element: ElemName) -> At
 /// и надо показать какое-то сообщение о проблеме. Что и делает данный компонент.
 #[function_component(DeficiteDescription)]
 pub(crate) fn deficite_description(
-    DeficiteDescriptionProps { deficites, on_calc_another }: &DeficiteDescriptionProps,
+    DeficiteDescriptionProps { deficites, query, on_calc_another }: &DeficiteDescriptionProps,
 ) -> Html {
+    let conflicts = use_memo(|query| find_conflicting_constraints(query), query.clone());
+
     let on_calc_another_click = {
         let on_calc_another = on_calc_another.clone();
         move |_| {
@@ -49,12 +172,19 @@ pub(crate) fn deficite_description(
         }
     };
 
-    let deficite_row = |name: &str, element: ElemName| -> Html {
+    let deficite_row = |element: ElemName| -> Html {
+        let info = element.info();
         html! {
-            if deficites[element] {
+            if let Some(deficite) = &deficites[element] {
                 <tr>
-                    <td class={classes!("p-2", nutrient_css_class(element))}>{name}</td>
-                    <td>{top_fertilizers_for_elem(PERMANENT_FERTILIZERS, element)}</td>
+                    <td class={classes!("p-2", nutrient_css_class(element))}>
+                        {capitalized(info.genitive_name)}{" ("}{info.symbol}{")"}
+                    </td>
+                    <td>
+                        {FloatFormat::new(deficite.amount, 3)}{" кг ("}
+                        {FloatFormat::new(deficite.percent, 0)}{"%)"}
+                    </td>
+                    <td>{render_remedies(deficite)}</td>
                 </tr>
             }
         }
@@ -62,7 +192,7 @@ pub(crate) fn deficite_description(
 
     html! {
         <>
-            <h1>{ "Вариант смеси №2 по методу Митлайдера" }</h1>
+            <h1>{ query.profile.heading() }</h1>
             <p class="warning">
                 <span class="warning"><strong>
                     { "Из этих удобрений нельзя составить нужную смесь с заданными условиями" }
@@ -72,16 +202,15 @@ pub(crate) fn deficite_description(
                 <thead>
                     <tr>
                         <th>{"В смеси не хватает"}</th>
-                        <th>{"Попробуйте добавить эти удобрения, содержащих нужный элемент"}</th>
+                        <th>{"Сколько"}</th>
+                        <th>{"Попробуйте добавить одно из этих удобрений"}</th>
                     </tr>
                 </thead>
                 <tbody>
-                {deficite_row("Азота (N)", ElemName::Nitrogen)}
-                {deficite_row("Калия (K)", ElemName::Potassium)}
-                {deficite_row("Фосфора (P)", ElemName::Phosphorus)}
-                {deficite_row("Магния (Mg)", ElemName::Magnesium)}
+                {for ElemName::ALL.into_iter().map(deficite_row)}
                 </tbody>
             </table>
+            {render_conflicts(query, &conflicts)}
           <div id="recommended">
             <a class="btn btn-secondary" href="#calculator" onclick={on_calc_another_click}>
             { "Исправить список удобрений" }
@@ -2,21 +2,27 @@ use std::rc::Rc;
 
 use stylist::style;
 use stylist::yew::styled_component;
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
-use crate::calculator::{ElemRange, ElemRangeName, ElemRatios};
-use crate::ui_components::html_chunks::{PhosphorusOxide, PotassiumOxide};
+use crate::calculator::crops::Crop;
+use crate::calculator::profiles::MixtureProfile;
+use crate::calculator::{ElemName, ElemRange, ElemRangeName, ElemRatios, TargetRatios};
+use crate::ui_components::crop_selector::CropSelector;
+use crate::ui_components::html_chunks::{
+    nutrient_name, NutrientTitle, PhosphorusOxide, PotassiumOxide,
+};
 use crate::ui_components::positive_float_input::PositiveFloatInput;
+use crate::ui_components::profile_selector::ProfileSelector;
 use crate::yew_utils::make_element_id;
 
 const MIN_RANGE_DELTA: f64 = 0.01;
 
 #[derive(PartialEq, Properties, Clone)]
 pub(crate) struct ElemRangeProps {
-    name: ElemRangeName,
     id: AttrValue,
     range: ElemRange,
-    onchange: Callback<(ElemRangeName, ElemRange)>,
+    onchange: Callback<ElemRange>,
 }
 
 /// Отдельный диапазон значений "от" и "до", включающий два поля ввода, синхронизированных
@@ -24,27 +30,27 @@ pub(crate) struct ElemRangeProps {
 #[function_component(ElemRangeInput)]
 pub(crate) fn elem_range_input(props: &ElemRangeProps) -> Html {
     let on_lower_bound_change = use_callback(
-        |entered_value, (range_name, upper_bound, onchange)| {
+        |entered_value, (upper_bound, onchange)| {
             if let Some(value) = entered_value {
                 let new_range = ElemRange { from: value, to: *upper_bound };
                 if new_range.is_valid() {
-                    onchange.emit((*range_name, new_range));
+                    onchange.emit(new_range);
                 }
             }
         },
-        (props.name, props.range.to, props.onchange.clone()),
+        (props.range.to, props.onchange.clone()),
     );
 
     let on_upper_bound_change = use_callback(
-        |entered_upper_value, (range_name, lower_bound, onchange)| {
+        |entered_upper_value, (lower_bound, onchange)| {
             if let Some(value) = entered_upper_value {
                 let new_range = ElemRange { from: *lower_bound, to: value };
                 if new_range.is_valid() {
-                    onchange.emit((*range_name, new_range));
+                    onchange.emit(new_range);
                 }
             }
         },
-        (props.name, props.range.from, props.onchange.clone()),
+        (props.range.from, props.onchange.clone()),
     );
 
     html! {
@@ -68,10 +74,111 @@ pub(crate) fn elem_range_input(props: &ElemRangeProps) -> Html {
     }
 }
 
+#[derive(PartialEq, Properties, Clone)]
+pub(crate) struct TargetInputProps {
+    name: ElemRangeName,
+    id: AttrValue,
+    value: f64,
+    onchange: Callback<(ElemRangeName, f64)>,
+}
+
+/// Поле для идеального соотношения, которое заменяет диапазон в режиме идеальных соотношений
+#[function_component(TargetInput)]
+pub(crate) fn target_input(props: &TargetInputProps) -> Html {
+    let on_value_change = use_callback(
+        |entered_value: Option<f64>, (range_name, onchange)| {
+            if let Some(value) = entered_value {
+                onchange.emit((*range_name, value));
+            }
+        },
+        (props.name, props.onchange.clone()),
+    );
+
+    html! {
+        <PositiveFloatInput
+            id={&props.id}
+            on_value_change={&on_value_change}
+            required={true}
+            value={Some(props.value)}
+            />
+    }
+}
+
+/// Либо диапазон соотношения, либо (если задан идеал) одно поле для идеального соотношения
+fn ratio_input(props: &NutrientRatiosProps, name: ElemRangeName, id: &Rc<AttrValue>) -> Html {
+    match props.target {
+        Some(target) => {
+            let on_target_change = props.on_target_change.clone();
+            let onchange = Callback::from(move |(range_name, value): (ElemRangeName, f64)| {
+                let mut new_target = target;
+                new_target[range_name] = value;
+                on_target_change.emit(Some(new_target));
+            });
+            html! {
+                <TargetInput id={(**id).clone()} {name} value={target[name]} {onchange} />
+            }
+        }
+        None => {
+            let on_ratio_change = props.on_ratio_change.clone();
+            let onchange = Callback::from(move |range| on_ratio_change.emit((name, range)));
+            html! {
+                <ElemRangeInput id={(**id).clone()} range={props.ratios[name]} {onchange} />
+            }
+        }
+    }
+}
+
+/// Необязательный диапазон соотношения вторичного элемента: флажок и, если он отмечен,
+/// поля "от" и "до"
+fn secondary_ratio_input(props: &NutrientRatiosProps, element: ElemName) -> Html {
+    let range = props.ratios.secondary.get(element);
+    let on_toggle = {
+        let on_secondary_ratio_change = props.on_secondary_ratio_change.clone();
+        Callback::from(move |_| {
+            let new_range = match range {
+                Some(_) => None,
+                None => element.info().default_ratio,
+            };
+            on_secondary_ratio_change.emit((element, new_range));
+        })
+    };
+    let onchange = {
+        let on_secondary_ratio_change = props.on_secondary_ratio_change.clone();
+        Callback::from(move |range| on_secondary_ratio_change.emit((element, Some(range))))
+    };
+    let id = format!("secondary-ratio-{}", element.symbol());
+    html! {
+        <div class="col">
+            <div class="form-check">
+                <input type="checkbox" class="form-check-input"
+                    id={id.clone()}
+                    checked={range.is_some()}
+                    onchange={on_toggle} />
+                <label class="form-check-label" for={id.clone()}>
+                    {nutrient_name(element)}{" "}<small class="text-muted">{"("}<NutrientTitle {element} />{")"}</small>
+                </label>
+            </div>
+            if let Some(range) = range {
+                <ElemRangeInput id={format!("{}-range", id)} {range} {onchange} />
+            }
+        </div>
+    }
+}
+
 #[derive(Properties, PartialEq)]
 pub(crate) struct NutrientRatiosProps {
+    /// Назначение смеси, подставляющее свои соотношения
+    pub profile: MixtureProfile,
+    pub on_profile_change: Callback<MixtureProfile>,
+    /// Культура, подставляющая свои соотношения макроэлементов
+    pub crop: Crop,
+    pub on_crop_change: Callback<Crop>,
     pub ratios: Rc<ElemRatios>,
     pub on_ratio_change: Callback<(ElemRangeName, ElemRange)>,
+    /// Идеальные соотношения, если вместо диапазонов пользователь задал идеал
+    pub target: Option<TargetRatios>,
+    pub on_target_change: Callback<Option<TargetRatios>>,
+    pub on_secondary_ratio_change: Callback<(ElemName, Option<ElemRange>)>,
 }
 
 /// Отвечает за ввод трёх диапазонов, каждый задающий соотношение одного макро-элемента питания
@@ -81,6 +188,20 @@ pub(crate) fn NutrientRatios(props: &NutrientRatiosProps) -> Html {
     let n_input_id = use_memo(make_element_id("n-ratio"), ());
     let k_input_id = use_memo(make_element_id("k-ratio"), ());
     let mg_input_id = use_memo(make_element_id("mg-ratio"), ());
+    let target_checkbox_id = use_memo(make_element_id("target-ratio"), ());
+    let target_checkbox_ref = use_node_ref();
+
+    let on_target_toggle = {
+        let target_checkbox_ref = target_checkbox_ref.clone();
+        let on_target_change = props.on_target_change.clone();
+        let ratios = props.ratios.clone();
+        Callback::from(move |_| {
+            if let Some(input) = target_checkbox_ref.cast::<HtmlInputElement>() {
+                // Начальный идеал - середины текущих диапазонов
+                on_target_change.emit(input.checked().then(|| TargetRatios::middle_of(&ratios)));
+            }
+        })
+    };
 
     let stylesheet = style!(
         input[type=number] { max-width: ${"5em"}; min-width: ${"3.6em"}; }
@@ -94,9 +215,22 @@ pub(crate) fn NutrientRatios(props: &NutrientRatiosProps) -> Html {
             <h2>{ "Шаг 2. Баланс элементов в смеси" }
             <small class="text-muted">{" (можно пропустить)"}</small>
             </h2>
-            <p>{ "Здесь приведены соотношения, рекомендуемые Т.Ю.Угаровой \
-                  (минимум-максимум для каждого элемента). Но если вы опытный овощевод, \
-                  можете внести свои коррективы" }</p>
+            <p>{ "Выберите, для чего и для какой культуры предназначена смесь: ниже появятся \
+                  рекомендуемые для неё соотношения (минимум-максимум для каждого элемента). \
+                  Но если вы опытный овощевод, можете внести свои коррективы" }</p>
+            <ProfileSelector value={props.profile} on_change={props.on_profile_change.clone()} />
+            <CropSelector value={props.crop} on_change={props.on_crop_change.clone()} />
+            <div class="form-check mb-2">
+                <input type="checkbox" class="form-check-input"
+                    id={(*target_checkbox_id).clone()}
+                    ref={target_checkbox_ref}
+                    checked={props.target.is_some()}
+                    onchange={on_target_toggle} />
+                <label class="form-check-label" for={(*target_checkbox_id).clone()}>
+                    {"Задать идеальное соотношение вместо диапазонов: калькулятор подберёт \
+                      смесь, наиболее близкую к нему, даже если точно его не достичь"}
+                </label>
+            </div>
             <div class="container-fluid">
                 <div class="row">
                     <div class="col-2">
@@ -113,11 +247,7 @@ pub(crate) fn NutrientRatios(props: &NutrientRatiosProps) -> Html {
                         <label for={n_input_id.as_ref()} class="form-label">
                             { "азот "}<small class="text-muted">{"(N)"}</small>
                         </label>
-                        <ElemRangeInput
-                            id={(*n_input_id).clone()}
-                            name={ElemRangeName::Nitrogen}
-                            range={props.ratios.n_to_p}
-                            onchange={&props.on_ratio_change} />
+                        {ratio_input(props, ElemRangeName::Nitrogen, &n_input_id)}
                     </div>
 
                     <div class="col">
@@ -127,25 +257,25 @@ pub(crate) fn NutrientRatios(props: &NutrientRatiosProps) -> Html {
                                 <small class="text-muted">{"("}<PotassiumOxide />{")"}</small>
                             </span>
                         </label>
-                        <ElemRangeInput
-                            id={(*k_input_id).clone()}
-                            name={ElemRangeName::Potassium}
-                            range={props.ratios.k_to_p}
-                            onchange={&props.on_ratio_change} />
+                        {ratio_input(props, ElemRangeName::Potassium, &k_input_id)}
                     </div>
 
                     <div class="col">
                         <label for={mg_input_id.as_ref()} class="form-label">
                         { "магний "}<small class="text-muted">{"(MgO)"}</small>
                         </label>
-                        <ElemRangeInput
-                            id={(*mg_input_id).clone()}
-                            name={ElemRangeName::Magnesium}
-                            range={props.ratios.mg_to_p}
-                            onchange={&props.on_ratio_change} />
+                        {ratio_input(props, ElemRangeName::Magnesium, &mg_input_id)}
                     </div>
                 </div>
             </div>
+            <h5 class="mt-3">{"Вторичные элементы "}<small class="text-muted">{"(необязательно)"}</small></h5>
+            <div class="container-fluid">
+                <div class="row">
+                    {for ElemName::secondary().map(|element| {
+                        secondary_ratio_input(props, element)
+                    })}
+                </div>
+            </div>
         </div>
     }
 }
@@ -1,13 +1,15 @@
 //! Небольшое количество элементов, таких как формулы веществ, определённые символы,
 //! которые повторяются снова и снова в различных компонентах.
 
-use yew::{function_component, html, Html};
+use yew::{function_component, html, Html, Properties};
 
-use crate::calculator::ElemName;
+use crate::calculator::units::NutrientUnits;
+use crate::calculator::{ElemName, ElemRangeName};
 
 pub const CROSS_MARK: &str = " \u{2718}"; // ✘ symbol
 pub const CHECK_MARK: &str = " \u{2713}"; // ✓ symbol
 pub const MDASH: &str = "\u{2014}"; // — symbol
+pub const CURRENCY: &str = " \u{20bd}"; // ₽ symbol
 
 #[function_component]
 pub fn PhosphorusOxide() -> Html {
@@ -22,19 +24,65 @@ pub fn PotassiumOxide() -> Html {
 }
 
 pub(crate) fn nutrient_css_class(element: ElemName) -> &'static str {
-    match element {
-        ElemName::Nitrogen => "nutrient-N",
-        ElemName::Phosphorus => "nutrient-P",
-        ElemName::Potassium => "nutrient-K",
-        ElemName::Magnesium => "nutrient-Mg",
-    }
+    element.info().css_class
 }
 
 pub(crate) fn nutrient_input_css_class(element: ElemName) -> &'static str {
-    match element {
-        ElemName::Nitrogen => "nutrient-N-input",
-        ElemName::Phosphorus => "nutrient-P-input",
-        ElemName::Potassium => "nutrient-K-input",
-        ElemName::Magnesium => "nutrient-Mg-input",
+    element.info().input_css_class
+}
+
+pub(crate) fn ratio_title(range_name: ElemRangeName) -> &'static str {
+    match range_name {
+        ElemRangeName::Nitrogen => "N:P",
+        ElemRangeName::Potassium => "K:P",
+        ElemRangeName::Magnesium => "Mg:P",
     }
 }
+
+/// Название элемента в именительном падеже, со строчной буквы
+pub(crate) fn nutrient_name(element: ElemName) -> &'static str {
+    element.info().name
+}
+
+/// Строка с заглавной буквы
+pub(crate) fn capitalized(text: &str) -> String {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Соотношение вторичного элемента к фосфору
+pub(crate) fn secondary_ratio_title(element: ElemName) -> String {
+    format!("{}:P", element.symbol())
+}
+
+/// Название элемента в том виде, в котором его содержание указывается на упаковке удобрения,
+/// или в пересчёте на сам элемент (цифры формулы оксида - нижние индексы)
+#[function_component]
+pub(crate) fn NutrientTitle(props: &NutrientTitleProps) -> Html {
+    let mut chunks = Vec::new();
+    let mut rest = props.units.formula(props.element);
+    while !rest.is_empty() {
+        let is_digit = rest.starts_with(|c: char| c.is_ascii_digit());
+        let chunk_len = rest
+            .find(|c: char| c.is_ascii_digit() != is_digit)
+            .unwrap_or(rest.len());
+        let (chunk, remainder) = rest.split_at(chunk_len);
+        chunks.push(if is_digit {
+            html! {<sub>{chunk}</sub>}
+        } else {
+            html! {chunk}
+        });
+        rest = remainder;
+    }
+    html! {<>{for chunks}</>}
+}
+
+#[derive(PartialEq, Properties)]
+pub(crate) struct NutrientTitleProps {
+    pub element: ElemName,
+    #[prop_or_default]
+    pub units: NutrientUnits,
+}
@@ -1,15 +1,22 @@
 use yew::prelude::*;
 
+use crate::calculator::profiles::MixtureProfile;
 use crate::{ui_components::collapsible_section::CollapsibleSection, yew_utils::raw_html};
 
-/// Блок, с которого начинается калькулятор
+#[derive(Properties, PartialEq)]
+pub struct IntroProps {
+    pub profile: MixtureProfile,
+}
+
+/// Блок, с которого начинается калькулятор. Название смеси зависит от выбранного профиля.
 #[function_component]
-pub fn Intro() -> Html {
+pub fn Intro(props: &IntroProps) -> Html {
+    let mixture = props.profile.mixture_name();
     html! {
         <>
-            <h1>{"Калькулятор оптимальной смеси удобрений №2"}<br/>{" по методу Миттлайдера"}</h1>
+            <h1>{format!("Калькулятор оптимальной {}", mixture)}<br/>{" по методу Миттлайдера"}</h1>
             <CollapsibleSection description="Что это такое?">
-                {raw_html(
+                {raw_html(format!(
                     r##"
                     <div>
                     <p>
@@ -21,8 +28,8 @@ pub fn Intro() -> Html {
                         </a> &mdash; этот калькулятор вам поможет!
                     </p>
                     <p>
-                        С помощью этого калькулятора можно составить смесь №2 или №2а из имеющихся
-                        у вас комплексных и моно- удобрений так, чтобы выполнялись
+                        С помощью этого калькулятора можно подобрать из имеющихся у вас
+                        комплексных и моно- удобрений состав {mixture} так, чтобы выполнялись
                         <a href="/info/arts/element-conditions/">главные условия</a>
                         соотношения макроэлементов
                         (N&mdash;P<sub>2</sub>O<sub>5</sub>&mdash;K<sub>2</sub>O и
@@ -42,7 +49,8 @@ pub fn Intro() -> Html {
                         </a></span>
                     </p>
                     </div>
-                    "##)}
+                    "##
+                ))}
             </CollapsibleSection>
         </>
     }
@@ -5,9 +5,10 @@ use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
 use crate::calculator::consts::PERMANENT_FERTILIZERS;
-use crate::calculator::{Deficites, ElemName, Fertilizer};
+use crate::calculator::{Deficites, ElemName, Fertilizer, FertilizerMinimum};
 use crate::store::{AppStore, PermanentFertilizersState, StoreAction};
-use crate::ui_components::html_chunks::nutrient_css_class;
+use crate::ui_components::html_chunks::{capitalized, nutrient_css_class, NutrientTitle};
+use crate::ui_components::minimum_input::MinimumInput;
 use crate::ui_components::positive_float_input::PositiveFloatInput;
 use crate::yew_utils::include_css;
 
@@ -18,13 +19,29 @@ struct KnownFertRowProp {
     fertilizer: Fertilizer,
     selected: bool,
     limit: Option<f64>,
+    minimum: Option<FertilizerMinimum>,
+    price: Option<f64>,
+    show_price: bool,
     on_limit_change: Callback<(usize, Option<f64>)>,
+    on_minimum_change: Callback<(usize, Option<FertilizerMinimum>)>,
+    on_price_change: Callback<(usize, Option<f64>)>,
     on_toggle: Callback<(usize, bool)>,
 }
 
 #[function_component]
 fn KnownFertRow(
-    KnownFertRowProp { fertilizer, selected, limit, on_toggle, on_limit_change }: &KnownFertRowProp,
+    KnownFertRowProp {
+        fertilizer,
+        selected,
+        limit,
+        minimum,
+        price,
+        show_price,
+        on_toggle,
+        on_limit_change,
+        on_minimum_change,
+        on_price_change,
+    }: &KnownFertRowProp,
 ) -> Html {
     let checkbox_input_ref = use_node_ref();
     let fert_input_id = AttrValue::from(format!("perma-fert-checkbox-{}", fertilizer.id));
@@ -48,11 +65,27 @@ fn KnownFertRow(
         })
     };
 
-    let nutrient_column = |name: &str, element: ElemName| -> Html {
+    let on_minimum_change = {
+        let fert_id = fertilizer.id;
+        let on_minimum_change = on_minimum_change.clone();
+        Callback::from(move |new_value: Option<FertilizerMinimum>| {
+            on_minimum_change.emit((fert_id, new_value));
+        })
+    };
+
+    let on_price_change = {
+        let fert_id = fertilizer.id;
+        let on_price_change = on_price_change.clone();
+        Callback::from(move |new_value: Option<f64>| {
+            on_price_change.emit((fert_id, new_value));
+        })
+    };
+
+    let nutrient_column = |element: ElemName| -> Html {
         html! {
             <div class={classes!("col", nutrient_css_class(element))}>
                 <span class="fert-nutrient">
-                    { name }<br/><span>{fertilizer[element].to_string()}{"%"}</span>
+                    { capitalized(element.info().name) }<br/><span>{fertilizer[element].to_string()}{"%"}</span>
                 </span>
             </div>
         }
@@ -71,14 +104,18 @@ fn KnownFertRow(
                         <label for={fert_input_id} class="form-check-label fs-5">
                             { fertilizer.name.clone() }
                         </label>
+                        {for ElemName::secondary()
+                            .filter(|element| fertilizer[*element] > 0.0)
+                            .map(|element| html! {
+                                <small class="text-muted ms-2">
+                                    <NutrientTitle {element} />{" "}{fertilizer[element]}{"%"}
+                                </small>
+                            })}
                     </div>
                 </div>
             </div>
             <div class="row pb-1 ps-4">
-                {nutrient_column("Азот", ElemName::Nitrogen)}
-                {nutrient_column("Фосфор", ElemName::Phosphorus)}
-                {nutrient_column("Калий", ElemName::Potassium)}
-                {nutrient_column("Магний", ElemName::Magnesium)}
+                {for ElemName::MACRO.into_iter().map(nutrient_column)}
                 <div class="col g-0 fert-remainder">
                     <PositiveFloatInput
                         placeholder="∞ кг."
@@ -88,6 +125,23 @@ fn KnownFertRow(
                         required={false}
                         title="Сколько удобрения у вас осталось" />
                 </div>
+                <div class="col g-0 fert-minimum">
+                    <MinimumInput
+                        size="4"
+                        value={*minimum}
+                        on_change={on_minimum_change} />
+                </div>
+                if *show_price {
+                    <div class="col g-0 fert-price">
+                        <PositiveFloatInput
+                            placeholder="₽/кг"
+                            size="5"
+                            value={price}
+                            on_value_change={on_price_change}
+                            required={false}
+                            title="Цена килограмма удобрения" />
+                    </div>
+                }
             </div>
         </div>
     }
@@ -98,6 +152,8 @@ pub(crate) struct KnownFertilizersProps {
     pub store_dispatcher: UseReducerDispatcher<AppStore>,
     pub fertilizers_status: Rc<PermanentFertilizersState>,
     pub deficites: Rc<Deficites>,
+    /// Показывать ли поля для ввода цен удобрений
+    pub show_prices: bool,
 }
 
 /// Перечисляет все предопределённые (распространённые и часто используемые) удобрения,
@@ -120,15 +176,34 @@ pub(crate) fn known_fertilizers(props: &KnownFertilizersProps) -> Html {
         })
     };
 
+    let on_minimum_change = {
+        let store_dispatcher = props.store_dispatcher.clone();
+        Callback::from(move |(fert_id, fert_minimum)| {
+            store_dispatcher.dispatch(StoreAction::UpdatePermanentMinimum(fert_id, fert_minimum));
+        })
+    };
+
+    let on_price_change = {
+        let store_dispatcher = props.store_dispatcher.clone();
+        Callback::from(move |(fert_id, fert_price)| {
+            store_dispatcher.dispatch(StoreAction::UpdatePermanentPrice(fert_id, fert_price));
+        })
+    };
+
     let rendered_fertilizers = PERMANENT_FERTILIZERS.iter().map(|fertilizer| {
         html! {
             <KnownFertRow
                 key={fertilizer.id}
                 fertilizer={fertilizer.clone()}
                 limit={props.fertilizers_status.get_limit(fertilizer.id)}
+                minimum={props.fertilizers_status.get_minimum(fertilizer.id)}
+                price={props.fertilizers_status.get_price(fertilizer.id)}
+                show_price={props.show_prices}
                 selected={props.fertilizers_status.is_selected(fertilizer.id)}
                 on_toggle={&on_fertilizer_toggle}
-                on_limit_change={&on_limit_change} />
+                on_limit_change={&on_limit_change}
+                on_minimum_change={&on_minimum_change}
+                on_price_change={&on_price_change} />
         }
     });
 
@@ -145,7 +220,16 @@ pub(crate) fn known_fertilizers(props: &KnownFertilizersProps) -> Html {
                   укажите его остаток в поле "}
                 <span class="input-field-reference">{"∞ кг."}</span>
                 {", добавьте другое аналогичное удобрение в смесь, и калькулятор покроет нехватку \
-                  за счёт аналога."}
+                  за счёт аналога. Если же какое-то удобрение нужно израсходовать, укажите, \
+                  сколько его должно быть в смеси как минимум, в поле "}
+                <span class="input-field-reference">{"0 кг"}</span>
+                {" (кнопкой "}<span class="input-field-reference">{"кг"}</span>
+                {" можно переключиться на проценты от массы смеси)."}
+                if props.show_prices {
+                    {" Цену килограмма удобрения укажите в поле "}
+                    <span class="input-field-reference">{"₽/кг"}</span>
+                    {"; удобрения без цены будут считаться бесплатными."}
+                }
             </p>
             <div class={classes!(stylesheet, "p-1")}>
                 {for rendered_fertilizers}
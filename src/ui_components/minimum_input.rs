@@ -0,0 +1,76 @@
+use yew::prelude::*;
+
+use crate::calculator::FertilizerMinimum;
+use crate::ui_components::positive_float_input::PositiveFloatInput;
+
+#[derive(Properties, PartialEq)]
+pub(crate) struct MinimumInputProps {
+    pub value: Option<FertilizerMinimum>,
+    pub on_change: Callback<Option<FertilizerMinimum>>,
+    pub id: Option<AttrValue>,
+    pub size: Option<AttrValue>,
+}
+
+/// Поле для ввода минимального количества удобрения в смеси с переключателем единиц:
+/// килограммы или проценты от массы смеси.
+#[function_component(MinimumInput)]
+pub(crate) fn minimum_input(props: &MinimumInputProps) -> Html {
+    // Единицы нужно помнить и тогда, когда значение ещё не введено
+    let share_selected = use_state(|| matches!(props.value, Some(FertilizerMinimum::Share(_))));
+    let is_share = match props.value {
+        Some(FertilizerMinimum::Share(_)) => true,
+        Some(FertilizerMinimum::Mass(_)) => false,
+        None => *share_selected,
+    };
+
+    let make_minimum = move |value: f64| {
+        if is_share {
+            FertilizerMinimum::Share(value)
+        } else {
+            FertilizerMinimum::Mass(value)
+        }
+    };
+
+    let on_value_change = {
+        let on_change = props.on_change.clone();
+        Callback::from(move |new_value: Option<f64>| {
+            on_change.emit(new_value.map(make_minimum));
+        })
+    };
+
+    let on_unit_toggle = {
+        let on_change = props.on_change.clone();
+        let share_selected = share_selected.clone();
+        let value = props.value;
+        Callback::from(move |_| {
+            share_selected.set(!is_share);
+            if let Some(minimum) = value {
+                let toggled = if is_share {
+                    FertilizerMinimum::Mass(minimum.value())
+                } else {
+                    FertilizerMinimum::Share(minimum.value().min(100.0))
+                };
+                on_change.emit(Some(toggled));
+            }
+        })
+    };
+
+    html! {
+        <div class="input-group flex-nowrap">
+            <PositiveFloatInput
+                placeholder="0"
+                size={props.size.clone()}
+                id={props.id.clone()}
+                value={props.value.map(FertilizerMinimum::value)}
+                {on_value_change}
+                required={false}
+                enforce_max={is_share.then_some(100.0)}
+                title="Сколько удобрения смесь должна содержать как минимум" />
+            <button type="button" class="btn btn-outline-secondary"
+                title="Переключить единицы: килограммы или проценты от массы смеси"
+                onclick={on_unit_toggle}>
+                {if is_share { "%" } else { "кг" }}
+            </button>
+        </div>
+    }
+}
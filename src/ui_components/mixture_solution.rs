@@ -1,10 +1,34 @@
+use std::rc::Rc;
+
 use stylist::yew::styled_component;
 use yew::prelude::*;
 
-use crate::calculator::formatted_solution::{DynMicroFertInfo, FormattedSolution};
-use crate::ui_components::html_chunks::{PhosphorusOxide, PotassiumOxide, MDASH};
+use crate::calculator::alternatives::AlternativeSolution;
+use crate::calculator::consts::{
+    MIN_SIGNIFICANT_CHANGE, NITROGEN_SHARE_RELAXATION_STEP, RATIO_RELAXATION_STEP,
+};
+use crate::calculator::formatted_solution::{
+    BindingConstraint, DynMicroFertInfo, FormattedSolution,
+};
+use crate::calculator::max_batch::MaxBatch;
+use crate::calculator::nitrogen::NitrogenForm;
+use crate::calculator::profiles::MicroFertTable;
+use crate::calculator::query::{MixtureObjective, MixtureQuery, TaskConstraint};
+use crate::calculator::units::NutrientUnits;
+use crate::calculator::{ElemName, ElemRangeName, Fertilizer, FertilizerMinimum};
+use crate::ui_components::html_chunks::{
+    nutrient_css_class, ratio_title, secondary_ratio_title, NutrientTitle, CURRENCY, MDASH,
+};
+use crate::ui_components::rounded_recipe::RoundedRecipeSection;
+use crate::ui_components::season_plan::SeasonPlanSection;
+use crate::ui_components::sensitivity_report::SensitivityReport;
+use crate::ui_components::simpler_mixtures::SimplerMixtures;
+use crate::ui_components::units_switch::UnitsSwitch;
 use crate::yew_utils::{include_css, raw_html, FloatFormat};
 
+/// Рецепты, отличающиеся меньше, чем на эту долю, считаются равноценными
+const SAME_OBJECTIVE_TOLERANCE: f64 = 1e-6;
+
 const REFERENCES_HTML: &str = r##"
 <div class="help d-print-none">
     <h2>Источники и примечания</h2>
@@ -44,19 +68,75 @@ const REFERENCES_HTML: &str = r##"
 </div>
 "##;
 
+/// Название оптимизируемой величины и её единица измерения
+fn objective_quantity(objective: MixtureObjective) -> (&'static str, &'static str) {
+    match objective {
+        MixtureObjective::Mass => ("массу смеси", " кг"),
+        MixtureObjective::Cost => ("стоимость смеси", CURRENCY),
+        MixtureObjective::Ballast => ("балласт в смеси", " кг"),
+    }
+}
+
+fn objective_title(objective: MixtureObjective) -> &'static str {
+    match objective {
+        MixtureObjective::Mass => "самая концентрированная смесь",
+        MixtureObjective::Cost => "самая дешёвая смесь",
+        MixtureObjective::Ballast => "смесь с наименьшим количеством балласта",
+    }
+}
+
+/// Содержание макроэлементов в удобрении - ячейки строки таблицы рецепта
+fn content_cells(fertilizer: &Fertilizer, units: NutrientUnits) -> Html {
+    html! {
+        {for ElemName::MACRO.into_iter().map(|element| html! {
+            <td>{ FloatFormat::new(units.convert(element, fertilizer[element]), 2) }</td>
+        })}
+    }
+}
+
 fn render_main_ingredients(
     solution: &FormattedSolution,
+    objective: MixtureObjective,
+    display_units: UseStateHandle<NutrientUnits>,
     on_calc_another_callback: Callback<MouseEvent>,
 ) -> Html {
-    let component_rows = solution.components.iter().map(|(fertilizer, weight)| {
+    let units = *display_units;
+    let on_units_change = Callback::from(move |units| display_units.set(units));
+    let concentration = |element: ElemName| units.convert(element, solution.concentration[element]);
+    let ratio = |idx: usize| {
+        let (range_name, ratio): (ElemRangeName, f64) = solution.relation[idx];
+        units.convert_ratio(range_name.element(), ratio)
+    };
+    let (quantity_name, quantity_unit) = objective_quantity(objective);
+    let component_rows = solution
+        .components
+        .iter()
+        .zip(solution.forced_penalty.iter())
+        .map(|((fertilizer, weight), penalty)| {
+            let title = (*weight == 0.0 && *penalty > 0.0).then(|| {
+                format!(
+                    "Не вошло в смесь: каждый килограмм этого удобрения увеличил бы \
+                     {} на {}{}",
+                    quantity_name,
+                    FloatFormat::new(*penalty, 2),
+                    quantity_unit
+                )
+            });
+            html! {
+                <tr class={classes!((*weight == 0.0).then_some("fert-not-used"))} {title}>
+                    <td>{ &fertilizer.name }</td>
+                    <td>{ FloatFormat::new(*weight, 3) }</td>
+                    {content_cells(fertilizer, units)}
+                </tr>
+            }
+        });
+
+    let existing_row = solution.existing.map(|existing| {
         html! {
-            <tr class={classes!((*weight == 0.0).then_some("fert-not-used"))}>
-                <td>{ &fertilizer.name }</td>
-                <td>{ FloatFormat::new(*weight, 3) }</td>
-                <td>{ fertilizer.N.to_string() }</td>
-                <td>{ fertilizer.P.to_string() }</td>
-                <td>{ fertilizer.K.to_string() }</td>
-                <td>{ fertilizer.Mg.to_string() }</td>
+            <tr class="fert-existing">
+                <td>{"Уже смешанное"}</td>
+                <td>{ FloatFormat::new(existing.mass, 3) }</td>
+                {content_cells(&existing.as_fertilizer(), units)}
             </tr>
         }
     });
@@ -74,7 +154,12 @@ fn render_main_ingredients(
             <p>
                 { "Для приготовления " }
                 <strong>{ FloatFormat::new(solution.total_weight, 3) }{ " кг. " }</strong>
-                { " смеси №2 вам понадобится:" }
+                { solution.profile.mixture_name() }
+                if solution.existing.is_some() {
+                    { " добавьте к уже смешанному:" }
+                } else {
+                    { " вам понадобится:" }
+                }
             </p>
             <div class="row">
                 <div class="col-auto">
@@ -82,35 +167,65 @@ fn render_main_ingredients(
                         <tr>
                         <th>{ "Удобрение" }</th>
                         <th class="weight">{ "вес (кг)" }</th>
-                        <th class="nutrient-N">{ "N, %" }</th>
-                        <th class="nutrient-P"><span><PhosphorusOxide />{", %"}</span></th>
-                        <th class="nutrient-K"><span><PotassiumOxide />{", %"}</span></th>
-                        <th class="nutrient-Mg">{ "MgO, %" }</th>
+                        {for ElemName::MACRO.into_iter().map(|element| html! {
+                            <th class={nutrient_css_class(element)}>
+                                <span><NutrientTitle {element} {units} />{", %"}</span>
+                            </th>
+                        })}
                         </tr>
+                        {for existing_row}
                         {for component_rows}
                     </table>
                 </div>
 
                 <div id="about" class="col">
                     <h2>{ "Характеристика смеси" }</h2>
+                    <p class="d-print-none">
+                        <small class="text-muted">{"Содержание элементов в пересчёте на: "}</small>
+                        <UnitsSwitch value={units} on_change={on_units_change} />
+                    </p>
                     <p>
-                        <span>{"Концентрация N"}{MDASH}<PhosphorusOxide />{MDASH}
-                            <PotassiumOxide />{MDASH}{"MgO (%):"}</span>
+                        if solution.existing.is_some() {
+                            <small class="text-muted">{"(для всей смеси, вместе с уже смешанным)"}</small>
+                            <br/>
+                        }
+                        <span>{"Концентрация "}
+                            {for ElemName::MACRO.into_iter().enumerate().map(|(idx, element)| html! {
+                                <>
+                                    if idx > 0 {{MDASH}}
+                                    <NutrientTitle {element} {units} />
+                                </>
+                            })}
+                            {" (%):"}</span>
                         <br/>
                         {
                             raw_html(
                                 format!(
                                     "<strong>{:.2}&mdash;{:.2}&mdash;{:.2}&mdash;{:.2}</strong>",
-                                    solution.concentration.N,
-                                    solution.concentration.P,
-                                    solution.concentration.K,
-                                    solution.concentration.Mg))
+                                    concentration(ElemName::Nitrogen),
+                                    concentration(ElemName::Phosphorus),
+                                    concentration(ElemName::Potassium),
+                                    concentration(ElemName::Magnesium)))
                         }
                     </p>
-                    <p>{ format!("Соотношение N:P:K = {:.2}:1:{:.2}",
-                                 solution.relation[0].1, solution.relation[1].1) }</p>
-                    <p>{ format!("Соотношение P:Mg = 1:{:.2}",
-                                 solution.relation[2].1) }</p>
+                    <p>{ format!("Соотношение N:P:K = {:.2}:1:{:.2}", ratio(0), ratio(1)) }</p>
+                    <p>{ format!("Соотношение P:Mg = 1:{:.2}", ratio(2)) }</p>
+                    {render_target_deviation(solution, units)}
+                    {render_nitrogen_forms(solution)}
+                    {render_secondary_concentrations(solution, units)}
+                    if let Some(total_price) = solution.total_price {
+                        <p>
+                            {"Стоимость удобрений: "}
+                            <strong>{FloatFormat::new(total_price, 2)}{CURRENCY}</strong>
+                            if objective == MixtureObjective::Cost {
+                                <br/>
+                                <small class="text-muted">
+                                    {"(удобрения без указанной цены считаются бесплатными)"}
+                                </small>
+                            }
+                        </p>
+                    }
+                    <p class="text-muted">{"Подобрана "}{objective_title(objective)}</p>
                     <p class="d-print-none">
                         <a class="btn btn-secondary" href="#calculator"
                             onclick={on_calc_another_callback}>{ "Изменить состав" }
@@ -127,10 +242,225 @@ fn render_main_ingredients(
                 </ul>
             </div>
             }
+            {render_binding_constraints(solution, objective)}
         </>
     }
 }
 
+/// Отклонения от идеального соотношения меньше этого значения не показываются
+const TARGET_DEVIATION_DISPLAY_MIN: f64 = 0.005;
+
+/// Из чего состоит азот смеси: доли каждой формы (и азота неизвестных форм) во всём азоте
+fn render_nitrogen_forms(solution: &FormattedSolution) -> Html {
+    let nitrogen = solution.concentration[ElemName::Nitrogen];
+    if nitrogen <= 0.0 || solution.nitrogen_forms.total() <= 0.0 {
+        return html! {};
+    }
+    let share = |concentration: f64| FloatFormat::new(100.0 * concentration / nitrogen, 0);
+    let unknown = nitrogen - solution.nitrogen_forms.total();
+    let forms: Vec<NitrogenForm> = NitrogenForm::ALL
+        .into_iter()
+        .filter(|form| solution.nitrogen_forms[*form] > 0.0)
+        .collect();
+    html! {
+        <p>
+            {"Азот (% всего азота): "}
+            {for forms.iter().enumerate().map(|(idx, form)| html! {
+                <>
+                    if idx > 0 {{", "}}
+                    {form.title()}{" "}{share(solution.nitrogen_forms[*form])}
+                </>
+            })}
+            if unknown > MIN_SIGNIFICANT_CHANGE {
+                {", неизвестной формы "}{share(unknown)}
+            }
+        </p>
+    }
+}
+
+/// Концентрации вторичных элементов, которые действительно есть в смеси
+fn render_secondary_concentrations(solution: &FormattedSolution, units: NutrientUnits) -> Html {
+    let present: Vec<ElemName> = ElemName::secondary()
+        .filter(|element| solution.concentration[*element] > 0.0)
+        .collect();
+    if present.is_empty() {
+        return html! {};
+    }
+    html! {
+        <p>
+            {"Вторичные элементы (%): "}
+            {for present.iter().enumerate().map(|(idx, element)| html! {
+                <>
+                    if idx > 0 {{", "}}
+                    <NutrientTitle element={*element} {units} />
+                    {format!(" {:.2}", units.convert(*element, solution.concentration[*element]))}
+                </>
+            })}
+        </p>
+    }
+}
+
+/// Показывает, насколько соотношения смеси отличаются от идеальных, если они заданы
+fn render_target_deviation(solution: &FormattedSolution, units: NutrientUnits) -> Html {
+    let Some(target_deviation) = solution.target_deviation else {
+        return html! {};
+    };
+    let significant = target_deviation
+        .iter()
+        .filter(|(_, deviation)| deviation.abs() >= TARGET_DEVIATION_DISPLAY_MIN)
+        .map(|(range_name, deviation)| {
+            let deviation = units.convert_ratio(range_name.element(), *deviation);
+            html! {
+                <li>
+                    {"Соотношение "}{ratio_title(*range_name)}{": "}
+                    {if deviation > 0.0 { "выше" } else { "ниже" }}
+                    {" идеального на "}{FloatFormat::new(deviation.abs(), 2)}
+                </li>
+            }
+        })
+        .collect::<Vec<_>>();
+    if significant.is_empty() {
+        html! { <p>{"Идеальное соотношение достигнуто"}</p> }
+    } else {
+        html! {
+            <>
+                <p>{"Точно достичь идеального соотношения из этих удобрений нельзя, \
+                     подобрана наиболее близкая к нему смесь:"}</p>
+                <ul>{for significant}</ul>
+            </>
+        }
+    }
+}
+
+/// Перечисляет ограничения, из-за которых смесь не может быть лучше (концентрированнее,
+/// дешевле или чище), и насколько её можно было бы улучшить, ослабив каждое из них.
+fn render_binding_constraints(solution: &FormattedSolution, objective: MixtureObjective) -> Html {
+    let (quantity_name, quantity_unit) = objective_quantity(objective);
+    let binding_rows =
+        solution
+            .bindings
+            .iter()
+            .map(|BindingConstraint { constraint, value, saving }| {
+                let description = match constraint {
+                    TaskConstraint::RatioFrom(range_name) => format!(
+                        "Нижняя граница соотношения {} ({}): если снизить её на {}",
+                        ratio_title(*range_name),
+                        FloatFormat::new(*value, 3),
+                        FloatFormat::new(RATIO_RELAXATION_STEP, 2),
+                    ),
+                    TaskConstraint::RatioTo(range_name) => format!(
+                        "Верхняя граница соотношения {} ({}): если поднять её на {}",
+                        ratio_title(*range_name),
+                        FloatFormat::new(*value, 3),
+                        FloatFormat::new(RATIO_RELAXATION_STEP, 2),
+                    ),
+                    TaskConstraint::SecondaryFrom(element) => format!(
+                        "Нижняя граница соотношения {} ({}): если снизить её на {}",
+                        secondary_ratio_title(*element),
+                        FloatFormat::new(*value, 3),
+                        FloatFormat::new(RATIO_RELAXATION_STEP, 2),
+                    ),
+                    TaskConstraint::SecondaryTo(element) => format!(
+                        "Верхняя граница соотношения {} ({}): если поднять её на {}",
+                        secondary_ratio_title(*element),
+                        FloatFormat::new(*value, 3),
+                        FloatFormat::new(RATIO_RELAXATION_STEP, 2),
+                    ),
+                    TaskConstraint::Limit(fert_idx) => format!(
+                        "Остаток удобрения \"{}\" ({} кг): если его будет на 1 кг больше",
+                        solution.components[*fert_idx].0.name,
+                        FloatFormat::new(*value, 3),
+                    ),
+                    TaskConstraint::Minimum(fert_idx) => {
+                        let fertilizer = &solution.components[*fert_idx].0;
+                        let unit = match fertilizer.minimum {
+                            Some(FertilizerMinimum::Share(_)) => "%",
+                            _ => " кг",
+                        };
+                        format!(
+                            "Минимум удобрения \"{}\" ({}{}): если снизить его на 1{}",
+                            fertilizer.name,
+                            FloatFormat::new(*value, 3),
+                            unit,
+                            unit,
+                        )
+                    }
+                    TaskConstraint::NitrogenShare(form) => format!(
+                        "Предельная доля {} азота ({}%): если поднять её на {}%",
+                        form.genitive_title(),
+                        FloatFormat::new(*value, 3),
+                        FloatFormat::new(NITROGEN_SHARE_RELAXATION_STEP, 2),
+                    ),
+                    TaskConstraint::Existing
+                    | TaskConstraint::Phosphorus
+                    | TaskConstraint::Target(_) => return html! {},
+                };
+                html! {
+                    <li>
+                        {description}{", "}{quantity_name}{" уменьшится на "}
+                        <strong>{FloatFormat::new(*saving, 3)}{quantity_unit}</strong>
+                    </li>
+                }
+            });
+
+    html! {
+        if !solution.bindings.is_empty() {
+            <div class="d-print-none">
+                <h3>{ "Что мешает улучшить смесь" }</h3>
+                <p class="help">
+                    {"При том же количестве питательных веществ "}{MDASH}{" то есть при той же \
+                      пользе для растений:"}
+                </p>
+                <ul>
+                    {for binding_rows}
+                </ul>
+            </div>
+        }
+    }
+}
+
+/// Сколько смеси можно приготовить из остатков удобрений и какое из них кончится первым,
+/// с предложением пересчитать смесь на эту массу
+fn render_max_batch(
+    query: &MixtureQuery,
+    max_batch: Option<&MaxBatch>,
+    on_mass_change: &Callback<f64>,
+) -> Html {
+    let Some(max_batch) = max_batch else {
+        return html! {};
+    };
+    let on_use_max_batch = {
+        let on_mass_change = on_mass_change.clone();
+        let mass = (max_batch.mass * 1000.0).floor() / 1000.0;
+        Callback::from(move |_| on_mass_change.emit(mass))
+    };
+    let first_exhausted = max_batch
+        .exhausted
+        .first()
+        .map(|(fert_idx, _)| &query.fertilizers[*fert_idx].name);
+    let can_make_more = max_batch.mass - query.mass > MIN_SIGNIFICANT_CHANGE;
+    html! {
+        <div class="d-print-none">
+            <p>
+                {"Из имеющихся остатков удобрений можно приготовить не больше "}
+                <strong>{FloatFormat::new(max_batch.mass, 3)}{" кг"}</strong>
+                {" смеси"}
+                if let Some(name) = first_exhausted {
+                    {", первым закончится удобрение \""}{name}{"\""}
+                }
+                {"."}
+                if can_make_more {
+                    {" "}
+                    <button type="button" class="btn btn-sm btn-outline-secondary"
+                        onclick={on_use_max_batch}>
+                        {"Рассчитать на "}{FloatFormat::new(max_batch.mass, 3)}{" кг"}
+                    </button>
+                }
+            </p>
+        </div>
+    }
+}
+
 fn microfert_recipe_column(
     microfert_recipe: &[DynMicroFertInfo],
     elem_name: &str,
@@ -188,9 +518,9 @@ fn render_micro_ingredients(solution: &FormattedSolution) -> Html {
         }
     };
 
-    html! {
-        <div class="row microferts">
-            <div class="col">
+    let table_title = |table: MicroFertTable| -> Html {
+        match table {
+            MicroFertTable::Ugarova2 => html! {
                 <h3>
                     {"Для культур на грядах"}<br />
                     <small class="text-muted">
@@ -201,10 +531,8 @@ fn render_micro_ingredients(solution: &FormattedSolution) -> Html {
                         {")"}
                     </small>
                 </h3>
-                {generate_microfert_table(&solution.microferts)}
-            </div>
-
-            <div class="col">
+            },
+            MicroFertTable::Ugarova2a => html! {
                 <h3 class="seedling">
                     {"Для рассады"}<br/>
                     <small class="text-muted">
@@ -212,10 +540,8 @@ fn render_micro_ingredients(solution: &FormattedSolution) -> Html {
                         <sup><a href="#ref2">{"2"}</a></sup>
                     </small>
                 </h3>
-                {generate_microfert_table(&solution.microferts_2a)}
-            </div>
-
-            <div class="col">
+            },
+            MicroFertTable::Mittleider => html! {
                 <h3 class="seedling">
                     {"По Митлайдеру "}
                     <small class="text-muted">
@@ -223,8 +549,18 @@ fn render_micro_ingredients(solution: &FormattedSolution) -> Html {
                         <a href="#ref3">{"3"}</a>
                     </small>
                 </h3>
-                {generate_microfert_table(&solution.microferts_mit)}
-            </div>
+            },
+        }
+    };
+
+    html! {
+        <div class="row microferts">
+            {for solution.microferts.iter().map(|(table, microferts)| html! {
+                <div class="col">
+                    {table_title(*table)}
+                    {generate_microfert_table(microferts)}
+                </div>
+            })}
         </div>
     }
 }
@@ -267,61 +603,117 @@ fn render_application_tips(solution: &FormattedSolution) -> Html {
 
     html! {
         <>
-            <h3>{ "Для подкормок на грядах" }</h3>
-            <table>
-            <thead>
-                <tr>
-                    <th rowspan="2">
-                        {"Тип почвы"}</th><th colspan="2">{"Доза внесения "}<br/>
-                        {"(\"грамм/погонный метр)\""}
-                    </th>
-                </tr>
-                <tr><th>{"По Угаровой"}</th><th>{"По Миттлайдеру"}</th></tr>
-            </thead>
-            <tbody>
-                {for ground_dozes_rows}
-            </tbody>
-            </table>
-
-            {raw_html(r##"<p class="help"><span>*</span> Максимальную дозу
-                следует вносить в тёплое и солнечное лето в период быстрого
-                роста растений и плодообразования!<br/>Минимальную же,
-                наоборот - в холодное и пасмурное лето,
-                при этом можно увеличить общее число подкормок на одну-две.</p>"##)}
-
-            <h3>
-                {"Для полива рассады"}<br />
-                <small class="text-muted">
-                {"(используйте только смеси со всеми микроэлементами, \
-                    как \"2а\" по Угаровой или по Миттлайдеру)" }
-                </small>
-            </h3>
-            <table>
-            <thead>
-                <tr>
-                    <th rowspan="2">{ "Объём воды" }</th>
-                    <th colspan="2">{ "Смесь с микроэлементами" }</th>
-                </tr>
-                <tr><th>{ "по Угаровой" }</th><th>{ "по Митлайдеру" }</th></tr>
-            </thead>
-            <tbody>
-            {for seedling_dozes_rows}
-            </tbody>
-            </table>
+            if !solution.ground_dozes.is_empty() {
+                <h3>
+                    {"Для подкормок на грядах"}<br />
+                    <small class="text-muted">{"("}{solution.crop.title()}{")"}</small>
+                </h3>
+                <table>
+                <thead>
+                    <tr>
+                        <th rowspan="2">
+                            {"Тип почвы"}</th><th colspan="2">{"Доза внесения "}<br/>
+                            {"(\"грамм/погонный метр)\""}
+                        </th>
+                    </tr>
+                    <tr><th>{"По Угаровой"}</th><th>{"По Миттлайдеру"}</th></tr>
+                </thead>
+                <tbody>
+                    {for ground_dozes_rows}
+                </tbody>
+                </table>
+
+                {raw_html(r##"<p class="help"><span>*</span> Максимальную дозу
+                    следует вносить в тёплое и солнечное лето в период быстрого
+                    роста растений и плодообразования!<br/>Минимальную же,
+                    наоборот - в холодное и пасмурное лето,
+                    при этом можно увеличить общее число подкормок на одну-две.</p>"##)}
+            }
+
+            if !solution.seedling_dozes.is_empty() {
+                <h3>
+                    {"Для полива рассады"}<br />
+                    <small class="text-muted">
+                    {"(используйте только смеси со всеми микроэлементами, \
+                        как \"2а\" по Угаровой или по Миттлайдеру)" }
+                    </small>
+                </h3>
+                <table>
+                <thead>
+                    <tr>
+                        <th rowspan="2">{ "Объём воды" }</th>
+                        <th colspan="2">{ "Смесь с микроэлементами" }</th>
+                    </tr>
+                    <tr><th>{ "по Угаровой" }</th><th>{ "по Митлайдеру" }</th></tr>
+                </thead>
+                <tbody>
+                {for seedling_dozes_rows}
+                </tbody>
+                </table>
+            }
         </>
     }
 }
 
+/// Переключатель между равноценными (или почти равноценными) рецептами
+fn render_alternatives_switch(
+    alternatives: &[AlternativeSolution],
+    selected_idx: usize,
+    selected: UseStateHandle<usize>,
+) -> Html {
+    if alternatives.len() < 2 {
+        return html! {};
+    }
+    let on_prev_click = {
+        let selected = selected.clone();
+        Callback::from(move |_| selected.set(selected_idx - 1))
+    };
+    let on_next_click = Callback::from(move |_| selected.set(selected_idx + 1));
+    let worsening = alternatives[selected_idx].worsening;
+
+    html! {
+        <div class="alternatives d-print-none">
+            <p class="help">
+                {"Смесь можно составить и по-другому. Выберите рецепт из тех удобрений, \
+                  которых у вас больше."}
+            </p>
+            <p>
+                <span class="btn-group btn-group-sm">
+                    <button class="btn btn-outline-secondary"
+                        disabled={selected_idx == 0} onclick={on_prev_click}>
+                        {"←"}
+                    </button>
+                    <button class="btn btn-outline-secondary"
+                        disabled={selected_idx + 1 == alternatives.len()} onclick={on_next_click}>
+                        {"→"}
+                    </button>
+                </span>
+                {" Вариант "}{selected_idx + 1}{" из "}{alternatives.len()}
+                if selected_idx > 0 {
+                    if worsening < SAME_OBJECTIVE_TOLERANCE {
+                        {" (не хуже основного)"}
+                    } else {
+                        {" (хуже основного на "}{FloatFormat::new(worsening * 100.0, 1)}{"%)"}
+                    }
+                }
+            </p>
+        </div>
+    }
+}
+
 #[derive(Debug, PartialEq, Properties)]
 pub(crate) struct MixtureSolutionProps {
     pub on_calc_another: Callback<()>,
+    /// Пересчитать смесь для другой массы
+    pub on_mass_change: Callback<f64>,
     pub solution: FormattedSolution,
+    pub query: Rc<MixtureQuery>,
 }
 
 /// Отвечает за вывод всей информации о вычисленной смеси, включая и её состав и технику применения.
 #[styled_component]
 pub(crate) fn MixtureSolution(
-    MixtureSolutionProps { solution, on_calc_another }: &MixtureSolutionProps,
+    MixtureSolutionProps { solution, on_calc_another, on_mass_change, query }: &MixtureSolutionProps,
 ) -> Html {
     let stylesheet = include_css!("mixture_solution.css");
 
@@ -342,12 +734,46 @@ pub(crate) fn MixtureSolution(
         })
     };
 
+    let alternatives = use_memo(|query| query.find_alternative_solutions(), query.clone());
+    // Наибольшая партия ищется с жёсткими диапазонами, так что для идеальных соотношений
+    // она не имеет смысла
+    let max_batch = use_memo(
+        |query| {
+            query
+                .target
+                .is_none()
+                .then(|| query.find_max_batch().ok())
+                .flatten()
+        },
+        query.clone(),
+    );
+    let selected = use_state(|| 0);
+    // Единицы вывода содержания элементов; на рецепт и ссылку они не влияют
+    let display_units = use_state(NutrientUnits::default);
+    let selected_idx = (*selected).min(alternatives.len().saturating_sub(1));
+    let solution = alternatives
+        .get(selected_idx)
+        .map_or(solution, |alternative| &alternative.solution);
+
+    let exact_weights: Vec<f64> = solution
+        .components
+        .iter()
+        .map(|(_, weight)| *weight)
+        .collect();
+
     html! {
         <div class={stylesheet}>
-            <h1>{ "Вариант смеси №2 по методу Митлайдера" }</h1>
+            <h1>{ solution.profile.heading() }</h1>
 
             <h2>{"Шаг 1. Смешайте основные удобрения"}</h2>
-            {render_main_ingredients(solution, on_calc_another_click.clone())}
+            {render_alternatives_switch(&alternatives, selected_idx, selected)}
+            {render_main_ingredients(
+                solution, query.objective, display_units, on_calc_another_click.clone()
+            )}
+            {render_max_batch(query, max_batch.as_ref().as_ref(), on_mass_change)}
+            <RoundedRecipeSection query={query.clone()} exact_weights={exact_weights.clone()} />
+            <SimplerMixtures query={query.clone()} exact_weights={exact_weights.clone()} />
+            <SensitivityReport query={query.clone()} />
 
             <div class="recommended">
                 <h2>
@@ -359,6 +785,16 @@ pub(crate) fn MixtureSolution(
                 <h2>{"Шаг 3. Используйте смесь"}</h2>
                 {render_application_tips(solution)}
 
+                if !solution.ground_dozes.is_empty() {
+                    <h2>{"Шаг 4. Составьте план подкормок на сезон"}</h2>
+                    <SeasonPlanSection
+                        query={query.clone()}
+                        {exact_weights}
+                        ground_dozes={solution.ground_dozes.clone()}
+                        mit_ground_doze={solution.mit_ground_doze}
+                        title={format!("{} ({})", solution.profile.heading(), solution.crop.title())} />
+                }
+
                 <p class="d-print-none">
                     <a class="btn btn-secondary" href="#calculator"
                         onclick={&on_calc_another_click}>{ "Рассчитать другой вариант смеси" }
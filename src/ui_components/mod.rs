@@ -1,11 +1,20 @@
 pub(crate) mod added_fertilizers;
 pub(crate) mod collapsible_section;
+pub(crate) mod crop_selector;
 pub(crate) mod deficite_description;
 pub(crate) mod elem_ranges;
 pub(crate) mod html_chunks;
 pub(crate) mod intro;
 pub(crate) mod known_fertilizers;
+pub(crate) mod minimum_input;
 pub(crate) mod mixture_solution;
+pub(crate) mod objective_selector;
 pub(crate) mod positive_float_input;
+pub(crate) mod profile_selector;
+pub(crate) mod rounded_recipe;
+pub(crate) mod season_plan;
+pub(crate) mod sensitivity_report;
+pub(crate) mod simpler_mixtures;
 pub(crate) mod status_bar;
 pub(crate) mod total_mass;
+pub(crate) mod units_switch;
@@ -0,0 +1,161 @@
+use yew::prelude::*;
+
+use crate::calculator::consts::MIN_INGREDIENTS;
+use crate::calculator::nitrogen::{NitrogenForm, NitrogenFormLimit};
+use crate::calculator::query::MixtureObjective;
+use crate::ui_components::positive_float_input::PositiveFloatInput;
+use crate::yew_utils::make_element_id;
+
+#[derive(Properties, PartialEq)]
+pub(crate) struct ObjectiveSelectorProps {
+    pub value: MixtureObjective,
+    pub on_change: Callback<MixtureObjective>,
+    pub max_ingredients: Option<usize>,
+    pub on_max_ingredients_change: Callback<Option<usize>>,
+    pub nitrogen_form_limit: Option<NitrogenFormLimit>,
+    pub on_nitrogen_form_limit_change: Callback<Option<NitrogenFormLimit>>,
+}
+
+/// Выбор того, какую из возможных смесей считать лучшей: самую концентрированную,
+/// самую дешёвую или содержащую меньше всего балласта. Здесь же задаются дополнительные
+/// условия: наибольшее число удобрений и предельная доля аммонийного или амидного азота.
+#[function_component(ObjectiveSelector)]
+pub(crate) fn objective_selector(props: &ObjectiveSelectorProps) -> Html {
+    let group_name = use_memo(make_element_id("objective"), ());
+    let max_ingredients_id = use_memo(make_element_id("max-ingredients"), ());
+    let nitrogen_form_group = use_memo(make_element_id("nitrogen-form"), ());
+    let nitrogen_share_id = use_memo(make_element_id("nitrogen-share"), ());
+    // Форма азота выбирается и тогда, когда предел ещё не задан
+    let nitrogen_form = use_state(|| {
+        props
+            .nitrogen_form_limit
+            .map_or(NitrogenForm::Ammonium, |limit| limit.form)
+    });
+
+    let on_max_ingredients_input = use_callback(
+        |value: Option<f64>, on_change| on_change.emit(value.map(|value| value.round() as usize)),
+        props.on_max_ingredients_change.clone(),
+    );
+
+    let on_nitrogen_share_input = {
+        let on_change = props.on_nitrogen_form_limit_change.clone();
+        let form = *nitrogen_form;
+        Callback::from(move |value: Option<f64>| {
+            on_change.emit(value.map(|max_share| NitrogenFormLimit { form, max_share }))
+        })
+    };
+
+    let nitrogen_form_option = |form: NitrogenForm| -> Html {
+        let input_id = format!("{}-{:?}", nitrogen_form_group, form);
+        let onchange = {
+            let nitrogen_form = nitrogen_form.clone();
+            let on_change = props.on_nitrogen_form_limit_change.clone();
+            let limit = props.nitrogen_form_limit;
+            Callback::from(move |_| {
+                nitrogen_form.set(form);
+                if let Some(limit) = limit {
+                    on_change.emit(Some(NitrogenFormLimit { form, ..limit }));
+                }
+            })
+        };
+        html! {
+            <div class="form-check form-check-inline">
+                <input type="radio" class="form-check-input"
+                    name={(*nitrogen_form_group).clone()}
+                    id={input_id.clone()}
+                    checked={*nitrogen_form == form}
+                    {onchange} />
+                <label class="form-check-label" for={input_id}>{form.genitive_title()}</label>
+            </div>
+        }
+    };
+
+    let objective_option = |objective: MixtureObjective, title: &str, help: &str| -> Html {
+        let input_id = format!("{}-{:?}", group_name, objective);
+        let onchange = {
+            let on_change = props.on_change.clone();
+            Callback::from(move |_| on_change.emit(objective))
+        };
+        html! {
+            <div class="form-check">
+                <input type="radio" class="form-check-input"
+                    name={(*group_name).clone()}
+                    id={input_id.clone()}
+                    checked={props.value == objective}
+                    {onchange} />
+                <label class="form-check-label" for={input_id}>
+                    {title}{" "}<small class="text-muted">{help}</small>
+                </label>
+            </div>
+        }
+    };
+
+    html! {
+        <>
+            <h2>{"Шаг 4. Какую смесь подобрать?"}</h2>
+            <div class="mb-3">
+                {objective_option(
+                    MixtureObjective::Mass,
+                    "Самую концентрированную",
+                    "(её понадобится вносить меньше всего)")}
+                {objective_option(
+                    MixtureObjective::Cost,
+                    "Самую дешёвую",
+                    "(укажите цены удобрений в шаге 1)")}
+                {objective_option(
+                    MixtureObjective::Ballast,
+                    "С наименьшим количеством балласта",
+                    "(меньше всего веществ, не являющихся элементами питания)")}
+            </div>
+            <div class="row mx-auto mb-3">
+                <div class="col-auto g-0">
+                    <label for={(*max_ingredients_id).clone()} class="col-form-label">
+                        {"Использовать не больше"}
+                    </label>
+                </div>
+                <div class="col-auto">
+                    <div class="input-group">
+                        <PositiveFloatInput
+                            size="3"
+                            id={(*max_ingredients_id).clone()}
+                            placeholder="∞"
+                            value={props.max_ingredients.map(|value| value as f64)}
+                            on_value_change={on_max_ingredients_input}
+                            enforce_min={Some(MIN_INGREDIENTS as f64)}
+                            required={false}
+                            title="Чем меньше удобрений, тем проще готовить смесь, \
+                                   но тем хуже она может получиться" />
+                        <span class="input-group-text">{"удобрений"}</span>
+                    </div>
+                </div>
+            </div>
+            <div class="row mx-auto mb-5 align-items-center">
+                <div class="col-auto g-0">{"Доля"}</div>
+                <div class="col-auto">
+                    {nitrogen_form_option(NitrogenForm::Ammonium)}
+                    {nitrogen_form_option(NitrogenForm::Urea)}
+                </div>
+                <div class="col-auto g-0">
+                    <label for={(*nitrogen_share_id).clone()} class="col-form-label">
+                        {"азота не больше"}
+                    </label>
+                </div>
+                <div class="col-auto">
+                    <div class="input-group">
+                        <PositiveFloatInput
+                            size="3"
+                            id={(*nitrogen_share_id).clone()}
+                            placeholder="100"
+                            value={props.nitrogen_form_limit.map(|limit| limit.max_share)}
+                            on_value_change={on_nitrogen_share_input}
+                            enforce_max={Some(100.0)}
+                            required={false}
+                            title="Считается только азот удобрений, формы азота которых известны. \
+                                   Для полива рассады аммонийного азота лучше не больше половины" />
+                        <span class="input-group-text">{"%"}</span>
+                    </div>
+                </div>
+            </div>
+        </>
+    }
+}
@@ -0,0 +1,41 @@
+use yew::prelude::*;
+
+use crate::calculator::profiles::MixtureProfile;
+use crate::yew_utils::make_element_id;
+
+#[derive(Properties, PartialEq)]
+pub(crate) struct ProfileSelectorProps {
+    pub value: MixtureProfile,
+    pub on_change: Callback<MixtureProfile>,
+}
+
+/// Выбор назначения смеси. Профиль подставляет свои соотношения элементов
+/// и определяет, какие таблицы микроудобрений и доз будут показаны для готовой смеси.
+#[function_component(ProfileSelector)]
+pub(crate) fn profile_selector(props: &ProfileSelectorProps) -> Html {
+    let group_name = use_memo(make_element_id("profile"), ());
+
+    let profile_option = |profile: MixtureProfile| -> Html {
+        let input_id = format!("{}-{:?}", group_name, profile);
+        let onchange = {
+            let on_change = props.on_change.clone();
+            Callback::from(move |_| on_change.emit(profile))
+        };
+        html! {
+            <div class="form-check form-check-inline">
+                <input type="radio" class="form-check-input"
+                    name={(*group_name).clone()}
+                    id={input_id.clone()}
+                    checked={props.value == profile}
+                    {onchange} />
+                <label class="form-check-label" for={input_id}>{profile.title()}</label>
+            </div>
+        }
+    };
+
+    html! {
+        <div class="mb-3">
+            {for MixtureProfile::ALL.into_iter().map(profile_option)}
+        </div>
+    }
+}
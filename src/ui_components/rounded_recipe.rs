@@ -0,0 +1,136 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::calculator::consts::ROUNDING_STEPS;
+use crate::calculator::query::MixtureQuery;
+use crate::calculator::rounding::{round_recipe, RoundedRecipe};
+use crate::ui_components::html_chunks::ratio_title;
+use crate::yew_utils::FloatFormat;
+
+#[derive(PartialEq, Properties)]
+pub(crate) struct RoundedRecipeProps {
+    pub query: Rc<MixtureQuery>,
+    /// Веса удобрений точного рецепта (кг), в порядке удобрений запроса
+    pub exact_weights: Vec<f64>,
+}
+
+fn render_rounded_table(
+    query: &MixtureQuery,
+    exact_weights: &[f64],
+    rounded: &RoundedRecipe,
+) -> Html {
+    let rows = query
+        .fertilizers
+        .iter()
+        .zip(exact_weights.iter().zip(rounded.weights.iter()))
+        .filter(|(_, (exact, rounded))| **exact > 0.0 || **rounded > 0.0)
+        .map(|(fertilizer, (exact, rounded))| {
+            html! {
+                <tr>
+                    <td>{&fertilizer.name}</td>
+                    <td class="fert-not-used">{FloatFormat::new(*exact, 3)}</td>
+                    <td><strong>{FloatFormat::new(*rounded, 3)}</strong></td>
+                </tr>
+            }
+        });
+    let existing_row = query.existing.map(|existing| {
+        html! {
+            <tr>
+                <td>{"Уже смешанное"}</td>
+                <td class="fert-not-used">{FloatFormat::new(existing.mass, 3)}</td>
+                <td><strong>{FloatFormat::new(existing.mass, 3)}</strong></td>
+            </tr>
+        }
+    });
+    let existing_mass = query.existing.map_or(0.0, |existing| existing.mass);
+    let deviations = rounded.relation.iter().zip(rounded.deviation.iter()).map(
+        |((range_name, value), (_, deviation))| {
+            html! {
+                <li>
+                    {ratio_title(*range_name)}{" = "}{FloatFormat::new(*value, 3)}
+                    {" ("}
+                    if *deviation >= 0.0 {{"+"}}
+                    {FloatFormat::new(*deviation, 3)}{")"}
+                </li>
+            }
+        },
+    );
+
+    html! {
+        <div class="row">
+            <div class="col-auto">
+                <table class="table resulting-mixture">
+                    <tr>
+                        <th>{"Удобрение"}</th>
+                        <th>{"точно (кг)"}</th>
+                        <th class="weight">{"округлённо (кг)"}</th>
+                    </tr>
+                    {for existing_row}
+                    {for rows}
+                    <tr>
+                        <td>{"Всего"}</td>
+                        <td class="fert-not-used">
+                            {FloatFormat::new(exact_weights.iter().sum::<f64>() + existing_mass, 3)}
+                        </td>
+                        <td><strong>{FloatFormat::new(rounded.total_weight, 3)}</strong></td>
+                    </tr>
+                </table>
+            </div>
+            <div class="col-auto">
+                <p>{"Соотношения элементов после округления (и их изменение):"}</p>
+                <ul>{for deviations}</ul>
+            </div>
+        </div>
+    }
+}
+
+/// Округляет рецепт до выбранного пользователем шага, чтобы веса удобрений можно было
+/// реально отмерить, и показывает точный и округлённый рецепты рядом.
+#[function_component(RoundedRecipeSection)]
+pub(crate) fn rounded_recipe_section(
+    RoundedRecipeProps { query, exact_weights }: &RoundedRecipeProps,
+) -> Html {
+    let step = use_state(|| None::<f64>);
+    let rounded = use_memo(
+        |(query, exact_weights, step)| step.map(|step| round_recipe(query, exact_weights, step)),
+        (query.clone(), exact_weights.clone(), *step),
+    );
+
+    let step_buttons = ROUNDING_STEPS.iter().map(|(step_value, title)| {
+        let is_active = *step == Some(*step_value);
+        let onclick = {
+            let step = step.clone();
+            let step_value = *step_value;
+            Callback::from(move |_| {
+                step.set(if is_active { None } else { Some(step_value) });
+            })
+        };
+        html! {
+            <button
+                class={classes!("btn", "btn-outline-secondary", is_active.then_some("active"))}
+                {onclick}>
+                {title}
+            </button>
+        }
+    });
+
+    html! {
+        <>
+            <p class="d-print-none">
+                {"Округлить веса до: "}
+                <span class="btn-group btn-group-sm">{for step_buttons}</span>
+            </p>
+            {match &*rounded {
+                None => html! {},
+                Some(Ok(rounded)) => render_rounded_table(query, exact_weights, rounded),
+                Some(Err(_)) => html! {
+                    <p class="warning">
+                        {"С таким шагом не удаётся подобрать веса, сохраняющие соотношения \
+                          элементов в заданных пределах. Попробуйте шаг поменьше."}
+                    </p>
+                },
+            }}
+        </>
+    }
+}
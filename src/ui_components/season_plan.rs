@@ -0,0 +1,222 @@
+use std::rc::Rc;
+
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::calculator::consts::{DEFAULT_BED_LENGTH, DEFAULT_FEEDINGS, ESTABLISHMENT_FEEDINGS};
+use crate::calculator::query::MixtureQuery;
+use crate::calculator::schedule::{CivilDate, SeasonPlan, SeasonPlanParams, UtcDateTime};
+use crate::calculator::ElemRange;
+use crate::ui_components::positive_float_input::PositiveFloatInput;
+use crate::yew_utils::{make_element_id, FloatFormat};
+
+#[derive(PartialEq, Properties)]
+pub(crate) struct SeasonPlanProps {
+    pub query: Rc<MixtureQuery>,
+    /// Веса удобрений точного рецепта (кг), в порядке удобрений запроса
+    pub exact_weights: Vec<f64>,
+    /// Дозы внесения по Угаровой для разных почв (г на погонный метр)
+    pub ground_dozes: Vec<(&'static str, ElemRange)>,
+    /// Доза внесения по Миттлайдеру (г на погонный метр)
+    pub mit_ground_doze: f64,
+    /// Название смеси для напоминаний в календаре
+    pub title: AttrValue,
+}
+
+/// Ссылка `data:`, по которой браузер скачает календарь. Все байты, кроме латинских
+/// букв, цифр и `-_.~`, кодируются как `%XX`.
+fn calendar_data_url(calendar: &str) -> String {
+    let mut url = String::from("data:text/calendar;charset=utf-8,");
+    for byte in calendar.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    url
+}
+
+/// План подкормок на сезон: по дате посадки, длине гряд и числу подкормок показывает,
+/// сколько смеси вносить каждую неделю и сколько всего удобрений купить, и позволяет
+/// сохранить напоминания о подкормках в календарь.
+#[function_component(SeasonPlanSection)]
+pub(crate) fn season_plan_section(props: &SeasonPlanProps) -> Html {
+    let planting_id = use_memo(make_element_id("planting-date"), ());
+    let bed_length_id = use_memo(make_element_id("bed-length"), ());
+    let feedings_id = use_memo(make_element_id("feedings"), ());
+    let doze_group = use_memo(make_element_id("plan-doze"), ());
+    let planting_ref = use_node_ref();
+
+    let planting = use_state(|| None::<CivilDate>);
+    let bed_length = use_state(|| DEFAULT_BED_LENGTH);
+    let feedings = use_state(|| DEFAULT_FEEDINGS);
+    // Почва, для которой берутся дозы по Угаровой, или `None` для дозы по Миттлайдеру
+    let soil = use_state(|| None::<usize>);
+
+    let doze = match *soil {
+        Some(idx) => props.ground_dozes[idx].1,
+        None => ElemRange { from: props.mit_ground_doze, to: props.mit_ground_doze },
+    };
+    let params = SeasonPlanParams {
+        planting: *planting,
+        bed_length: *bed_length,
+        feedings: *feedings,
+        doze,
+    };
+    let plan = SeasonPlan::new(&props.query, &props.exact_weights, &params);
+    // Ссылка на календарь собирается при отрисовке, тогда же он и выгружается
+    let exported_at = UtcDateTime::from_unix_seconds((js_sys::Date::now() / 1000.0) as i64);
+    let calendar = plan.to_icalendar(&props.title, exported_at);
+
+    let on_planting_change = {
+        let planting = planting.clone();
+        let planting_ref = planting_ref.clone();
+        Callback::from(move |_| {
+            if let Some(input) = planting_ref.cast::<HtmlInputElement>() {
+                planting.set(CivilDate::parse(&input.value()));
+            }
+        })
+    };
+    let on_bed_length_change = {
+        let bed_length = bed_length.clone();
+        Callback::from(move |value: Option<f64>| {
+            if let Some(value) = value.filter(|value| *value > 0.0) {
+                bed_length.set(value);
+            }
+        })
+    };
+    let on_feedings_change = {
+        let feedings = feedings.clone();
+        Callback::from(move |value: Option<f64>| {
+            if let Some(value) = value {
+                feedings.set(value.round() as usize);
+            }
+        })
+    };
+
+    let doze_option = |option: Option<usize>, title: &str| -> Html {
+        let input_id = format!("{}-{:?}", doze_group, option);
+        let onchange = {
+            let soil = soil.clone();
+            Callback::from(move |_| soil.set(option))
+        };
+        html! {
+            <div class="form-check form-check-inline">
+                <input type="radio" class="form-check-input"
+                    name={(*doze_group).clone()}
+                    id={input_id.clone()}
+                    checked={*soil == option}
+                    {onchange} />
+                <label class="form-check-label" for={input_id}>{title.to_owned()}</label>
+            </div>
+        }
+    };
+
+    let feeding_rows = plan.feedings.iter().map(|feeding| {
+        html! {
+            <tr>
+                <td>{feeding.week}</td>
+                <td>{feeding.date.map(|date| date.to_string()).unwrap_or_default()}</td>
+                <td>{if feeding.establishment { "приживание" } else { "рост и плодоношение" }}</td>
+                <td>{FloatFormat::new(feeding.doze, 0)}{" г/м"}</td>
+                <td><strong>{FloatFormat::new(feeding.mass, 2)}{" кг"}</strong></td>
+            </tr>
+        }
+    });
+    let shopping_rows = plan.shopping_list.iter().map(|(name, mass)| {
+        html! {
+            <tr>
+                <td>{name}</td>
+                <td><strong>{FloatFormat::new(*mass, 2)}{" кг"}</strong></td>
+            </tr>
+        }
+    });
+
+    html! {
+        <>
+            <div class="row mb-3 d-print-none">
+                <div class="col-auto">
+                    <label for={(*planting_id).clone()} class="form-label">{"Дата посадки"}</label>
+                    <input type="date" class="form-control"
+                        id={(*planting_id).clone()}
+                        ref={planting_ref}
+                        onchange={on_planting_change} />
+                </div>
+                <div class="col-auto">
+                    <label for={(*bed_length_id).clone()} class="form-label">
+                        {"Длина гряд, погонных метров"}
+                    </label>
+                    <PositiveFloatInput
+                        size="5"
+                        id={(*bed_length_id).clone()}
+                        value={Some(*bed_length)}
+                        on_value_change={on_bed_length_change}
+                        required={true} />
+                </div>
+                <div class="col-auto">
+                    <label for={(*feedings_id).clone()} class="form-label">
+                        {"Еженедельных подкормок"}
+                    </label>
+                    <PositiveFloatInput
+                        size="3"
+                        id={(*feedings_id).clone()}
+                        value={Some(*feedings as f64)}
+                        on_value_change={on_feedings_change}
+                        enforce_min={Some(1.0)}
+                        required={true} />
+                </div>
+            </div>
+            <div class="mb-3 d-print-none">
+                <span class="me-3">{"Доза:"}</span>
+                {doze_option(None, "по Миттлайдеру")}
+                {for props.ground_dozes.iter().enumerate().map(|(idx, (ground_type, _))| {
+                    doze_option(Some(idx), &format!("по Угаровой: {}", ground_type.to_lowercase()))
+                })}
+            </div>
+            <p class="help">
+                {format!(
+                    "Первые {} подкормки, пока растения приживаются, смесь вносится \
+                     в минимальной дозе, а затем - в максимальной.",
+                    ESTABLISHMENT_FEEDINGS
+                )}
+            </p>
+            <table>
+                <thead>
+                    <tr>
+                        <th>{"Неделя"}</th>
+                        <th>{"Дата"}</th>
+                        <th>{"Период"}</th>
+                        <th>{"Доза"}</th>
+                        <th>{"Смеси на все гряды"}</th>
+                    </tr>
+                </thead>
+                <tbody>{for feeding_rows}</tbody>
+            </table>
+
+            <h3>
+                {"Что купить на сезон"}<br />
+                <small class="text-muted">
+                    {"(всего "}{FloatFormat::new(plan.total_mass, 2)}{" кг смеси)"}
+                </small>
+            </h3>
+            <table>
+                <tbody>{for shopping_rows}</tbody>
+            </table>
+
+            <p class="d-print-none mt-3">
+                if let Some(calendar) = calendar {
+                    <a class="btn btn-outline-secondary" download="feeding-plan.ics"
+                        href={calendar_data_url(&calendar)}>
+                        {"Сохранить напоминания в календарь"}
+                    </a>
+                } else {
+                    <small class="text-muted">
+                        {"Укажите дату посадки, чтобы сохранить напоминания о подкормках \
+                          в календарь"}
+                    </small>
+                }
+            </p>
+        </>
+    }
+}
@@ -0,0 +1,119 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::calculator::modified_simplex::SimplexError;
+use crate::calculator::query::MixtureQuery;
+use crate::calculator::sensitivity::{analyze_sensitivity, ParamRange, SensitivityParam};
+use crate::ui_components::html_chunks::{ratio_title, NutrientTitle};
+use crate::yew_utils::FloatFormat;
+
+#[derive(PartialEq, Properties)]
+pub(crate) struct SensitivityReportProps {
+    pub query: Rc<MixtureQuery>,
+}
+
+fn render_range(range: &ParamRange, unit: &'static str) -> Html {
+    html! {
+        <>
+            {"от "}{FloatFormat::new(range.from, 2)}{unit}
+            if range.to.is_finite() {
+                {" до "}{FloatFormat::new(range.to, 2)}{unit}
+            } else {
+                {" и выше"}
+            }
+        </>
+    }
+}
+
+fn render_param_row(query: &MixtureQuery, range: &ParamRange) -> Html {
+    let (title, unit) = match range.param {
+        SensitivityParam::Content(fert_idx, element) => (
+            html! {
+                <>
+                    {&query.fertilizers[fert_idx].name}{", "}
+                    <NutrientTitle {element} />
+                </>
+            },
+            "%",
+        ),
+        SensitivityParam::RatioFrom(range_name) => (
+            html! {<>{"Нижняя граница "}{ratio_title(range_name)}</>},
+            "",
+        ),
+        SensitivityParam::RatioTo(range_name) => (
+            html! {<>{"Верхняя граница "}{ratio_title(range_name)}</>},
+            "",
+        ),
+    };
+    html! {
+        <tr>
+            <td>{title}</td>
+            <td>{FloatFormat::new(range.value, 3)}{unit}</td>
+            <td>{render_range(range, unit)}</td>
+        </tr>
+    }
+}
+
+/// Анализ чувствительности: показывает, насколько может отличаться состав удобрений
+/// (и границы соотношений), чтобы рецепт смеси остался прежним. Таблица длинная и нужна
+/// не всем, поэтому строится только по просьбе пользователя.
+#[function_component(SensitivityReport)]
+pub(crate) fn sensitivity_report(
+    SensitivityReportProps { query }: &SensitivityReportProps,
+) -> Html {
+    let report = use_state(|| None::<Result<Vec<ParamRange>, SimplexError>>);
+
+    let on_analyze_click = {
+        let report = report.clone();
+        let query = query.clone();
+        Callback::from(move |_| report.set(Some(analyze_sensitivity(&query))))
+    };
+
+    html! {
+        <div class="d-print-none">
+            <h3>{"Насколько точно нужно знать состав удобрений"}</h3>
+            {match &*report {
+                None => html! {
+                    <>
+                        <p class="help">
+                            {"Состав удобрения на упаковке часто отличается от табличного. \
+                              Можно проверить, в каких пределах он может меняться, \
+                              чтобы смесь по-прежнему составлялась из тех же удобрений."}
+                        </p>
+                        <p>
+                            <button class="btn btn-outline-secondary" onclick={on_analyze_click}>
+                                {"Проверить устойчивость рецепта"}
+                            </button>
+                        </p>
+                    </>
+                },
+                Some(Ok(ranges)) => html! {
+                    <>
+                        <p class="help">
+                            {"В указанных пределах (каждый параметр по отдельности) смесь \
+                              составляется из тех же удобрений, меняются лишь их количества. \
+                              Если ваше удобрение выходит за эти пределы, \
+                              рассчитайте смесь заново с его настоящим составом."}
+                        </p>
+                        <table class="table sensitivity">
+                            <thead>
+                                <tr>
+                                    <th>{"Параметр"}</th>
+                                    <th>{"Сейчас"}</th>
+                                    <th>{"Рецепт не изменится"}</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {for ranges.iter().map(|range| render_param_row(query, range))}
+                            </tbody>
+                        </table>
+                    </>
+                },
+                Some(Err(_)) => html! {
+                    <p class="warning">{"Проверить устойчивость этого рецепта не удалось"}</p>
+                },
+            }}
+        </div>
+    }
+}
@@ -0,0 +1,139 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::calculator::cardinality::find_simplest_solutions;
+use crate::calculator::consts::MIN_INGREDIENTS;
+use crate::calculator::formatted_solution::FormattedSolution;
+use crate::calculator::query::{MixtureObjective, MixtureQuery};
+use crate::calculator::{ElemName, Fertilizer};
+use crate::yew_utils::FloatFormat;
+
+#[derive(PartialEq, Properties)]
+pub(crate) struct SimplerMixturesProps {
+    pub query: Rc<MixtureQuery>,
+    /// Веса удобрений (кг) смеси, найденной без дополнительных ограничений
+    /// на число удобрений, в порядке удобрений запроса
+    pub exact_weights: Vec<f64>,
+}
+
+fn fertilizers_word(count: usize) -> &'static str {
+    match count {
+        2..=4 => " удобрения",
+        _ => " удобрений",
+    }
+}
+
+/// Значение целевой функции в пересчёте на единицу фосфора: при одинаковой массе смеси
+/// сравнивать нужно именно его, так как в разных смесях разное количество питания
+fn objective_per_phosphorus<'a>(
+    objective: MixtureObjective,
+    components: impl Iterator<Item = (&'a Fertilizer, &'a f64)>,
+) -> f64 {
+    let (value, phosphorus) =
+        components.fold((0.0, 0.0), |(value, phosphorus), (fertilizer, weight)| {
+            (
+                value + objective.coefficient(fertilizer) * weight,
+                phosphorus + fertilizer[ElemName::Phosphorus] * weight,
+            )
+        });
+    value / phosphorus
+}
+
+fn worsening_title(objective: MixtureObjective) -> &'static str {
+    match objective {
+        MixtureObjective::Mass => "вносить придётся больше на ",
+        MixtureObjective::Cost => "удобрение обойдётся дороже на ",
+        MixtureObjective::Ballast => "балласта больше на ",
+    }
+}
+
+fn render_simpler_mixture(
+    objective: MixtureObjective,
+    best_value: f64,
+    count: usize,
+    solution: &FormattedSolution,
+) -> Html {
+    let value = objective_per_phosphorus(
+        objective,
+        solution
+            .components
+            .iter()
+            .map(|(fertilizer, weight)| (fertilizer, weight)),
+    );
+    let worsening = (value / best_value - 1.0) * 100.0;
+    let components = solution
+        .components
+        .iter()
+        .filter(|(_, weight)| *weight > 0.0)
+        .map(|(fertilizer, weight)| {
+            html! {
+                <li>{&fertilizer.name}{" "}{FloatFormat::new(*weight, 3)}{" кг"}</li>
+            }
+        });
+    html! {
+        <li>
+            <strong>{count}{fertilizers_word(count)}{": "}</strong>
+            {worsening_title(objective)}{FloatFormat::new(worsening.max(0.0), 1)}{"%"}
+            <ul>{for components}</ul>
+        </li>
+    }
+}
+
+/// Смеси из меньшего числа удобрений, чем в найденной: иногда проще купить на пару мешков
+/// меньше, пусть смесь и получится немного хуже. Поиск перебирает наборы удобрений,
+/// поэтому выполняется только по просьбе пользователя.
+#[function_component(SimplerMixtures)]
+pub(crate) fn simpler_mixtures(
+    SimplerMixturesProps { query, exact_weights }: &SimplerMixturesProps,
+) -> Html {
+    let mixtures = use_state(|| None::<Vec<(usize, FormattedSolution)>>);
+
+    let used_count = exact_weights.iter().filter(|weight| **weight > 0.0).count();
+    let max_count = used_count.saturating_sub(1);
+    let on_search_click = {
+        let mixtures = mixtures.clone();
+        let query = query.clone();
+        Callback::from(move |_| {
+            mixtures.set(Some(find_simplest_solutions(&query, max_count)));
+        })
+    };
+
+    if max_count < MIN_INGREDIENTS {
+        return html! {};
+    }
+
+    let best_value = objective_per_phosphorus(
+        query.objective,
+        query.fertilizers.iter().zip(exact_weights.iter()),
+    );
+    html! {
+        <div class="d-print-none">
+            {match &*mixtures {
+                None => html! {
+                    <p>
+                        <button class="btn btn-outline-secondary" onclick={on_search_click}>
+                            {"Подобрать смеси из меньшего числа удобрений"}
+                        </button>
+                    </p>
+                },
+                Some(mixtures) if mixtures.is_empty() => html! {
+                    <p class="warning">
+                        {"Из меньшего числа удобрений смесь с заданными соотношениями \
+                          составить не удаётся"}
+                    </p>
+                },
+                Some(mixtures) => html! {
+                    <>
+                        <p>{"Смеси из меньшего числа удобрений (той же массы):"}</p>
+                        <ul>
+                            {for mixtures.iter().map(|(count, mixture)| {
+                                render_simpler_mixture(query.objective, best_value, *count, mixture)
+                            })}
+                        </ul>
+                    </>
+                },
+            }}
+        </div>
+    }
+}
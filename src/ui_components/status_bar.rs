@@ -4,12 +4,12 @@ use stylist::yew::styled_component;
 use yew::{classes, html, Callback, Html, Properties};
 
 use crate::{
-    calculator::{Deficites, ElemName},
+    calculator::{consts::PERMANENT_FERTILIZERS, Deficites, ElemName},
     ui_components::html_chunks::CHECK_MARK,
-    yew_utils::include_css,
+    yew_utils::{include_css, FloatFormat},
 };
 
-use super::html_chunks::nutrient_css_class;
+use super::html_chunks::{nutrient_css_class, nutrient_name};
 
 #[derive(PartialEq, Properties)]
 pub(crate) struct StatusBarProps {
@@ -22,7 +22,7 @@ pub(crate) struct StatusBarProps {
 /// в ходе выбора удобрений для смеси, и позволяет перейти к просмотру результата.
 #[styled_component]
 pub(crate) fn StatusBar(props: &StatusBarProps) -> Html {
-    const LACK: &str = "—";
+    const MINUS: &str = "−";
 
     let stylesheet = include_css!("status_bar.css");
 
@@ -33,15 +33,34 @@ pub(crate) fn StatusBar(props: &StatusBarProps) -> Html {
         })
     };
 
-    let nutrient_deficite_column = |element: ElemName, name: &str| -> Html {
-        let deficite = props.deficites[element];
+    let nutrient_deficite_column = |element: ElemName| -> Html {
+        let deficite = &props.deficites[element];
         let nutrient_class = nutrient_css_class(element);
+        let title = deficite.as_ref().map(|deficite| {
+            let mut title = format!(
+                "Не хватает {} кг ({}%)",
+                FloatFormat::new(deficite.amount, 3),
+                FloatFormat::new(deficite.percent, 0)
+            );
+            if let Some((perm_idx, _)) = deficite.remedies.first() {
+                title.push_str(". Лучше всего добавить: ");
+                title.push_str(&PERMANENT_FERTILIZERS[*perm_idx].name);
+            }
+            title
+        });
         html! {
             <div class={classes!("col", "gx-2", "gx-sm-4", "py-2",
-                                nutrient_class, deficite.then_some("deficite"))}>
-                <span>if deficite {{LACK}} else {{CHECK_MARK} }</span>
+                                nutrient_class, deficite.is_some().then_some("deficite"))}
+                {title}>
+                <span>
+                    if let Some(deficite) = deficite {
+                        {MINUS}{FloatFormat::new(deficite.percent, 0)}{"%"}
+                    } else {
+                        {CHECK_MARK}
+                    }
+                </span>
                 {" "}
-                {name}
+                {nutrient_name(element)}
             </div>
         }
     };
@@ -51,10 +70,13 @@ pub(crate) fn StatusBar(props: &StatusBarProps) -> Html {
             <div class="container-fluid">
                 <div class="row mx-auto status-bar">
                     <div class="col d-none d-md-block">{"Баланс"}</div>
-                    {nutrient_deficite_column(ElemName::Nitrogen, "азот")}
-                    {nutrient_deficite_column(ElemName::Phosphorus, "фосфор")}
-                    {nutrient_deficite_column(ElemName::Potassium, "калий")}
-                    {nutrient_deficite_column(ElemName::Magnesium, "магний")}
+                    // Вторичных элементов может не хватать, только если их соотношение задано
+                    {for ElemName::ALL
+                        .into_iter()
+                        .filter(|element| {
+                            ElemName::MACRO.contains(element) || props.deficites[*element].is_some()
+                        })
+                        .map(nutrient_deficite_column)}
                     <div class="col gx-sm-4 gx-1">
                         <button type="submit" class="btn btn-primary"
                             disabled={props.deficites.any() || !props.state_is_valid}
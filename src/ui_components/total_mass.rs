@@ -1,15 +1,67 @@
-use crate::{ui_components::positive_float_input::PositiveFloatInput, yew_utils::make_element_id};
+use crate::{
+    calculator::query::ExistingMixture,
+    calculator::ElemName,
+    ui_components::html_chunks::{capitalized, nutrient_input_css_class, nutrient_name, MDASH},
+    ui_components::positive_float_input::PositiveFloatInput,
+    yew_utils::make_element_id,
+};
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
 pub(crate) struct TotalMassInputProps {
     pub value: f64,
     pub on_change: Callback<f64>,
+    /// Уже приготовленная часть смеси
+    pub existing: Option<ExistingMixture>,
+    pub on_existing_change: Callback<Option<ExistingMixture>>,
+}
+
+/// Поле для ввода массы уже приготовленной части смеси (`element == None`)
+/// или содержания в ней одного из макроэлементов
+fn existing_input_column(
+    existing: ExistingMixture,
+    on_change: &Callback<Option<ExistingMixture>>,
+    element: Option<ElemName>,
+) -> Html {
+    let (label, value) = match element {
+        None => ("Уже есть, кг".to_string(), existing.mass),
+        Some(element) => (
+            format!("{}, %", capitalized(nutrient_name(element))),
+            existing.content[element],
+        ),
+    };
+    let on_value_change = {
+        let on_change = on_change.clone();
+        Callback::from(move |new_value: Option<f64>| {
+            let new_value = new_value.unwrap_or_default();
+            let mut existing = existing;
+            match element {
+                None => existing.mass = new_value,
+                Some(element) => existing.content[element] = new_value,
+            }
+            on_change.emit(Some(existing));
+        })
+    };
+    html! {
+        <div class="col">
+            <label class="form-label">{label}</label>
+            <PositiveFloatInput size="6"
+                class={element.map(nutrient_input_css_class)}
+                value={Some(value)}
+                {on_value_change}
+                required={true}
+                enforce_min={Some(0.0)}
+                enforce_max={element.map(|_| 100.0)} />
+        </div>
+    }
 }
 
 #[function_component(TotalMassInput)]
 pub(crate) fn total_mass_input(props: &TotalMassInputProps) -> Html {
     let input_id = use_memo(make_element_id("weight-total"), ());
+    let existing_checkbox_id = use_memo(make_element_id("existing-mixture"), ());
+    let existing_checkbox_ref = use_node_ref();
     let on_input_change = use_callback(
         |value: Option<f64>, on_change| {
             if let Some(value) = value {
@@ -20,10 +72,22 @@ pub(crate) fn total_mass_input(props: &TotalMassInputProps) -> Html {
         },
         props.on_change.clone(),
     );
+    let on_existing_toggle = {
+        let existing_checkbox_ref = existing_checkbox_ref.clone();
+        let on_existing_change = props.on_existing_change.clone();
+        Callback::from(move |_| {
+            if let Some(input) = existing_checkbox_ref.cast::<HtmlInputElement>() {
+                on_existing_change.emit(input.checked().then_some(ExistingMixture::default()));
+            }
+        })
+    };
+    let existing_too_heavy = props
+        .existing
+        .is_some_and(|existing| existing.mass > props.value);
     html! {
         <>
         <h2>{"Шаг 3. Сколько смеси вам нужно?"}</h2>
-        <div class="row mx-auto mb-5">
+        <div class="row mx-auto mb-2">
             <div class="col-auto g-0">
                 <label for="{&*input_id}" class="col-form-label">{"Мне нужно"}</label>
             </div>
@@ -40,6 +104,34 @@ pub(crate) fn total_mass_input(props: &TotalMassInputProps) -> Html {
                 </div>
             </div>
         </div>
+        <div class="form-check mb-2">
+            <input type="checkbox" class="form-check-input"
+                id={(*existing_checkbox_id).clone()}
+                ref={existing_checkbox_ref}
+                checked={props.existing.is_some()}
+                onchange={on_existing_toggle} />
+            <label class="form-check-label" for={(*existing_checkbox_id).clone()}>
+                {"Часть смеси уже приготовлена, её нужно дополнить"}
+            </label>
+        </div>
+        if let Some(existing) = props.existing {
+            <div class="row mx-auto mb-2">
+                {existing_input_column(existing, &props.on_existing_change, None)}
+                {for ElemName::MACRO.into_iter().map(|element| {
+                    existing_input_column(existing, &props.on_existing_change, Some(element))
+                })}
+            </div>
+            if existing_too_heavy {
+                <p class="text-danger">
+                    {"Уже приготовленной смеси больше, чем нужно всего."}
+                </p>
+            } else {
+                <p class="help">
+                    {"Масса выше "}{MDASH}{" это масса всей смеси вместе с уже приготовленной частью."}
+                </p>
+            }
+        }
+        <div class="mb-5"></div>
         </>
     }
 }
@@ -0,0 +1,37 @@
+use yew::prelude::*;
+
+use crate::calculator::units::NutrientUnits;
+use crate::calculator::ElemName;
+use crate::ui_components::html_chunks::NutrientTitle;
+
+#[derive(Properties, PartialEq)]
+pub(crate) struct UnitsSwitchProps {
+    pub value: NutrientUnits,
+    pub on_change: Callback<NutrientUnits>,
+}
+
+/// Переключатель единиц содержания элементов: оксиды, как на отечественных упаковках,
+/// или сами элементы, как на многих иностранных этикетках и в лабораторных анализах.
+#[function_component(UnitsSwitch)]
+pub(crate) fn units_switch(props: &UnitsSwitchProps) -> Html {
+    let units_button = |units: NutrientUnits, title: &'static str| -> Html {
+        let onclick = {
+            let on_change = props.on_change.clone();
+            Callback::from(move |_| on_change.emit(units))
+        };
+        html! {
+            <button type="button" {title} {onclick}
+                class={classes!("btn", "btn-sm", "btn-outline-secondary",
+                                (props.value == units).then_some("active"))}>
+                <NutrientTitle element={ElemName::Phosphorus} {units} />{", "}
+                <NutrientTitle element={ElemName::Potassium} {units} />
+            </button>
+        }
+    };
+    html! {
+        <div class="btn-group" role="group">
+            {units_button(NutrientUnits::Oxide, "В пересчёте на оксиды")}
+            {units_button(NutrientUnits::Elemental, "В пересчёте на сами элементы")}
+        </div>
+    }
+}